@@ -5,7 +5,7 @@ import {SP1Verifier} from "./SP1Verifier.sol";
 
 struct KeyEncOut {
     bytes32 keyHash;
-    bytes keyCipher;
+    bytes[] keyCiphers;
 }
 
 /// @title KeyEnc.
@@ -21,17 +21,22 @@ contract KeyEnc is SP1Verifier {
     /// @param proof The encoded proof.
     /// @param publicValues The encoded public values.
     /// @param keyHashCommited The commited key hash.
-    /// @return keyCipher The shared key cipher.
+    /// @return keyCiphers The shared key cipher of each recipient.
     function verifyKeyEncProof(
         bytes memory proof,
         bytes memory publicValues,
         bytes32 keyHashCommited
-    ) public view returns (bytes memory) {
-        this.verifyProof(ecdhProgramVkey, publicValues, proof);
+    ) public view returns (bytes[] memory) {
+        _verifyEcdhProof(proof, publicValues);
         (KeyEncOut memory out) = abi.decode(publicValues, (KeyEncOut));
         if (keyHashCommited != out.keyHash) {
             revert("KeyEnc: preimage does not match with previous commited key.");
         }
-        return out.keyCipher;
+        return out.keyCiphers;
+    }
+
+    /// @notice Reverts unless `proof` is an SP1 proof of the ecdh program committing to `publicValues`.
+    function _verifyEcdhProof(bytes memory proof, bytes memory publicValues) internal view virtual {
+        this.verifyProof(ecdhProgramVkey, publicValues, proof);
     }
 }
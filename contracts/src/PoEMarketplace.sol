@@ -18,8 +18,8 @@ contract ProofOfExploitMarketplace is ERC721, KeyEnc {
         address purchasedBy; // will be the address of the buyer of the token
         uint256 price;  // price set by the creator
         bytes32 keyHash; // the hash of the key of the encryption of the proof of the exploit
-        bytes sharedKeyCipher; // the encrypted key of the exploit, released by the redeeming process
-        bool redeemed; // true if this exploit has been redeemed and the sharedKeyCipher is available
+        bytes[] sharedKeyCiphers; // the encrypted key of the exploit for each recipient, released by the redeeming process
+        bool redeemed; // true if this exploit has been redeemed and the sharedKeyCiphers are available
     }
 
     /// map of all exploits (which are also tokens)
@@ -52,15 +52,12 @@ contract ProofOfExploitMarketplace is ERC721, KeyEnc {
         uint256 id = exploitCount;
         exploitCount++;
 
-        exploits[id] = Exploit({
-            description: description,
-            creator: msg.sender,
-            purchasedBy: address(0),
-            price: price,
-            keyHash: keyHash,
-            sharedKeyCipher: new bytes(0),
-            redeemed: false
-        });
+        // Set field by field: a memory struct holding bytes[] can't be copied to storage.
+        Exploit storage exploit = exploits[id];
+        exploit.description = description;
+        exploit.creator = msg.sender;
+        exploit.price = price;
+        exploit.keyHash = keyHash;
 
         _mint(msg.sender, id);
 
@@ -100,10 +97,12 @@ contract ProofOfExploitMarketplace is ERC721, KeyEnc {
             @param publicValues The encoded public values.
             @param keyHashCommited The commited key hash.
         */
-        bytes memory sharedKeyCipher = verifyKeyEncProof(proof, publicValues, exploit.keyHash);
+        bytes[] memory sharedKeyCiphers = verifyKeyEncProof(proof, publicValues, exploit.keyHash);
 
         exploit.redeemed = true;
-        exploit.sharedKeyCipher = sharedKeyCipher;
+        for (uint256 i = 0; i < sharedKeyCiphers.length; i++) {
+            exploit.sharedKeyCiphers.push(sharedKeyCiphers[i]);
+        }
 
         address buyer = ownerOf(tokenId);
         require(exploit.purchasedBy != address(0), "No buyer for this token");
@@ -116,14 +115,14 @@ contract ProofOfExploitMarketplace is ERC721, KeyEnc {
     /**
      * @notice Allows a token holder to retrieve the encrypted key for the exploit.
      * @param tokenId The ID of the token (exploit).
-     * @return The encrypted key.
+     * @return The encrypted key, once per recipient.
      */
-    function getExploitKey(uint256 tokenId) external view returns (bytes memory) {
+    function getExploitKey(uint256 tokenId) external view returns (bytes[] memory) {
         require(ownerOf(tokenId) == msg.sender, "Only the owner can get the key");
 
         Exploit storage exploit = exploits[tokenId];
         require(exploit.redeemed, "Exploit not redeemed yet");
 
-        return exploit.sharedKeyCipher;
+        return exploit.sharedKeyCiphers;
     }
 }
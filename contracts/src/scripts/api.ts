@@ -63,10 +63,20 @@ export const purchaseToken = async (tokenId:number) => await writeContract('purc
 // The White Hat Hacker uses the vendor's public key derived from the purchaseToken transaction to compute the proofs and receive the payment
 export const redeem = async (tokenId:number, key:bigint) => await writeContract('redeemExploit', [tokenId, key])
 
-// Finally, the vendor can retrieve the shared key from the exploit token
-export const retrieveKey = async (tokenId:number) => publicClient.readContract({
+// Finally, the vendors can retrieve the shared key ciphers from the exploit token, one per recipient
+// in the order they were given to the ecdh prover
+export const retrieveKey = async (tokenId:number) => await publicClient.readContract({
     address: CONTRACT as `0x${string}`,
     abi: abi,
     functionName: 'getExploitKey',
     args: [tokenId]
-})
\ No newline at end of file
+}) as `0x${string}`[]
+
+// The shared key cipher made for the recipient at `recipient` in that order
+export const retrieveKeyFor = async (tokenId:number, recipient:number) => {
+    const keyCiphers = await retrieveKey(tokenId)
+    if (recipient >= keyCiphers.length) {
+        throw new Error(`exploit ${tokenId} has ${keyCiphers.length} key ciphers, none for recipient ${recipient}`)
+    }
+    return keyCiphers[recipient]
+}
\ No newline at end of file
@@ -0,0 +1,58 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.20;
+
+import { ProofOfExploitMarketplace } from "../src/PoEMarketplace.sol";
+import { KeyEncOut } from "../src/KeyEnc.sol";
+
+/// @dev The marketplace with the SP1 proof check stubbed out, so its public values can be made up.
+contract UnverifiedMarketplace is ProofOfExploitMarketplace {
+    constructor() ProofOfExploitMarketplace(bytes32(0)) {}
+
+    function _verifyEcdhProof(bytes memory, bytes memory) internal pure override {}
+}
+
+/// @dev Posts and redeems the exploit, the marketplace only lets its creator do the latter.
+contract Creator {
+    function post(ProofOfExploitMarketplace market, uint256 price, bytes32 keyHash) external returns (uint256) {
+        return market.postExploit("reentrancy in withdraw", price, keyHash);
+    }
+
+    function redeem(ProofOfExploitMarketplace market, uint256 id, bytes memory publicValues) external {
+        market.redeemExploit(id, "", publicValues);
+    }
+
+    receive() external payable {}
+}
+
+/// @dev Buys the exploit token, the marketplace only hands the key ciphers to its owner.
+contract Buyer {
+    function purchase(ProofOfExploitMarketplace market, uint256 id) external payable {
+        market.purchaseToken{ value: msg.value }(id);
+    }
+
+    function keyCiphers(ProofOfExploitMarketplace market, uint256 id) external view returns (bytes[] memory) {
+        return market.getExploitKey(id);
+    }
+}
+
+contract PoEMarketplaceTest {
+    function testRedeemStoresTheKeyCipherOfEveryRecipient() public {
+        UnverifiedMarketplace market = new UnverifiedMarketplace();
+        Creator creator = new Creator();
+        Buyer buyer = new Buyer();
+        bytes32 keyHash = keccak256("key");
+        bytes[] memory keyCiphers = new bytes[](2);
+        keyCiphers[0] = hex"0102";
+        keyCiphers[1] = hex"030405";
+
+        uint256 id = creator.post(market, 1 ether, keyHash);
+        buyer.purchase{ value: 1 ether }(market, id);
+        creator.redeem(market, id, abi.encode(KeyEncOut(keyHash, keyCiphers)));
+
+        bytes[] memory stored = buyer.keyCiphers(market, id);
+        require(stored.length == 2, "expected a key cipher per recipient");
+        require(keccak256(stored[0]) == keccak256(keyCiphers[0]), "first key cipher differs");
+        require(keccak256(stored[1]) == keccak256(keyCiphers[1]), "second key cipher differs");
+        require(address(creator).balance == 1 ether, "creator wasn't paid");
+    }
+}
@@ -11,7 +11,11 @@ fn main() {
     sp1_sdk::utils::setup_logger();
 
     tracing::info!("exporting groth16 verifier");
-    let contracts_src_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../contracts/src");
+    let contracts_src_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("..")
+        .join("..")
+        .join("contracts")
+        .join("src");
     sp1_sdk::artifacts::export_solidity_groth16_verifier(contracts_src_dir)
         .expect("failed to export verifier");
 }
@@ -0,0 +1,118 @@
+//! Recovers the ChaCha key the vendor was sent, from their entry of `keyCiphers` in an ecdh
+//! fixture.
+//!
+//! You can run this script using the following command:
+//! ```shell
+//! cargo run --package ecdh-script --bin decrypt --release -- --vendor-sk <hex>
+//! ```
+
+use std::{fs, path::PathBuf, process::ExitCode};
+
+use clap::Parser;
+use ecdh_script::{
+    default_fixtures_dir,
+    keys::{decrypt_key_cipher, parse_fixed_hex, KeyError, MismatchError},
+    SP1EcdhProofFixture,
+};
+
+/// The arguments for the decrypt command.
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct DecryptArgs {
+    #[clap(long, help = "hex private key of the vendor")]
+    vendor_sk: String,
+
+    #[clap(
+        long,
+        help = "hex public key of the prover, derived from the fixture if omitted"
+    )]
+    local_pk: Option<String>,
+
+    #[clap(long, help = "defaults to the fixture written by the prove command")]
+    fixture: Option<PathBuf>,
+}
+
+/// Errors that can occur while decrypting a key cipher.
+#[derive(Debug, thiserror::Error)]
+enum DecryptError {
+    #[error("failed to read {}: {source}", .path.display())]
+    ReadFixture {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("failed to parse fixture: {0}")]
+    ParseFixture(serde_json::Error),
+    #[error("fixture field {field} is malformed")]
+    InvalidField { field: &'static str },
+    #[error("the vendor key is not one of the fixture's vendorPks")]
+    UnknownVendor,
+    #[error(transparent)]
+    Key(#[from] KeyError),
+    #[error(transparent)]
+    Mismatch(#[from] MismatchError),
+}
+
+fn decrypt(args: &DecryptArgs) -> Result<[u8; 32], DecryptError> {
+    let fixture_path = args
+        .fixture
+        .clone()
+        .unwrap_or_else(|| default_fixtures_dir().join("ecdh_fixture.json"));
+    let fixture =
+        fs::read_to_string(&fixture_path).map_err(|source| DecryptError::ReadFixture {
+            path: fixture_path,
+            source,
+        })?;
+    let fixture: SP1EcdhProofFixture =
+        serde_json::from_str(&fixture).map_err(DecryptError::ParseFixture)?;
+
+    // The fixture records the curve and KDF the prover used, so the keys are checked against that.
+    let curve = fixture.curve;
+    let vendor_sk = curve.parse_secret_key("vendor-sk", &args.vendor_sk)?;
+    let local_pk = match &args.local_pk {
+        Some(pk) => curve.parse_public_key("local-pk", pk)?,
+        None => fixture.local_public_key()?,
+    };
+
+    // The key was encrypted to every vendor in turn, so find the cipher meant for this one.
+    let vendor_pk = curve.public_key("vendor-sk", &vendor_sk)?;
+    let recipient = fixture
+        .vendor_pks
+        .iter()
+        .position(|pk| {
+            hex::decode(pk.strip_prefix("0x").unwrap_or(pk)).is_ok_and(|pk| pk == vendor_pk)
+        })
+        .ok_or(DecryptError::UnknownVendor)?;
+    let key_cipher = fixture
+        .key_ciphers
+        .get(recipient)
+        .and_then(|cipher| hex::decode(cipher.strip_prefix("0x").unwrap_or(cipher)).ok())
+        .ok_or(DecryptError::InvalidField {
+            field: "keyCiphers",
+        })?;
+    let nonce = parse_fixed_hex("nonce", &fixture.nonce)?;
+    let key_hash = parse_fixed_hex("keyHash", &fixture.key_hash)?;
+
+    let shared = curve.shared_secret(&vendor_sk, &local_pk)?;
+    Ok(decrypt_key_cipher(
+        fixture.kdf,
+        &shared,
+        &nonce,
+        &key_cipher,
+        key_hash,
+    )?)
+}
+
+fn main() -> ExitCode {
+    let args = DecryptArgs::parse();
+
+    match decrypt(&args) {
+        Ok(key) => {
+            println!("Key: {}", hex::encode(key));
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("Error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -6,86 +6,161 @@
 //! RUST_LOG=info cargo run --package fibonacci-script --bin prove --release
 //! ```
 
-use std::{fs, io::Read, path::PathBuf};
+use std::path::PathBuf;
 
-use alloy_sol_types::{sol, SolType};
-use clap::Parser;
+use alloy_sol_types::SolType;
+use clap::{Parser, ValueEnum};
+use ecdh_script::{
+    default_fixtures_dir,
+    keys::{
+        encrypt_key_to, parse_fixed_hex, read_key_file, resolve_key_file, Curve, Kdf, KeyError,
+        KeyFileError, MismatchError, DEFAULT_KEY_FILE,
+    },
+    KeyEncOut, SP1EcdhProofFixture,
+};
 use rand::Rng;
-use serde::{Deserialize, Serialize};
-use sp1_sdk::{Groth16Proof, HashableKey, ProverClient, SP1Stdin};
+use sp1_sdk::{HashableKey, ProverClient, SP1PublicValues, SP1Stdin};
 
 /// The ELF (executable and linkable format) file for the Succinct RISC-V zkVM.
 ///
-/// This file is generated by running `cargo prove build` inside the `program` directory.
-pub const ECDH_ELF: &[u8] = include_bytes!("../../../ecdh/elf/riscv32im-succinct-zkvm-elf");
+/// The build script compiles it from `ecdh`. It isn't checked in: a copy left over from an older
+/// program would prove with outdated inputs and public values.
+pub const ECDH_ELF: &[u8] = include_bytes!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/../ecdh/elf/riscv32im-succinct-zkvm-elf"
+));
 
 /// The arguments for the prove command.
 #[derive(Parser, Debug)]
 #[clap(author, version, about, long_about = None)]
 struct ProveArgs {
-    // #[clap(long)]
-    // local_sk: String,
+    #[clap(long, value_enum, default_value_t = Curve::K256, help = "curve of both keys")]
+    curve: Curve,
 
-    // #[clap(long)]
-    // vendor_pk: String,
+    #[clap(long, value_enum, default_value_t = Kdf::Raw, help = "how the key cipher's key is derived from the shared secret")]
+    kdf: Kdf,
+
+    #[clap(long, help = "hex private key, random if omitted")]
+    local_sk: Option<String>,
+
+    #[clap(
+        long,
+        help = "hex public key of a vendor to encrypt the key to, repeat for several; random if omitted"
+    )]
+    vendor_pk: Vec<String>,
+
+    /// The ChaCha key written by the zkpoex script; relative paths are resolved against this crate.
+    #[clap(long, default_value = DEFAULT_KEY_FILE)]
+    key_file: PathBuf,
+
+    /// The ChaCha key as hex, e.g. the one passed to the zkpoex script's `--enc-key`, instead of
+    /// reading it from `--key-file`.
+    #[clap(long, conflicts_with = "key_file")]
+    enc_key: Option<String>,
+
+    #[clap(long, value_enum, default_value_t = ProofMode::Groth16)]
+    proof_mode: ProofMode,
+
+    /// Where to write the fixture; relative paths are resolved against the current directory.
+    #[clap(long, default_value_os_t = default_fixtures_dir(), value_parser = resolve_dir)]
+    fixtures_dir: PathBuf,
+
+    /// Also write the hex local public key to this file, to hand to the vendors.
+    #[clap(long, value_parser = resolve_dir)]
+    public_key: Option<PathBuf>,
+}
+
+fn resolve_dir(s: &str) -> std::io::Result<PathBuf> {
+    Ok(std::env::current_dir()?.join(s))
 }
 
-/// A fixture that can be used to test the verification of SP1 zkVM proofs inside Solidity.
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct SP1EcdhProofFixture {
-    local_sk: String,
-    vendor_pk: String,
-    vkey: String,
-    key_hash: String,
-    public_values: String,
-    proof: String,
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum ProofMode {
+    /// A compressed STARK, cheap to produce but not verifiable on-chain.
+    Compressed,
+    /// A Groth16 SNARK wrapping the STARK, verifiable on-chain.
+    Groth16,
+    /// A PLONK SNARK wrapping the STARK, verifiable on-chain.
+    Plonk,
 }
 
-sol! {
-    struct KeyEncOut {
-        bytes32 keyHash;
-        bytes keyCipher;
+/// Errors that can occur while producing an ecdh proof.
+#[derive(Debug, thiserror::Error)]
+enum ProveError {
+    #[error(transparent)]
+    Key(#[from] KeyError),
+    #[error(transparent)]
+    KeyFile(#[from] KeyFileError),
+    #[error("failed to generate proof: {0}")]
+    Proof(anyhow::Error),
+    #[error("failed to decode public values: {0}")]
+    PublicValues(alloy_sol_types::Error),
+    #[error(transparent)]
+    Mismatch(#[from] MismatchError),
+    #[error("failed to write {}: {source}", .path.display())]
+    Write {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+}
+
+impl ProveArgs {
+    /// The ChaCha key to encrypt to the vendors, from `--enc-key` if given or else the key file.
+    fn key(&self) -> Result<[u8; 32], ProveError> {
+        match &self.enc_key {
+            Some(key) => Ok(parse_fixed_hex("enc-key", key)?),
+            None => Ok(read_key_file(&resolve_key_file(&self.key_file))?),
+        }
+    }
+
+    /// The local secret key and the vendor public keys to run the exchange with, checked against
+    /// `--curve` so a bad key is caught before the zkVM runs.
+    fn keys(&self) -> Result<(Vec<u8>, Vec<Vec<u8>>), KeyError> {
+        let curve = self.curve;
+        let local_sk = match &self.local_sk {
+            Some(sk) => curve.parse_secret_key("local-sk", sk)?,
+            None => curve.random_secret_key(),
+        };
+
+        // Without a vendor, encrypt to a throwaway key pair so the flow can still be exercised.
+        let vendor_pks = if self.vendor_pk.is_empty() {
+            vec![curve.random_key_pair().1]
+        } else {
+            self.vendor_pk
+                .iter()
+                .map(|pk| curve.parse_public_key("vendor-pk", pk))
+                .collect::<Result<_, _>>()?
+        };
+
+        Ok((local_sk, vendor_pks))
     }
 }
 
-fn main() {
+fn main() -> Result<(), ProveError> {
     // Setup the logger.
     sp1_sdk::utils::setup_logger();
 
     // Parse the command line arguments.
     let args = ProveArgs::parse();
 
-    use static_dh_ecdh::ecdh::ecdh::{
-        FromBytes, KeyExchange, Pkk256, Skk256, ToBytes, ECDHNISTK256,
-    };
-
-    let local_sk = ECDHNISTK256::generate_private_key([12; 32])
-        .to_bytes()
-        .to_vec();
-
-    let vendor_sk = ECDHNISTK256::generate_private_key([13; 32]);
-    let vendor_pk = ECDHNISTK256::generate_public_key(&vendor_sk)
-        .to_bytes()
-        .to_vec();
+    let (local_sk, vendor_pks) = args.keys()?;
 
     let local_sk_hex = hex::encode(&local_sk);
-    let vendor_pk_hex = hex::encode(&vendor_pk);
+    // The vendors need it to derive the shared secret, and to encrypt back to us.
+    let local_pk_hex = hex::encode(args.curve.public_key("local-sk", &local_sk)?);
+    let vendor_pks_hex: Vec<String> = vendor_pks.iter().map(hex::encode).collect();
 
     println!("local sk: {}", local_sk_hex);
-    println!("vendor pk: {}", vendor_pk_hex);
-
-    // let local_sk = hex::decode(&args.local_sk).unwrap();
-    // let vendor_pk = hex::decode(&args.vendor_pk).unwrap();
+    println!("local pk: {}", local_pk_hex);
+    for vendor_pk in &vendor_pks_hex {
+        println!("vendor pk: {}", vendor_pk);
+    }
 
     let mut rng = rand::thread_rng();
 
     let nonce: [u8; 12] = rng.gen();
 
-    let key: [u8; 32] = fs::read("./data/zkpoex_enc_key")
-        .unwrap()
-        .try_into()
-        .unwrap();
+    let key = args.key()?;
 
     // Setup the prover client.
     let client = ProverClient::new();
@@ -95,29 +170,66 @@ fn main() {
 
     // Setup the inputs.;
     let mut stdin = SP1Stdin::new();
-    stdin.write(&(key, nonce, local_sk, vendor_pk));
+    stdin.write(&(
+        key,
+        nonce,
+        args.curve.id(),
+        args.kdf.id(),
+        local_sk.clone(),
+        vendor_pks.clone(),
+    ));
 
-    // Generate the proof.
-    let proof = client
-        .prove_groth16(&pk, stdin)
-        .expect("failed to generate proof");
+    // Generate the proof. Only the SNARK modes produce proof bytes for the Solidity verifier.
+    let (public_values, proof): (SP1PublicValues, Option<String>) = match args.proof_mode {
+        ProofMode::Compressed => {
+            let proof = client
+                .prove_compressed(&pk, stdin)
+                .map_err(ProveError::Proof)?;
+            (proof.public_values, None)
+        }
+        ProofMode::Groth16 => {
+            let proof = client
+                .prove_groth16(&pk, stdin)
+                .map_err(ProveError::Proof)?;
+            let bytes = proof.bytes();
+            (proof.public_values, Some(bytes))
+        }
+        ProofMode::Plonk => {
+            let proof = client.prove_plonk(&pk, stdin).map_err(ProveError::Proof)?;
+            let bytes = proof.bytes();
+            (proof.public_values, Some(bytes))
+        }
+    };
 
     let KeyEncOut {
         keyHash,
-        keyCipher,
-    } = KeyEncOut::abi_decode(proof.public_values.as_slice(), false).unwrap();
+        keyCiphers,
+    } = KeyEncOut::abi_decode(public_values.as_slice(), false).map_err(ProveError::PublicValues)?;
 
     let key_hash = hex::encode(keyHash);
     println!("Key Hash: {}", key_hash);
 
+    // Catch a host/program divergence before a fixture that no vendor can decrypt is written.
+    let expected = encrypt_key_to(args.curve, args.kdf, &local_sk, &vendor_pks, &key, &nonce)?;
+    if let Some(recipient) = (0..expected.len())
+        .find(|&i| keyCiphers.get(i).map(|c| c.as_ref()) != Some(expected[i].as_slice()))
+    {
+        return Err(MismatchError::KeyCipher { recipient }.into());
+    }
+
     // Create the testing fixture so we can test things end-ot-end.
     let fixture = SP1EcdhProofFixture {
         local_sk: local_sk_hex,
-        vendor_pk: vendor_pk_hex,
+        local_pk: Some(local_pk_hex.clone()),
+        vendor_pks: vendor_pks_hex,
         vkey: vk.bytes32().to_string(),
-        public_values: proof.public_values.bytes().to_string(),
-        proof: proof.bytes().to_string(),
+        public_values: public_values.bytes().to_string(),
+        proof,
         key_hash,
+        curve: args.curve,
+        kdf: args.kdf,
+        nonce: hex::encode(nonce),
+        key_ciphers: keyCiphers.iter().map(hex::encode).collect(),
     };
 
     // The verification key is used to verify that the proof corresponds to the execution of the
@@ -134,14 +246,110 @@ fn main() {
 
     // The proof proves to the verifier that the program was executed with some inputs that led to
     // the give public values.
-    println!("Proof Bytes: {}", fixture.proof);
+    if let Some(proof) = &fixture.proof {
+        println!("Proof Bytes: {}", proof);
+    }
+
+    if let Some(path) = &args.public_key {
+        std::fs::write(path, &local_pk_hex).map_err(|source| ProveError::Write {
+            path: path.clone(),
+            source,
+        })?;
+    }
 
     // Save the fixture to a file.
-    let fixture_path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../fixtures");
-    std::fs::create_dir_all(&fixture_path).expect("failed to create fixture path");
+    std::fs::create_dir_all(&args.fixtures_dir).map_err(|source| ProveError::Write {
+        path: args.fixtures_dir.clone(),
+        source,
+    })?;
+    let fixture_path = args.fixtures_dir.join("ecdh_fixture.json");
     std::fs::write(
-        fixture_path.join("ecdh_fixture.json"),
-        serde_json::to_string_pretty(&fixture).unwrap(),
+        &fixture_path,
+        serde_json::to_string_pretty(&fixture).expect("fixture serializes to json"),
     )
-    .expect("failed to write fixture");
+    .map_err(|source| ProveError::Write {
+        path: fixture_path,
+        source,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn proof_mode_parses_all_variants() {
+        for (value, mode) in [
+            ("compressed", ProofMode::Compressed),
+            ("groth16", ProofMode::Groth16),
+            ("plonk", ProofMode::Plonk),
+        ] {
+            let args = ProveArgs::try_parse_from(["prove", "--proof-mode", value]).unwrap();
+            assert_eq!(args.proof_mode, mode);
+        }
+        assert!(ProveArgs::try_parse_from(["prove", "--proof-mode", "stark"]).is_err());
+    }
+
+    #[test]
+    fn malformed_vendor_pk_is_rejected_before_proving() {
+        for curve in ["k256", "p256"] {
+            let args =
+                ProveArgs::try_parse_from(["prove", "--curve", curve, "--vendor-pk", "04deadbeef"])
+                    .unwrap();
+            assert!(matches!(args.keys(), Err(KeyError::WrongLength { .. })));
+        }
+    }
+
+    #[test]
+    fn omitted_keys_are_generated_for_the_curve() {
+        let args = ProveArgs::try_parse_from(["prove", "--curve", "p256"]).unwrap();
+        let (local_sk, vendor_pks) = args.keys().unwrap();
+        assert_eq!(local_sk.len(), Curve::SECRET_KEY_LEN);
+        assert_eq!(vendor_pks.len(), 1);
+        assert_eq!(vendor_pks[0].len(), Curve::PUBLIC_KEY_LEN);
+    }
+
+    #[test]
+    fn vendor_pk_is_repeatable() {
+        let (_, alice) = Curve::K256.random_key_pair();
+        let (_, bob) = Curve::K256.random_key_pair();
+        let args = ProveArgs::try_parse_from([
+            "prove",
+            "--vendor-pk",
+            &hex::encode(&alice),
+            "--vendor-pk",
+            &hex::encode(&bob),
+        ])
+        .unwrap();
+        assert_eq!(args.keys().unwrap().1, [alice, bob]);
+    }
+
+    #[test]
+    fn enc_key_replaces_the_key_file() {
+        let hex = "07".repeat(32);
+        let args = ProveArgs::try_parse_from(["prove", "--enc-key", &hex]).unwrap();
+        assert_eq!(args.key().unwrap(), [7; 32]);
+
+        let args = ProveArgs::try_parse_from(["prove", "--enc-key", "0707"]).unwrap();
+        assert!(matches!(args.key(), Err(ProveError::Key(_))));
+
+        let err =
+            ProveArgs::try_parse_from(["prove", "--enc-key", &hex, "--key-file", "k"]).unwrap_err();
+        assert_eq!(err.kind(), clap::error::ErrorKind::ArgumentConflict);
+    }
+
+    #[test]
+    fn proof_mode_defaults_to_groth16() {
+        let args = ProveArgs::try_parse_from(["prove"]).unwrap();
+        assert_eq!(args.proof_mode, ProofMode::Groth16);
+    }
+
+    #[test]
+    fn fixtures_dir_resolves_against_the_current_directory() {
+        let args = ProveArgs::try_parse_from(["prove", "--fixtures-dir", "ci/fixtures"]).unwrap();
+        assert_eq!(
+            args.fixtures_dir,
+            std::env::current_dir().unwrap().join("ci/fixtures")
+        );
+    }
 }
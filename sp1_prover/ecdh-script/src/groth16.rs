@@ -0,0 +1,184 @@
+//! Checking a Groth16 fixture without the prover state that produced it.
+//!
+//! The fixture stores the proof as `SP1Groth16Proof::bytes` encodes it for the SP1 Solidity
+//! verifier: the first four bytes of the verifier's circuit vkey hash, which select the verifier
+//! version, followed by the eight BN254 base field elements of the proof points `a`, `b` and `c`.
+
+use alloy_sol_types::SolType;
+use sp1_sdk::{HashableKey, ProverClient};
+
+use crate::{KeyEncOut, SP1EcdhProofFixture};
+
+pub const SELECTOR_LEN: usize = 4;
+pub const ELEMENT_LEN: usize = 32;
+pub const ELEMENTS: usize = 8;
+
+/// The BN254 base field modulus, big-endian.
+const FIELD_MODULUS: [u8; ELEMENT_LEN] = [
+    0x30, 0x64, 0x4e, 0x72, 0xe1, 0x31, 0xa0, 0x29, 0xb8, 0x50, 0x45, 0xb6, 0x81, 0x81, 0x58, 0x5d,
+    0x97, 0x81, 0x6a, 0x91, 0x68, 0x71, 0xca, 0x8d, 0x3c, 0x20, 0x8c, 0x16, 0xd8, 0x7c, 0xfd, 0x47,
+];
+
+/// A Groth16 proof in the encoding the Solidity verifier reads.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Groth16Proof {
+    pub selector: [u8; SELECTOR_LEN],
+    /// `a`, `b` and `c` as big-endian field elements, in the order the verifier reads them.
+    pub elements: [[u8; ELEMENT_LEN]; ELEMENTS],
+}
+
+impl Groth16Proof {
+    /// Encodes the proof the way `SP1Groth16Proof::bytes` does, `0x` prefix included.
+    pub fn to_hex(&self) -> String {
+        let mut bytes = self.selector.to_vec();
+        bytes.extend(self.elements.iter().flatten());
+        format!("0x{}", hex::encode(bytes))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ParseError {
+    #[error("proof is not valid hex: {0}")]
+    Hex(hex::FromHexError),
+    #[error("proof is {0} bytes long, expected {}", SELECTOR_LEN + ELEMENTS * ELEMENT_LEN)]
+    Length(usize),
+    #[error("proof element {0} is not a BN254 field element")]
+    NotInField(usize),
+}
+
+/// Parses the hex of a proof as written to the fixture, with or without the `0x` prefix.
+pub fn groth16_from_hex(s: &str) -> Result<Groth16Proof, ParseError> {
+    let bytes = hex::decode(s.strip_prefix("0x").unwrap_or(s)).map_err(ParseError::Hex)?;
+    if bytes.len() != SELECTOR_LEN + ELEMENTS * ELEMENT_LEN {
+        return Err(ParseError::Length(bytes.len()));
+    }
+
+    let (selector, rest) = bytes.split_at(SELECTOR_LEN);
+    let mut elements = [[0; ELEMENT_LEN]; ELEMENTS];
+    for (index, (element, chunk)) in elements
+        .iter_mut()
+        .zip(rest.chunks_exact(ELEMENT_LEN))
+        .enumerate()
+    {
+        element.copy_from_slice(chunk);
+        // Big-endian arrays of equal length compare like the numbers they encode.
+        if *element >= FIELD_MODULUS {
+            return Err(ParseError::NotInField(index));
+        }
+    }
+
+    Ok(Groth16Proof {
+        selector: selector.try_into().expect("split at the selector length"),
+        elements,
+    })
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum VerifyError {
+    #[error("fixture has no proof, it was proven in compressed mode")]
+    MissingProof,
+    #[error(transparent)]
+    Parse(#[from] ParseError),
+    #[error("fixture vkey {fixture} is not the vkey {program} of the program")]
+    VkeyMismatch { fixture: String, program: String },
+    #[error("failed to decode public values: {0}")]
+    PublicValues(String),
+    #[error("fixture {field} doesn't match the committed public values")]
+    PublicValuesMismatch { field: &'static str },
+}
+
+/// Checks that `fixture` holds a well-formed Groth16 proof of `elf` whose public values are the
+/// ones the fixture claims.
+///
+/// The pairing check itself needs the gnark circuit artifacts the SP1 verifier contract is built
+/// from, so it is left to the Solidity tests that consume the fixture.
+pub fn verify_groth16(fixture: &SP1EcdhProofFixture, elf: &[u8]) -> Result<(), VerifyError> {
+    let proof = fixture.proof.as_deref().ok_or(VerifyError::MissingProof)?;
+    groth16_from_hex(proof)?;
+
+    // The vkey only depends on the program, so it can be recomputed from the ELF alone.
+    let (_, vk) = ProverClient::new().setup(elf);
+    let program = vk.bytes32();
+    if !strip_0x(&fixture.vkey).eq_ignore_ascii_case(strip_0x(&program)) {
+        return Err(VerifyError::VkeyMismatch {
+            fixture: fixture.vkey.clone(),
+            program,
+        });
+    }
+
+    let public_values = hex::decode(strip_0x(&fixture.public_values))
+        .map_err(|e| VerifyError::PublicValues(e.to_string()))?;
+    let KeyEncOut {
+        keyHash,
+        keyCiphers,
+    } = KeyEncOut::abi_decode(&public_values, false)
+        .map_err(|e| VerifyError::PublicValues(e.to_string()))?;
+    if !strip_0x(&fixture.key_hash).eq_ignore_ascii_case(&hex::encode(keyHash)) {
+        return Err(VerifyError::PublicValuesMismatch { field: "keyHash" });
+    }
+    let ciphers_match = fixture.key_ciphers.len() == keyCiphers.len()
+        && fixture
+            .key_ciphers
+            .iter()
+            .zip(&keyCiphers)
+            .all(|(fixture, program)| {
+                strip_0x(fixture).eq_ignore_ascii_case(&hex::encode(program))
+            });
+    if !ciphers_match {
+        return Err(VerifyError::PublicValuesMismatch {
+            field: "keyCiphers",
+        });
+    }
+
+    Ok(())
+}
+
+fn strip_0x(s: &str) -> &str {
+    s.strip_prefix("0x").unwrap_or(s)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_proof() -> Groth16Proof {
+        let mut elements = [[0; ELEMENT_LEN]; ELEMENTS];
+        for (i, element) in elements.iter_mut().enumerate() {
+            element[ELEMENT_LEN - 1] = i as u8 + 1;
+        }
+        Groth16Proof {
+            selector: [0xc4, 0x30, 0xff, 0x7f],
+            elements,
+        }
+    }
+
+    #[test]
+    fn hex_round_trips() {
+        let proof = test_proof();
+        let hex = proof.to_hex();
+        assert!(hex.starts_with("0xc430ff7f"));
+        assert_eq!(hex.len(), 2 + 2 * (SELECTOR_LEN + ELEMENTS * ELEMENT_LEN));
+
+        assert_eq!(groth16_from_hex(&hex), Ok(proof.clone()));
+        assert_eq!(groth16_from_hex(&hex[2..]), Ok(proof));
+    }
+
+    #[test]
+    fn malformed_proofs_are_rejected() {
+        let hex = test_proof().to_hex();
+        assert_eq!(
+            groth16_from_hex(&hex[..hex.len() - 2]),
+            Err(ParseError::Length(
+                SELECTOR_LEN + ELEMENTS * ELEMENT_LEN - 1
+            ))
+        );
+        assert!(matches!(groth16_from_hex("0xzz"), Err(ParseError::Hex(_))));
+
+        let mut proof = test_proof();
+        proof.elements[3] = FIELD_MODULUS;
+        assert_eq!(
+            groth16_from_hex(&proof.to_hex()),
+            Err(ParseError::NotInField(3))
+        );
+    }
+}
@@ -0,0 +1,591 @@
+//! Parsing and generating the keys the ECDH exchange runs on.
+
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use chacha20::{
+    cipher::{KeyIvInit, StreamCipher},
+    ChaCha20,
+};
+use clap::ValueEnum;
+use rand::{rngs::OsRng, Rng};
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Keccak256};
+use static_dh_ecdh::ecdh::ecdh::{
+    FromBytes, KeyExchange, PkP256, Pkk256, SkP256, Skk256, ToBytes, ECDHNISTK256, ECDHNISTP256,
+};
+
+/// The curve the ECDH exchange runs on. The program treats both keys as opaque bytes, so the same
+/// program serves either curve; only the host side needs to know which one is in use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Curve {
+    /// secp256k1, the Ethereum curve.
+    #[default]
+    K256,
+    /// NIST P-256, also known as secp256r1.
+    P256,
+}
+
+impl fmt::Display for Curve {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Curve::K256 => "K256",
+            Curve::P256 => "P256",
+        })
+    }
+}
+
+/// How the key encrypting the ChaCha key is derived from the ECDH shared secret.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Kdf {
+    /// The shared secret, the x coordinate of the shared point, is used as the key directly.
+    #[default]
+    Raw,
+    /// Keccak256 of the shared secret.
+    Keccak256,
+}
+
+impl fmt::Display for Kdf {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Kdf::Raw => "raw",
+            Kdf::Keccak256 => "keccak256",
+        })
+    }
+}
+
+impl Kdf {
+    /// Identifies the KDF to the program.
+    pub fn id(self) -> u8 {
+        match self {
+            Kdf::Raw => 0,
+            Kdf::Keccak256 => 1,
+        }
+    }
+
+    pub fn derive(self, shared_secret: &[u8; 32]) -> [u8; 32] {
+        match self {
+            Kdf::Raw => *shared_secret,
+            Kdf::Keccak256 => Keccak256::digest(shared_secret).into(),
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum KeyError {
+    #[error("{field} is not valid hex: {error}")]
+    Hex {
+        field: &'static str,
+        error: hex::FromHexError,
+    },
+    #[error("{field} is {actual} bytes long, but {curve} keys of its kind are {expected} bytes")]
+    WrongLength {
+        field: &'static str,
+        curve: Curve,
+        expected: usize,
+        actual: usize,
+    },
+    #[error("{field} is not a valid {curve} private key")]
+    InvalidSecretKey { field: &'static str, curve: Curve },
+    #[error("{field} is not a valid point on the {curve} curve")]
+    InvalidPublicKey { field: &'static str, curve: Curve },
+    #[error(transparent)]
+    Length(#[from] LengthError),
+}
+
+/// A fixed-size input, such as a key or nonce, was given with the wrong number of bytes.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("expected {expected} bytes, got {actual} in {field}")]
+pub struct LengthError {
+    pub expected: usize,
+    pub actual: usize,
+    pub field: &'static str,
+}
+
+/// Converts `bytes` into an array, reporting a [`LengthError`] for `field` if it is not `N` bytes
+/// long.
+pub fn fixed_bytes<const N: usize>(
+    field: &'static str,
+    bytes: Vec<u8>,
+) -> Result<[u8; N], LengthError> {
+    bytes.try_into().map_err(|bytes: Vec<u8>| LengthError {
+        expected: N,
+        actual: bytes.len(),
+        field,
+    })
+}
+
+/// Decodes a hex value of exactly `N` bytes, with or without a `0x` prefix.
+pub fn parse_fixed_hex<const N: usize>(field: &'static str, s: &str) -> Result<[u8; N], KeyError> {
+    Ok(fixed_bytes(field, decode(field, s)?)?)
+}
+
+/// Where the zkpoex ChaCha key is looked for when `--key-file` is not given. The zkpoex script
+/// only writes it there with `--persist-key`.
+pub const DEFAULT_KEY_FILE: &str = "data/zkpoex_enc_key";
+
+#[derive(Debug, thiserror::Error)]
+pub enum KeyFileError {
+    #[error(
+        "key file {} not found, prove with --persist-key or pass the key with --enc-key",
+        .0.display()
+    )]
+    Missing(PathBuf),
+    #[error("failed to read {}: {source}", .path.display())]
+    Read {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("{}: {source}", .path.display())]
+    Length { path: PathBuf, source: LengthError },
+}
+
+/// Resolves a relative key path against the crate directory rather than the CWD, so the script
+/// behaves the same wherever it is run from.
+pub fn resolve_key_file(path: &Path) -> PathBuf {
+    if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join(path)
+    }
+}
+
+/// Reads the raw 32 byte ChaCha key written by the zkpoex script.
+pub fn read_key_file(path: &Path) -> Result<[u8; 32], KeyFileError> {
+    let bytes = std::fs::read(path).map_err(|source| match source.kind() {
+        std::io::ErrorKind::NotFound => KeyFileError::Missing(path.to_path_buf()),
+        _ => KeyFileError::Read {
+            path: path.to_path_buf(),
+            source,
+        },
+    })?;
+    fixed_bytes("key-file", bytes).map_err(|source| KeyFileError::Length {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+fn decode(field: &'static str, s: &str) -> Result<Vec<u8>, KeyError> {
+    hex::decode(s.strip_prefix("0x").unwrap_or(s)).map_err(|error| KeyError::Hex { field, error })
+}
+
+/// Runs `$body` with `$kx`, `$sk` and `$pk` naming the key exchange, secret key and public key
+/// types of `$curve`.
+macro_rules! with_curve {
+    ($curve:expr, |$kx:ident, $sk:ident, $pk:ident| $body:expr) => {
+        match $curve {
+            // Not every body uses all three types.
+            #[allow(dead_code)]
+            Curve::K256 => {
+                type $kx = ECDHNISTK256;
+                type $sk = Skk256;
+                type $pk = Pkk256;
+                $body
+            }
+            #[allow(dead_code)]
+            Curve::P256 => {
+                type $kx = ECDHNISTP256<32>;
+                type $sk = SkP256;
+                type $pk = PkP256;
+                $body
+            }
+        }
+    };
+}
+
+impl Curve {
+    pub const SECRET_KEY_LEN: usize = 32;
+    /// Public keys are SEC1 encoded uncompressed points: a tag byte and both coordinates.
+    pub const PUBLIC_KEY_LEN: usize = 65;
+
+    /// Identifies the curve to the program.
+    pub fn id(self) -> u8 {
+        match self {
+            Curve::K256 => 0,
+            Curve::P256 => 1,
+        }
+    }
+
+    fn check_len(self, field: &'static str, bytes: &[u8], expected: usize) -> Result<(), KeyError> {
+        if bytes.len() != expected {
+            return Err(KeyError::WrongLength {
+                field,
+                curve: self,
+                expected,
+                actual: bytes.len(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Decodes a hex private key, checking it is a valid scalar of this curve.
+    pub fn parse_secret_key(self, field: &'static str, s: &str) -> Result<Vec<u8>, KeyError> {
+        let bytes = decode(field, s)?;
+        self.check_len(field, &bytes, Self::SECRET_KEY_LEN)?;
+        with_curve!(self, |Kx, Sk, Pk| Sk::from_bytes(&bytes)
+            .map(|_| ())
+            .map_err(|_| KeyError::InvalidSecretKey { field, curve: self }))?;
+        Ok(bytes)
+    }
+
+    /// Decodes a hex public key, checking it is a point on this curve.
+    pub fn parse_public_key(self, field: &'static str, s: &str) -> Result<Vec<u8>, KeyError> {
+        let bytes = decode(field, s)?;
+        self.check_len(field, &bytes, Self::PUBLIC_KEY_LEN)?;
+        with_curve!(self, |Kx, Sk, Pk| Pk::from_bytes(&bytes)
+            .map(|_| ())
+            .map_err(|_| KeyError::InvalidPublicKey { field, curve: self }))?;
+        Ok(bytes)
+    }
+
+    /// A fresh private key seeded from the operating system's CSPRNG.
+    pub fn random_secret_key(self) -> Vec<u8> {
+        let seed: [u8; 32] = OsRng.gen();
+        with_curve!(self, |Kx, Sk, Pk| Kx::generate_private_key(seed)
+            .to_bytes()
+            .to_vec())
+    }
+
+    /// A fresh `(secret, public)` key pair, for when there is no real counterparty to encrypt to.
+    pub fn random_key_pair(self) -> (Vec<u8>, Vec<u8>) {
+        let sk = self.random_secret_key();
+        let pk = self
+            .public_key("secret key", &sk)
+            .expect("generated keys are valid");
+        (sk, pk)
+    }
+
+    /// The public half of the private key `sk`.
+    pub fn public_key(self, field: &'static str, sk: &[u8]) -> Result<Vec<u8>, KeyError> {
+        with_curve!(self, |Kx, Sk, Pk| {
+            let sk = Sk::from_bytes(sk)
+                .map_err(|_| KeyError::InvalidSecretKey { field, curve: self })?;
+            Ok(Kx::generate_public_key(&sk).to_bytes().to_vec())
+        })
+    }
+
+    /// The ECDH shared secret between `sk` and `pk`, which both sides of the exchange derive.
+    pub fn shared_secret(self, sk: &[u8], pk: &[u8]) -> Result<[u8; 32], MismatchError> {
+        let invalid_sk = KeyError::InvalidSecretKey {
+            field: "secret key",
+            curve: self,
+        };
+        let invalid_pk = KeyError::InvalidPublicKey {
+            field: "public key",
+            curve: self,
+        };
+        let shared = with_curve!(self, |Kx, Sk, Pk| {
+            let sk = Sk::from_bytes(sk).map_err(|_| invalid_sk)?;
+            let pk = Pk::from_bytes(pk).map_err(|_| invalid_pk)?;
+            Kx::generate_shared_secret(&sk, &pk)
+                .map_err(|_| MismatchError::SharedSecret)?
+                .to_bytes()
+                .to_vec()
+        });
+        shared.try_into().map_err(|_| MismatchError::SharedSecret)
+    }
+}
+
+/// The host-side derivation disagrees with what the program committed to.
+#[derive(Debug, thiserror::Error)]
+pub enum MismatchError {
+    #[error(transparent)]
+    InvalidKey(#[from] KeyError),
+    #[error("failed to derive the ECDH shared secret")]
+    SharedSecret,
+    #[error("local-pk is not the public key of local-sk")]
+    LocalPublicKey,
+    #[error("key cipher of recipient {recipient} differs from the one the host derived")]
+    KeyCipher { recipient: usize },
+    #[error("key hash mismatch: program committed {}, host derived {}", hex::encode(.expected), hex::encode(.actual))]
+    Hash {
+        expected: [u8; 32],
+        actual: [u8; 32],
+    },
+}
+
+/// Keccak256 of the ECDH shared secret between `local_sk` and `vendor_pk`.
+pub fn shared_key_hash(
+    curve: Curve,
+    local_sk: &[u8],
+    vendor_pk: &[u8],
+) -> Result<[u8; 32], MismatchError> {
+    Ok(Keccak256::digest(curve.shared_secret(local_sk, vendor_pk)?).into())
+}
+
+/// Encrypts `key` to every one of `vendor_pks` the way the program does: with ChaCha20 under the
+/// key `kdf` derives from the shared secret of `local_sk` and that vendor.
+pub fn encrypt_key_to(
+    curve: Curve,
+    kdf: Kdf,
+    local_sk: &[u8],
+    vendor_pks: &[Vec<u8>],
+    key: &[u8; 32],
+    nonce: &[u8; 12],
+) -> Result<Vec<Vec<u8>>, MismatchError> {
+    vendor_pks
+        .iter()
+        .map(|vendor_pk| {
+            let shared = curve.shared_secret(local_sk, vendor_pk)?;
+            let mut key_cipher = key.to_vec();
+            ChaCha20::new(&kdf.derive(&shared).into(), nonce.into())
+                .apply_keystream(&mut key_cipher);
+            Ok(key_cipher)
+        })
+        .collect()
+}
+
+/// Recovers the ChaCha key the program encrypted under the key `kdf` derives from the ECDH shared
+/// secret and checks it against the committed `key_hash`. ChaCha20 is a stream cipher, so
+/// decrypting reapplies the keystream.
+pub fn decrypt_key_cipher(
+    kdf: Kdf,
+    shared_secret: &[u8; 32],
+    nonce: &[u8; 12],
+    key_cipher: &[u8],
+    key_hash: [u8; 32],
+) -> Result<[u8; 32], MismatchError> {
+    let mut key = key_cipher.to_vec();
+    ChaCha20::new(&kdf.derive(shared_secret).into(), nonce.into()).apply_keystream(&mut key);
+
+    let actual: [u8; 32] = Keccak256::digest(&key).into();
+    if actual != key_hash {
+        return Err(MismatchError::Hash {
+            expected: key_hash,
+            actual,
+        });
+    }
+    Ok(key
+        .try_into()
+        .expect("hash matched, so the key is the 32 bytes that were hashed"))
+}
+
+/// Recomputes the shared secret off-chain and checks it hashes to the `keyHash` the program
+/// committed, so a host/program divergence is caught before a fixture is written.
+pub fn verify_key_commitment(
+    curve: Curve,
+    local_sk: &[u8],
+    vendor_pk: &[u8],
+    expected_hash: [u8; 32],
+) -> Result<(), MismatchError> {
+    let actual = shared_key_hash(curve, local_sk, vendor_pk)?;
+    if actual != expected_hash {
+        return Err(MismatchError::Hash {
+            expected: expected_hash,
+            actual,
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CURVES: [Curve; 2] = [Curve::K256, Curve::P256];
+
+    #[test]
+    fn generated_keys_are_accepted_under_each_curve() {
+        for curve in CURVES {
+            let (sk, pk) = curve.random_key_pair();
+            assert_eq!(sk.len(), Curve::SECRET_KEY_LEN, "{curve}");
+            assert_eq!(pk.len(), Curve::PUBLIC_KEY_LEN, "{curve}");
+            assert_eq!(
+                curve
+                    .parse_secret_key("local-sk", &hex::encode(&sk))
+                    .unwrap(),
+                sk
+            );
+            assert_eq!(
+                curve
+                    .parse_public_key("vendor-pk", &hex::encode(&pk))
+                    .unwrap(),
+                pk
+            );
+
+            let (other_sk, other_pk) = curve.random_key_pair();
+            assert_eq!(
+                curve.shared_secret(&sk, &other_pk).unwrap(),
+                curve.shared_secret(&other_sk, &pk).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn malformed_vendor_pk_is_rejected() {
+        for curve in CURVES {
+            assert!(matches!(
+                curve.parse_public_key("vendor-pk", "04zz"),
+                Err(KeyError::Hex { .. })
+            ));
+            assert!(matches!(
+                curve.parse_public_key("vendor-pk", &hex::encode([0x04; 65])),
+                Err(KeyError::InvalidPublicKey {
+                    field: "vendor-pk",
+                    ..
+                })
+            ));
+        }
+    }
+
+    #[test]
+    fn key_of_wrong_length_for_the_curve_is_rejected() {
+        assert!(matches!(
+            Curve::P256.parse_public_key("vendor-pk", &hex::encode([0x04; 33])),
+            Err(KeyError::WrongLength {
+                curve: Curve::P256,
+                expected: 65,
+                actual: 33,
+                ..
+            })
+        ));
+        assert!(matches!(
+            Curve::K256.parse_secret_key("local-sk", &hex::encode([1; 48])),
+            Err(KeyError::WrongLength {
+                expected: 32,
+                actual: 48,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn relative_key_file_resolves_against_the_crate() {
+        assert_eq!(
+            resolve_key_file(Path::new(DEFAULT_KEY_FILE)),
+            Path::new(env!("CARGO_MANIFEST_DIR")).join(DEFAULT_KEY_FILE)
+        );
+        assert_eq!(
+            resolve_key_file(Path::new("/tmp/key")),
+            PathBuf::from("/tmp/key")
+        );
+    }
+
+    #[test]
+    fn key_file_of_wrong_length_is_rejected() {
+        let path = std::env::temp_dir().join(format!("ecdh-short-key-{}", std::process::id()));
+        std::fs::write(&path, [7u8; 31]).unwrap();
+        let result = read_key_file(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        let Err(KeyFileError::Length { source, .. }) = result else {
+            panic!("expected a length error, got {result:?}");
+        };
+        assert_eq!(source.to_string(), "expected 32 bytes, got 31 in key-file");
+    }
+
+    #[test]
+    fn nonce_of_wrong_length_is_rejected() {
+        assert_eq!(
+            parse_fixed_hex::<12>("nonce", &hex::encode([6; 12])).unwrap(),
+            [6; 12]
+        );
+        assert!(matches!(
+            parse_fixed_hex::<12>("nonce", "0x0606"),
+            Err(KeyError::Length(LengthError {
+                expected: 12,
+                actual: 2,
+                field: "nonce",
+            }))
+        ));
+    }
+
+    #[test]
+    fn missing_key_file_is_reported() {
+        let path = std::env::temp_dir().join("ecdh-no-such-key");
+        assert!(matches!(
+            read_key_file(&path),
+            Err(KeyFileError::Missing(p)) if p == path
+        ));
+    }
+
+    #[test]
+    fn key_commitment_matches_the_vendor_side_derivation() {
+        let local_sk = ECDHNISTK256::generate_private_key([12; 32]);
+        let local_pk = ECDHNISTK256::generate_public_key(&local_sk);
+        let vendor_sk = ECDHNISTK256::generate_private_key([13; 32]);
+        let vendor_pk = ECDHNISTK256::generate_public_key(&vendor_sk);
+
+        // The vendor derives the same secret from their side of the exchange.
+        let shared = ECDHNISTK256::generate_shared_secret(&vendor_sk, &local_pk).unwrap();
+        let expected: [u8; 32] = Keccak256::digest(shared.to_bytes()).into();
+
+        let local_sk = local_sk.to_bytes().to_vec();
+        let vendor_pk = vendor_pk.to_bytes().to_vec();
+        verify_key_commitment(Curve::K256, &local_sk, &vendor_pk, expected).unwrap();
+        assert!(matches!(
+            verify_key_commitment(Curve::K256, &local_sk, &vendor_pk, [0; 32]),
+            Err(MismatchError::Hash {
+                expected: [0; 32],
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn each_recipient_recovers_the_key_from_their_own_cipher() {
+        let (local_sk, local_pk) = Curve::K256.random_key_pair();
+        let (alice_sk, alice_pk) = Curve::K256.random_key_pair();
+        let (bob_sk, bob_pk) = Curve::K256.random_key_pair();
+        let key = [5u8; 32];
+        let nonce = [6u8; 12];
+        let key_hash: [u8; 32] = Keccak256::digest(key).into();
+
+        let ciphers = encrypt_key_to(
+            Curve::K256,
+            Kdf::Raw,
+            &local_sk,
+            &[alice_pk, bob_pk],
+            &key,
+            &nonce,
+        )
+        .unwrap();
+        assert_eq!(ciphers.len(), 2);
+        assert_ne!(ciphers[0], ciphers[1]);
+
+        for (sk, cipher) in [alice_sk, bob_sk].iter().zip(&ciphers) {
+            let shared = Curve::K256.shared_secret(sk, &local_pk).unwrap();
+            assert_eq!(
+                decrypt_key_cipher(Kdf::Raw, &shared, &nonce, cipher, key_hash).unwrap(),
+                key
+            );
+        }
+    }
+
+    #[test]
+    fn vendor_recovers_the_key_from_their_side() {
+        let (local_sk, local_pk) = Curve::K256.random_key_pair();
+        let (vendor_sk, vendor_pk) = Curve::K256.random_key_pair();
+        let key = [5u8; 32];
+        let nonce = [6u8; 12];
+        let key_hash: [u8; 32] = Keccak256::digest(key).into();
+
+        let mut key_cipher = key.to_vec();
+        let shared = Curve::K256.shared_secret(&local_sk, &vendor_pk).unwrap();
+        ChaCha20::new(&shared.into(), &nonce.into()).apply_keystream(&mut key_cipher);
+
+        let vendor_shared = Curve::K256.shared_secret(&vendor_sk, &local_pk).unwrap();
+        assert_eq!(
+            decrypt_key_cipher(Kdf::Raw, &vendor_shared, &nonce, &key_cipher, key_hash).unwrap(),
+            key
+        );
+        assert!(matches!(
+            decrypt_key_cipher(
+                Kdf::Keccak256,
+                &vendor_shared,
+                &nonce,
+                &key_cipher,
+                key_hash
+            ),
+            Err(MismatchError::Hash { .. })
+        ));
+        assert!(matches!(
+            decrypt_key_cipher(Kdf::Raw, &[0; 32], &nonce, &key_cipher, key_hash),
+            Err(MismatchError::Hash { .. })
+        ));
+    }
+}
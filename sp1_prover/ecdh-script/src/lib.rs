@@ -0,0 +1,174 @@
+//! Shared pieces of the ecdh binaries.
+
+pub mod groth16;
+pub mod keys;
+
+use std::path::PathBuf;
+
+use alloy_sol_types::sol;
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::keys::{Curve, Kdf, MismatchError};
+
+/// The directory the fixture is written to and read from unless told otherwise, anchored on this
+/// crate so it is the same wherever the binaries run from.
+pub fn default_fixtures_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("..")
+        .join("fixtures")
+}
+
+/// A fixture that can be used to test the verification of SP1 zkVM proofs inside Solidity.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SP1EcdhProofFixture {
+    pub local_sk: String,
+    /// The public half of `local_sk`, which the vendors need to derive the shared secret. Fixtures
+    /// from before it was recorded leave it out.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub local_pk: Option<String>,
+    /// The recipients the key was encrypted to. Fixtures from before there could be several
+    /// record a single `vendorPk`.
+    #[serde(alias = "vendorPk", deserialize_with = "one_or_many")]
+    pub vendor_pks: Vec<String>,
+    pub vkey: String,
+    pub key_hash: String,
+    /// Fixtures from before the curve was selectable are all K256.
+    #[serde(default)]
+    pub curve: Curve,
+    /// How the key cipher's key is derived from the shared secret. Fixtures from before it was
+    /// recorded all use the raw shared secret.
+    #[serde(default)]
+    pub kdf: Kdf,
+    /// The ChaCha20 nonce `key_ciphers` were encrypted with; the vendor needs it to decrypt.
+    pub nonce: String,
+    /// The key encrypted to each of `vendor_pks`, in the same order.
+    #[serde(alias = "keyCipher", deserialize_with = "one_or_many")]
+    pub key_ciphers: Vec<String>,
+    pub public_values: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proof: Option<String>,
+}
+
+/// Accepts a single string where a list is expected, as written by older fixtures.
+fn one_or_many<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(s) => vec![s],
+        OneOrMany::Many(v) => v,
+    })
+}
+
+impl SP1EcdhProofFixture {
+    /// The prover's public key: `local_pk`, checked to be a point on the curve and to belong to
+    /// `local_sk`, or derived from `local_sk` if the fixture predates it.
+    pub fn local_public_key(&self) -> Result<Vec<u8>, MismatchError> {
+        let derived = self.curve.public_key(
+            "local-sk",
+            &self.curve.parse_secret_key("local-sk", &self.local_sk)?,
+        )?;
+        if let Some(local_pk) = &self.local_pk {
+            if self.curve.parse_public_key("local-pk", local_pk)? != derived {
+                return Err(MismatchError::LocalPublicKey);
+            }
+        }
+        Ok(derived)
+    }
+}
+
+sol! {
+    /// The public values encoded as a tuple that can be easily deserialized inside Solidity.
+    struct KeyEncOut {
+        bytes32 keyHash;
+        bytes[] keyCiphers;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn curve_and_kdf_round_trip_through_json() {
+        let fixture = SP1EcdhProofFixture {
+            local_sk: "11".repeat(32),
+            local_pk: None,
+            vendor_pks: vec!["04".repeat(65)],
+            vkey: "0x00".to_string(),
+            key_hash: "22".repeat(32),
+            curve: Curve::P256,
+            kdf: Kdf::Keccak256,
+            nonce: "33".repeat(12),
+            key_ciphers: vec!["44".repeat(32)],
+            public_values: "0x".to_string(),
+            proof: None,
+        };
+
+        let json = serde_json::to_value(&fixture).unwrap();
+        assert_eq!(json["curve"], "p256");
+        assert_eq!(json["kdf"], "keccak256");
+
+        let decoded: SP1EcdhProofFixture = serde_json::from_value(json).unwrap();
+        assert_eq!((decoded.curve, decoded.kdf), (Curve::P256, Kdf::Keccak256));
+    }
+
+    #[test]
+    fn fixture_without_kdf_uses_the_raw_shared_secret() {
+        let fixture = r#"{
+            "localSk": "", "vendorPk": "", "vkey": "0x00", "keyHash": "",
+            "nonce": "", "keyCipher": "", "publicValues": "0x"
+        }"#;
+        let fixture: SP1EcdhProofFixture = serde_json::from_str(fixture).unwrap();
+        assert_eq!((fixture.curve, fixture.kdf), (Curve::K256, Kdf::Raw));
+    }
+
+    #[test]
+    fn single_recipient_fixture_reads_as_a_list() {
+        let fixture = r#"{
+            "localSk": "", "vendorPk": "aa", "vkey": "0x00", "keyHash": "",
+            "nonce": "", "keyCipher": "bb", "publicValues": "0x"
+        }"#;
+        let fixture: SP1EcdhProofFixture = serde_json::from_str(fixture).unwrap();
+        assert_eq!(fixture.vendor_pks, ["aa"]);
+        assert_eq!(fixture.key_ciphers, ["bb"]);
+
+        let json = serde_json::to_value(&fixture).unwrap();
+        assert_eq!(json["vendorPks"], serde_json::json!(["aa"]));
+        assert_eq!(json["keyCiphers"], serde_json::json!(["bb"]));
+    }
+
+    #[test]
+    fn local_pk_belongs_to_local_sk() {
+        for curve in [Curve::K256, Curve::P256] {
+            let (local_sk, local_pk) = curve.random_key_pair();
+            let mut fixture: SP1EcdhProofFixture = serde_json::from_str(
+                r#"{
+                    "localSk": "", "vendorPks": [], "vkey": "0x00", "keyHash": "",
+                    "nonce": "", "keyCiphers": [], "publicValues": "0x"
+                }"#,
+            )
+            .unwrap();
+            fixture.curve = curve;
+            fixture.local_sk = hex::encode(&local_sk);
+
+            // Older fixtures without it still yield the key.
+            assert_eq!(fixture.local_public_key().unwrap(), local_pk);
+
+            fixture.local_pk = Some(hex::encode(&local_pk));
+            assert_eq!(fixture.local_public_key().unwrap(), local_pk);
+
+            let (_, other) = curve.random_key_pair();
+            fixture.local_pk = Some(hex::encode(other));
+            assert!(matches!(
+                fixture.local_public_key(),
+                Err(MismatchError::LocalPublicKey)
+            ));
+        }
+    }
+}
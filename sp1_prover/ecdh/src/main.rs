@@ -8,50 +8,48 @@
 #![no_main]
 sp1_zkvm::entrypoint!(main);
 
-use alloy_primitives::{fixed_bytes, FixedBytes, U256};
+use alloy_primitives::Bytes;
 use alloy_sol_types::{sol, SolType};
-use chacha20::cipher::{KeyIvInit, StreamCipher, StreamCipherSeek};
+use chacha20::cipher::{KeyIvInit, StreamCipher};
 use chacha20::ChaCha20;
 use sha3::{Digest, Keccak256};
-// use static_dh_ecdh::ecdh::ecdh::{FromBytes, KeyExchange, Pkk256, Skk256, ToBytes, ECDHNISTK256};
+use static_dh_ecdh::ecdh::ecdh::{
+    FromBytes, KeyExchange, PkP256, Pkk256, SkP256, Skk256, ToBytes, ECDHNISTK256, ECDHNISTP256,
+};
 
 /// The public values encoded as a tuple that can be easily deserialized inside Solidity.
 sol! {
     struct KeyEncOut {
         bytes32 keyHash;
-        bytes keyCipher;
+        bytes[] keyCiphers;
     }
 }
 
 pub fn main() {
-    let (key, nonce, seller_sk_bytes, buyer_pk_bytes) =
-        sp1_zkvm::io::read::<([u8; 32], [u8; 12], Vec<u8>, Vec<u8>)>();
+    let (key, nonce, curve_id, kdf_id, seller_sk_bytes, buyer_pks) =
+        sp1_zkvm::io::read::<([u8; 32], [u8; 12], u8, u8, Vec<u8>, Vec<Vec<u8>>)>();
 
-    // let seller_sk = Skk256::from_bytes(&seller_sk_bytes).unwrap();
-    // let buyer_pk = Pkk256::from_bytes(&buyer_pk_bytes).unwrap();
-
-    // let shared_key: [u8; 32] = ECDHNISTK256::generate_shared_secret(&seller_sk, &buyer_pk)
-    //     .unwrap()
-    //     .to_bytes()
-    //     .to_vec()
-    //     .try_into()
-    //     .unwrap();
-
-    let mut cipher = ChaCha20::new(&key.into(), &nonce.into());
-
-    let mut buffer = key.to_vec();
-
-    cipher.apply_keystream(&mut buffer);
-
-    let ciphertext = buffer.clone();
+    // Every recipient gets the key encrypted under their own shared secret with the seller.
+    let key_ciphers = buyer_pks
+        .iter()
+        .map(|buyer_pk_bytes| {
+            let shared_key = kdf(
+                kdf_id,
+                shared_secret(curve_id, &seller_sk_bytes, buyer_pk_bytes),
+            );
+            let mut buffer = key.to_vec();
+            ChaCha20::new(&shared_key.into(), &nonce.into()).apply_keystream(&mut buffer);
+            Bytes::from(buffer)
+        })
+        .collect();
 
     let mut hasher = Keccak256::new();
     hasher.update(key);
-    let key_hash: [u8; 32] = hasher.finalize().to_vec().try_into().unwrap();
+    let key_hash: [u8; 32] = hasher.finalize().into();
 
     let out = KeyEncOut {
         keyHash: key_hash.into(),
-        keyCipher: ciphertext.into(),
+        keyCiphers: key_ciphers,
     };
 
     let bytes = KeyEncOut::abi_encode(&out);
@@ -59,3 +57,34 @@ pub fn main() {
     // Commit to the public values of the program.
     sp1_zkvm::io::commit_slice(&bytes);
 }
+
+/// The ECDH shared secret of `sk` and `pk`. The ids match `Curve::id` on the host.
+fn shared_secret(curve_id: u8, sk: &[u8], pk: &[u8]) -> [u8; 32] {
+    let shared = match curve_id {
+        0 => ECDHNISTK256::generate_shared_secret(
+            &Skk256::from_bytes(sk).expect("valid K256 secret key"),
+            &Pkk256::from_bytes(pk).expect("valid K256 public key"),
+        )
+        .expect("K256 shared secret")
+        .to_bytes()
+        .to_vec(),
+        1 => ECDHNISTP256::<32>::generate_shared_secret(
+            &SkP256::from_bytes(sk).expect("valid P256 secret key"),
+            &PkP256::from_bytes(pk).expect("valid P256 public key"),
+        )
+        .expect("P256 shared secret")
+        .to_bytes()
+        .to_vec(),
+        id => panic!("unsupported curve {id}"),
+    };
+    shared.try_into().expect("shared secrets are 32 bytes")
+}
+
+/// The key the cipher is encrypted under. The ids match `Kdf::id` on the host.
+fn kdf(kdf_id: u8, shared_secret: [u8; 32]) -> [u8; 32] {
+    match kdf_id {
+        0 => shared_secret,
+        1 => Keccak256::digest(shared_secret).into(),
+        id => panic!("unsupported kdf {id}"),
+    }
+}
@@ -8,15 +8,32 @@
 #![no_main]
 sp1_zkvm::entrypoint!(main);
 
+use aes_gcm::Aes256Gcm;
 use alloy_sol_types::{sol, SolType};
 use chacha20::cipher::{KeyIvInit, StreamCipher, StreamCipherSeek};
-use chacha20::ChaCha20;
+use chacha20::{ChaCha20, XChaCha20};
+use chacha20poly1305::{
+    aead::{Aead, Payload},
+    ChaCha20Poly1305, KeyInit,
+};
 use evm_runner::{run_simulation, RunEvmResult};
 use sha3::{Digest, Keccak256};
 
+/// Leads the committed values; bump it whenever their layout changes so hosts expecting the old
+/// layout reject them instead of misdecoding.
+const PUBLIC_VALUES_VERSION: u8 = 2;
+
 pub fn main() {
-    let (key, nonce, calldata, blockchain_settings, drand_master_pk, round) =
-        sp1_zkvm::io::read::<([u8; 32], [u8; 12], String, String, Vec<u8>, u64)>();
+    let (
+        key,
+        cipher_id,
+        nonce,
+        associated_data,
+        calldata,
+        blockchain_settings,
+        drand_master_pk,
+        round,
+    ) = sp1_zkvm::io::read::<([u8; 32], u8, Vec<u8>, Vec<u8>, String, String, Vec<u8>, u64)>();
 
     let RunEvmResult {
         before,
@@ -25,11 +42,36 @@ pub fn main() {
         private_inputs_concat,
     }: RunEvmResult = run_simulation(&calldata, &blockchain_settings);
 
-    let mut cipher = ChaCha20::new(&key.into(), &nonce.into());
-
     let mut chacha_cipher = private_inputs_concat.as_bytes().to_vec();
 
-    cipher.apply_keystream(&mut chacha_cipher);
+    // The ids match `PayloadCipher::id` on the host.
+    match cipher_id {
+        0 => ChaCha20::new(&key.into(), nonce[..].into()).apply_keystream(&mut chacha_cipher),
+        1 => XChaCha20::new(&key.into(), nonce[..].into()).apply_keystream(&mut chacha_cipher),
+        2 => {
+            chacha_cipher = ChaCha20Poly1305::new(&key.into())
+                .encrypt(
+                    nonce[..].into(),
+                    Payload {
+                        msg: &chacha_cipher,
+                        aad: &associated_data,
+                    },
+                )
+                .expect("payload fits ChaCha20-Poly1305")
+        }
+        3 => {
+            chacha_cipher = Aes256Gcm::new(&key.into())
+                .encrypt(
+                    nonce[..].into(),
+                    Payload {
+                        msg: &chacha_cipher,
+                        aad: &associated_data,
+                    },
+                )
+                .expect("payload fits AES-256-GCM")
+        }
+        id => panic!("unsupported payload cipher {id}"),
+    }
 
     // let mut tlock_cipher = vec![];
     // tlock::encrypt(
@@ -45,13 +87,23 @@ pub fn main() {
     let key_hash = hasher.finalize();
     let key_hash_str = hex::encode(key_hash);
 
+    // Ties the calldata and settings a fixture advertises to the ones proven; the layout matches
+    // `inputs_hash` on the host.
+    let mut hasher = Keccak256::new();
+    hasher.update((calldata.len() as u64).to_be_bytes());
+    hasher.update(calldata.as_bytes());
+    hasher.update(blockchain_settings.as_bytes());
+    let inputs_hash: [u8; 32] = hasher.finalize().into();
+
     // Commit to the public values of the program.
     sp1_zkvm::io::commit(&(
+        PUBLIC_VALUES_VERSION,
         before,
         after,
         hash_private_inputs,
         chacha_cipher,
         key_hash_str,
+        inputs_hash,
         // tlock_cipher,
         // round,
     ));
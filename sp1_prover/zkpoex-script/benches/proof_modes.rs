@@ -0,0 +1,66 @@
+//! Proving and verification latency, and proof size, of the zkpoex program in every proof mode.
+//!
+//! Proves for real against the default drand chain, so it needs a working SP1 prover and the
+//! network:
+//! ```shell
+//! ZKPOEX_BENCH_CALLDATA=63d9b770 cargo bench --package zkpoex-script --features bench
+//! ```
+
+use std::fs;
+
+use clap::ValueEnum;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use zkpoex_script::{
+    calldata::calldata_arg, proof::ProofMode, settings::BlockchainSettings, ProveOptions,
+    ZkPoExProver,
+};
+
+/// The calldata proven when `ZKPOEX_BENCH_CALLDATA` is not set.
+const DEFAULT_CALLDATA: &str = "63d9b770";
+
+fn proof_modes(c: &mut Criterion) {
+    let calldata = std::env::var("ZKPOEX_BENCH_CALLDATA")
+        .map(|calldata| calldata_arg(&calldata).expect("ZKPOEX_BENCH_CALLDATA is valid calldata"))
+        .unwrap_or_else(|_| DEFAULT_CALLDATA.to_string());
+    let settings = BlockchainSettings::default();
+
+    let mut group = c.benchmark_group("zkpoex");
+    // A single proof takes minutes, criterion's default of 100 samples would take hours.
+    group.sample_size(10);
+
+    for &mode in ProofMode::value_variants() {
+        let name = mode.to_possible_value().expect("no skipped variants");
+        let prover = ZkPoExProver::new(ProveOptions {
+            proof_mode: mode,
+            ..Default::default()
+        })
+        .expect("prover sets up");
+
+        group.bench_function(BenchmarkId::new("prove", name.get_name()), |b| {
+            b.iter(|| prover.prove(&calldata, &settings).expect("exploit proves"))
+        });
+
+        let (_, proof) = prover.prove(&calldata, &settings).expect("exploit proves");
+        group.bench_function(BenchmarkId::new("verify", name.get_name()), |b| {
+            b.iter(|| prover.verify(&proof).expect("proof verifies"))
+        });
+
+        let path = std::env::temp_dir().join(format!("zkpoex-bench-{}.bincode", name.get_name()));
+        proof.save(&path).expect("proof saves");
+        let size = fs::metadata(&path).expect("saved proof exists").len();
+        fs::remove_file(&path).expect("saved proof is removable");
+        println!(
+            "zkpoex/size/{}: {} bytes saved, {} bytes on-chain",
+            name.get_name(),
+            size,
+            proof
+                .bytes()
+                .map_or(0, |bytes| bytes.len().saturating_sub(2) / 2)
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, proof_modes);
+criterion_main!(benches);
@@ -1,5 +1,5 @@
-use sp1_helper::build_program;
-
 fn main() {
-    build_program("../zk-poex")
+    // Without the prover the embedded ELF isn't used to prove anything, so it isn't rebuilt.
+    #[cfg(feature = "prover")]
+    sp1_helper::build_program("../zk-poex")
 }
@@ -0,0 +1,192 @@
+//! Packing the outputs of a prove run into a single `.tar.zst`, so a disclosure can be shared and
+//! archived as one file.
+//!
+//! The archive holds the manifest first, then every artifact it lists in manifest order, all at the
+//! archive root under their output directory names. Modification times, owners and modes are
+//! fixed, so the same outputs always pack into the same archive. The encryption key is not an
+//! artifact, so it is never bundled.
+
+use std::{
+    fs, io,
+    path::{Component, Path, PathBuf},
+};
+
+use crate::{
+    manifest::{Manifest, ManifestError},
+    output::write_atomic_with,
+};
+
+/// The zstd level archives are compressed at.
+const ZSTD_LEVEL: i32 = 19;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ArchiveError {
+    #[error("failed to access {}: {source}", .path.display())]
+    Io { path: PathBuf, source: io::Error },
+    #[error("failed to parse the bundled manifest: {0}")]
+    ParseManifest(serde_json::Error),
+    #[error("bundle has no manifest")]
+    MissingManifest,
+    /// An entry would be written outside of the directory the bundle is unpacked into.
+    #[error("bundle entry {} is not a plain file name", .0.display())]
+    UnsafePath(PathBuf),
+    #[error(transparent)]
+    Manifest(#[from] ManifestError),
+}
+
+/// Packs the manifest named `manifest_name` in `out_dir` and every artifact it lists into the
+/// archive at `path`, returning the manifest.
+pub fn bundle_outputs(
+    out_dir: &Path,
+    manifest_name: &str,
+    path: &Path,
+) -> Result<Manifest, ArchiveError> {
+    let manifest_path = out_dir.join(manifest_name);
+    let manifest: Manifest =
+        serde_json::from_slice(&read(&manifest_path)?).map_err(ArchiveError::ParseManifest)?;
+    // Packing files that changed since the manifest was written would only fail on unbundle.
+    manifest.verify(out_dir)?;
+
+    let mut entries = vec![(PathBuf::from(manifest_name), read(&manifest_path)?)];
+    for artifact in &manifest.artifacts {
+        entries.push((artifact.path.clone(), read(&out_dir.join(&artifact.path))?));
+    }
+
+    let io_error = |source| ArchiveError::Io {
+        path: path.to_path_buf(),
+        source,
+    };
+    write_atomic_with(path, |staging| {
+        let encoder = zstd::Encoder::new(fs::File::create(staging)?, ZSTD_LEVEL)?;
+        let mut tar = tar::Builder::new(encoder);
+        for (name, contents) in &entries {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(contents.len() as u64);
+            header.set_mode(0o644);
+            header.set_mtime(0);
+            header.set_cksum();
+            tar.append_data(&mut header, name, contents.as_slice())?;
+        }
+        tar.into_inner()?.finish()?;
+        Ok(())
+    })
+    .map_err(io_error)?;
+
+    Ok(manifest)
+}
+
+/// Unpacks the archive at `path` into `dir` and checks every artifact against the bundled
+/// manifest, returning the manifest.
+pub fn unbundle(path: &Path, dir: &Path) -> Result<Manifest, ArchiveError> {
+    let io_error = |path: &Path| {
+        let path = path.to_path_buf();
+        move |source| ArchiveError::Io { path, source }
+    };
+    fs::create_dir_all(dir).map_err(io_error(dir))?;
+
+    let decoder = zstd::Decoder::new(fs::File::open(path).map_err(io_error(path))?)
+        .map_err(io_error(path))?;
+    let mut tar = tar::Archive::new(decoder);
+
+    let mut manifest = None;
+    for entry in tar.entries().map_err(io_error(path))? {
+        let mut entry = entry.map_err(io_error(path))?;
+        let name = entry.path().map_err(io_error(path))?.into_owned();
+        if !matches!(
+            name.components().collect::<Vec<_>>()[..],
+            [Component::Normal(_)]
+        ) {
+            return Err(ArchiveError::UnsafePath(name));
+        }
+
+        let target = dir.join(&name);
+        entry.unpack(&target).map_err(io_error(&target))?;
+        // The manifest is packed first, so it is the first entry.
+        if manifest.is_none() {
+            manifest = Some(
+                serde_json::from_slice::<Manifest>(&read(&target)?)
+                    .map_err(ArchiveError::ParseManifest)?,
+            );
+        }
+    }
+
+    let manifest = manifest.ok_or(ArchiveError::MissingManifest)?;
+    manifest.verify(dir)?;
+    Ok(manifest)
+}
+
+fn read(path: &Path) -> Result<Vec<u8>, ArchiveError> {
+    fs::read(path).map_err(|source| ArchiveError::Io {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("zkpoex-archive-{name}-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn unbundling_reproduces_the_outputs() {
+        let out_dir = temp_dir("out");
+        let artifacts = [
+            ("zkpoex.bincode", &b"proof"[..]),
+            ("zkpoex_chacha", b"chacha"),
+            ("zkpoex_tlock", b"tlock"),
+            ("zkpoex_fixture.json", b"{}"),
+        ];
+        for (name, contents) in artifacts {
+            fs::write(out_dir.join(name), contents).unwrap();
+        }
+        let manifest = Manifest::build(
+            &out_dir,
+            Some(42),
+            "0x00ab".to_string(),
+            artifacts.map(|(n, _)| n),
+        )
+        .unwrap();
+        fs::write(
+            out_dir.join("manifest.json"),
+            serde_json::to_string_pretty(&manifest).unwrap(),
+        )
+        .unwrap();
+        // The key must not travel with the disclosure.
+        fs::write(out_dir.join("zkpoex_enc_key"), [7; 32]).unwrap();
+
+        let archive = out_dir.join("disclosure.tar.zst");
+        assert_eq!(
+            bundle_outputs(&out_dir, "manifest.json", &archive).unwrap(),
+            manifest
+        );
+
+        let unpacked = temp_dir("unpacked");
+        assert_eq!(unbundle(&archive, &unpacked).unwrap(), manifest);
+        for name in artifacts
+            .map(|(name, _)| name)
+            .into_iter()
+            .chain(["manifest.json"])
+        {
+            assert_eq!(
+                fs::read(unpacked.join(name)).unwrap(),
+                fs::read(out_dir.join(name)).unwrap(),
+                "{name}"
+            );
+        }
+        assert!(!unpacked.join("zkpoex_enc_key").exists());
+
+        // Packing the same outputs again gives the same archive.
+        let again = out_dir.join("again.tar.zst");
+        bundle_outputs(&out_dir, "manifest.json", &again).unwrap();
+        assert_eq!(fs::read(&archive).unwrap(), fs::read(&again).unwrap());
+
+        fs::remove_dir_all(out_dir).unwrap();
+        fs::remove_dir_all(unpacked).unwrap();
+    }
+}
@@ -0,0 +1,343 @@
+//! Checking a fixture handed to a third party, who has neither the prover's outputs nor the key.
+//!
+//! Each check passes, fails or is skipped when the fixture or what came with it doesn't give enough
+//! to run it, e.g. the timelock can't be opened before its round is published. Whoever runs the
+//! audit decides how much a skipped check matters; only a failed one makes the fixture untrusted.
+
+use std::fmt;
+
+use crate::{
+    disclose::verify_tlock_against_commitment, tlock_header::tlock_inspect, PublicValues,
+    SP1ZkPoExProofFixture,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Pass,
+    Fail(String),
+    Skip(String),
+}
+
+/// The outcome of one check, see [`audit_fixture`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Check {
+    pub name: &'static str,
+    pub outcome: Outcome,
+}
+
+impl fmt::Display for Check {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.outcome {
+            Outcome::Pass => write!(f, "PASS {}", self.name),
+            Outcome::Fail(reason) => write!(f, "FAIL {}: {reason}", self.name),
+            Outcome::Skip(reason) => write!(f, "SKIP {}: {reason}", self.name),
+        }
+    }
+}
+
+/// Every check run on a fixture, in the order they ran.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditReport {
+    pub checks: Vec<Check>,
+}
+
+impl AuditReport {
+    /// Whether no check failed, skipped ones included.
+    pub fn passed(&self) -> bool {
+        !self
+            .checks
+            .iter()
+            .any(|check| matches!(check.outcome, Outcome::Fail(_)))
+    }
+
+    /// The outcome of the check called `name`, if it ran.
+    pub fn outcome(&self, name: &str) -> Option<&Outcome> {
+        self.checks
+            .iter()
+            .find(|check| check.name == name)
+            .map(|check| &check.outcome)
+    }
+}
+
+impl fmt::Display for AuditReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for check in &self.checks {
+            writeln!(f, "{check}")?;
+        }
+        f.write_str(if self.passed() { "PASS" } else { "FAIL" })
+    }
+}
+
+/// What the auditor has besides the fixture.
+#[derive(Debug, Clone, Default)]
+pub struct Evidence {
+    /// The vkey of the program the auditor trusts, derived from an ELF they built themselves.
+    pub vkey: Option<String>,
+    /// The public values the proof commits to, e.g. parsed with [`PublicValues::from_json`].
+    pub public_values: Option<PublicValues>,
+    /// The drand signature of the round the key is timelocked to, once it is published.
+    pub round_signature: Option<Vec<u8>>,
+}
+
+/// Runs every check `evidence` allows on `fixture`:
+///
+/// - `vkey`: the fixture's vkey is the one of the trusted program.
+/// - `tlock round`: the timelock is locked to the round the fixture names.
+/// - `public values`: the public values agree with the fixture, down to the calldata and settings.
+/// - `disclosure`: the timelock opens to a key hashing to the committed `key_hash`.
+pub fn audit_fixture(fixture: &SP1ZkPoExProofFixture, evidence: &Evidence) -> AuditReport {
+    let checks = vec![
+        Check {
+            name: "vkey",
+            outcome: check_vkey(fixture, evidence.vkey.as_deref()),
+        },
+        Check {
+            name: "tlock round",
+            outcome: check_tlock_rounds(fixture),
+        },
+        Check {
+            name: "public values",
+            outcome: check_public_values(fixture, evidence.public_values.as_ref()),
+        },
+        Check {
+            name: "disclosure",
+            outcome: check_disclosure(fixture, evidence),
+        },
+    ];
+    AuditReport { checks }
+}
+
+fn check_vkey(fixture: &SP1ZkPoExProofFixture, vkey: Option<&str>) -> Outcome {
+    let Some(vkey) = vkey else {
+        return Outcome::Skip("no trusted program to derive the vkey from".to_string());
+    };
+    let normalize = |vkey: &str| vkey.trim_start_matches("0x").to_ascii_lowercase();
+    if normalize(vkey) != normalize(&fixture.vkey) {
+        return Outcome::Fail(format!(
+            "fixture vkey {} is not the program vkey {vkey}",
+            fixture.vkey
+        ));
+    }
+    Outcome::Pass
+}
+
+/// Checks that `tlock_cipher` is locked to `round`, as the fixture claims.
+pub fn check_tlock_round(tlock_cipher: &[u8], round: u64) -> Result<(), String> {
+    match tlock_inspect(tlock_cipher) {
+        Ok(header) if header.round == round => Ok(()),
+        Ok(header) => Err(format!(
+            "tlock cipher is locked to round {}, fixture says {round}",
+            header.round
+        )),
+        Err(e) => Err(format!("malformed tlock cipher: {e}")),
+    }
+}
+
+fn check_tlock_rounds(fixture: &SP1ZkPoExProofFixture) -> Outcome {
+    let checked = match (fixture.round, fixture.tlock_shares.is_empty()) {
+        (Some(round), true) => check_tlock_round(&fixture.tlock_cipher, round),
+        (None, true) if !fixture.tlock_cipher.is_empty() => {
+            Err("fixture has a tlock cipher but no round".to_string())
+        }
+        (None, true) => return Outcome::Skip("the key isn't timelocked".to_string()),
+        (_, false) => fixture
+            .tlock_shares
+            .iter()
+            .enumerate()
+            .try_for_each(|(i, share)| {
+                check_tlock_round(&share.tlock_cipher, share.round)
+                    .map_err(|e| format!("key share {i}: {e}"))
+            }),
+    };
+    match checked {
+        Ok(()) => Outcome::Pass,
+        Err(e) => Outcome::Fail(e),
+    }
+}
+
+fn check_public_values(
+    fixture: &SP1ZkPoExProofFixture,
+    public_values: Option<&PublicValues>,
+) -> Outcome {
+    let Some(public_values) = public_values else {
+        return Outcome::Skip("no public values given".to_string());
+    };
+    let mismatched = [
+        ("before", public_values.before == fixture.before),
+        ("after", public_values.after == fixture.after),
+        (
+            "hashPrivateInputs",
+            public_values.hash_private_inputs == fixture.hash_private_inputs,
+        ),
+        (
+            "chachaCipher",
+            public_values.chacha_cipher == fixture.chacha_cipher,
+        ),
+    ]
+    .into_iter()
+    .filter_map(|(field, matches)| (!matches).then_some(field))
+    .collect::<Vec<_>>();
+    if !mismatched.is_empty() {
+        return Outcome::Fail(format!(
+            "the fixture's {} differ from the public values",
+            mismatched.join(", ")
+        ));
+    }
+    match fixture.check_inputs(public_values) {
+        Ok(()) => Outcome::Pass,
+        Err(e) => Outcome::Fail(e.to_string()),
+    }
+}
+
+fn check_disclosure(fixture: &SP1ZkPoExProofFixture, evidence: &Evidence) -> Outcome {
+    if !fixture.tlock_shares.is_empty() {
+        return Outcome::Skip("the key is split, recover it with the disclose command".to_string());
+    }
+    if fixture.round.is_none() {
+        return Outcome::Skip("the key isn't timelocked".to_string());
+    }
+    let Some(signature) = &evidence.round_signature else {
+        return Outcome::Skip("the round hasn't been published yet".to_string());
+    };
+    // The proof's commitment is what counts, the fixture's key is only as good as the fixture.
+    let committed = match &evidence.public_values {
+        Some(public_values) => public_values.key_hash.clone(),
+        None => return Outcome::Skip("no public values to take the key hash from".to_string()),
+    };
+    match verify_tlock_against_commitment(&fixture.tlock_cipher, signature, &committed) {
+        Ok(()) => Outcome::Pass,
+        Err(e) => Outcome::Fail(e.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        cipher::PayloadCipher,
+        digest::PrivateInputsDigest,
+        disclose::key_hash,
+        drand::{VECTOR_PUBLIC_KEY, VECTOR_ROUND, VECTOR_SIGNATURE},
+        inputs_hash,
+        proof::ProofMode,
+        settings::BlockchainSettings,
+        FIXTURE_SCHEMA_VERSION,
+    };
+
+    const VKEY: &str = "0x00ab";
+
+    /// A fixture timelocked to the vector round, with the public values it was proven with.
+    fn audited() -> (SP1ZkPoExProofFixture, Evidence) {
+        let key = [5; 32];
+        let mut tlock_cipher = vec![];
+        tlock::encrypt(
+            &mut tlock_cipher,
+            &key[..],
+            &hex::decode(VECTOR_PUBLIC_KEY).unwrap(),
+            VECTOR_ROUND,
+        )
+        .unwrap();
+        let blockchain_settings = BlockchainSettings::default().to_json();
+        let public_values = PublicValues {
+            before: "{\"balance\":1}".to_string(),
+            after: "{\"balance\":0}".to_string(),
+            hash_private_inputs: PrivateInputsDigest([3; 32]),
+            chacha_cipher: vec![4; 16],
+            key_hash: key_hash(&key),
+            inputs_hash: inputs_hash("63d9b770", &blockchain_settings),
+        };
+        let fixture = SP1ZkPoExProofFixture {
+            schema_version: FIXTURE_SCHEMA_VERSION,
+            key,
+            cipher: PayloadCipher::ChaCha20,
+            nonce: vec![2; 12],
+            round: Some(VECTOR_ROUND),
+            before: public_values.before.clone(),
+            after: public_values.after.clone(),
+            hash_private_inputs: public_values.hash_private_inputs,
+            chacha_cipher: public_values.chacha_cipher.clone(),
+            tlock_cipher,
+            tlock_shares: vec![],
+            calldata: "63d9b770".to_string(),
+            blockchain_settings,
+            vkey: VKEY.to_string(),
+            proof_mode: ProofMode::Compressed,
+            proof: None,
+            proving_duration_ms: None,
+            cycles: None,
+            report: None,
+            policy_note: None,
+        };
+        let evidence = Evidence {
+            // Vkeys differing only in case are the same.
+            vkey: Some("0x00AB".to_string()),
+            public_values: Some(public_values),
+            round_signature: Some(hex::decode(VECTOR_SIGNATURE).unwrap()),
+        };
+        (fixture, evidence)
+    }
+
+    fn failed(report: &AuditReport) -> Vec<&'static str> {
+        report
+            .checks
+            .iter()
+            .filter(|check| matches!(check.outcome, Outcome::Fail(_)))
+            .map(|check| check.name)
+            .collect()
+    }
+
+    #[test]
+    fn untampered_fixture_passes_every_check() {
+        let (fixture, evidence) = audited();
+        let report = audit_fixture(&fixture, &evidence);
+        assert!(report.passed(), "{report}");
+        assert!(report
+            .checks
+            .iter()
+            .all(|check| check.outcome == Outcome::Pass));
+    }
+
+    #[test]
+    fn checks_without_evidence_are_skipped_not_failed() {
+        let (fixture, _) = audited();
+        let report = audit_fixture(&fixture, &Evidence::default());
+        assert!(report.passed(), "{report}");
+        assert!(matches!(report.outcome("vkey"), Some(Outcome::Skip(_))));
+        assert_eq!(report.outcome("tlock round"), Some(&Outcome::Pass));
+        assert!(matches!(
+            report.outcome("disclosure"),
+            Some(Outcome::Skip(_))
+        ));
+    }
+
+    #[test]
+    fn tampered_fixtures_fail_the_check_they_break() {
+        let tampered: [(&str, fn(&mut SP1ZkPoExProofFixture)); 5] = [
+            ("vkey", |f| f.vkey = "0x00ac".to_string()),
+            ("tlock round", |f| f.round = Some(VECTOR_ROUND + 1)),
+            ("public values", |f| f.after = "{\"balance\":1}".to_string()),
+            ("public values", |f| f.calldata = "63d9b771".to_string()),
+            ("public values", |f| f.chacha_cipher = vec![5; 16]),
+        ];
+        for (check, tamper) in tampered {
+            let (mut fixture, evidence) = audited();
+            tamper(&mut fixture);
+            let report = audit_fixture(&fixture, &evidence);
+            assert!(!report.passed());
+            assert!(failed(&report).contains(&check), "{report}");
+        }
+    }
+
+    #[test]
+    fn timelock_opening_to_another_key_fails_disclosure() {
+        let (fixture, mut evidence) = audited();
+        let public_values = evidence.public_values.as_mut().unwrap();
+        public_values.key_hash = key_hash(&[6; 32]);
+
+        let report = audit_fixture(&fixture, &evidence);
+        let Some(Outcome::Fail(reason)) = report.outcome("disclosure") else {
+            panic!("expected disclosure to fail:\n{report}");
+        };
+        assert!(reason.contains("proof commits to"), "{reason}");
+    }
+}
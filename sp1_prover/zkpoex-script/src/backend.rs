@@ -0,0 +1,200 @@
+//! The proof system a [`ZkPoExProver`](crate::ZkPoExProver) runs the program on.
+//!
+//! [`ProverClient`] executes and proves for real. [`MockBackend`] hands back canned public values
+//! instead, so everything around the proof (keys, timelocks, fixtures) can be tested quickly and
+//! deterministically without a prover.
+
+use std::path::Path;
+
+use sp1_sdk::{HashableKey, ProverClient, SP1ProvingKey, SP1Stdin, SP1VerifyingKey};
+
+use crate::{proof::ProofMode, report::ProgramReport, setup::setup_cached, ZkPoExProof};
+
+/// What the prover needs of a proof, whichever backend produced it.
+pub trait BackendProof {
+    fn mode(&self) -> ProofMode;
+
+    /// The public values the proof commits to, as the program wrote them.
+    fn public_value_bytes(&self) -> &[u8];
+
+    /// The encoded proof for the Solidity verifier, if it can be verified on-chain.
+    fn bytes(&self) -> Option<String>;
+}
+
+pub trait ProofBackend {
+    type ProvingKey;
+    type VerifyingKey;
+    type Proof: BackendProof;
+
+    /// Generates the keys for `elf`, loading them from `cache_dir` when given and already
+    /// generated for it.
+    fn setup(&self, elf: &[u8], cache_dir: Option<&Path>)
+        -> (Self::ProvingKey, Self::VerifyingKey);
+
+    /// The vkey as the fixture records it.
+    fn vkey(&self, vk: &Self::VerifyingKey) -> String;
+
+    /// Runs `elf` on `stdin` without proving it, returning its public values and what it ran.
+    fn execute(&self, elf: &[u8], stdin: SP1Stdin) -> anyhow::Result<(Vec<u8>, ProgramReport)>;
+
+    fn prove_compressed(
+        &self,
+        pk: &Self::ProvingKey,
+        stdin: SP1Stdin,
+    ) -> anyhow::Result<Self::Proof>;
+
+    fn prove_groth16(&self, pk: &Self::ProvingKey, stdin: SP1Stdin) -> anyhow::Result<Self::Proof>;
+
+    fn prove_plonk(&self, pk: &Self::ProvingKey, stdin: SP1Stdin) -> anyhow::Result<Self::Proof>;
+
+    fn verify(&self, proof: &Self::Proof, vk: &Self::VerifyingKey) -> anyhow::Result<()>;
+
+    /// Proves `stdin` in `mode`.
+    fn prove(
+        &self,
+        pk: &Self::ProvingKey,
+        stdin: SP1Stdin,
+        mode: ProofMode,
+    ) -> anyhow::Result<Self::Proof> {
+        match mode {
+            ProofMode::Compressed => self.prove_compressed(pk, stdin),
+            ProofMode::Groth16 => self.prove_groth16(pk, stdin),
+            ProofMode::Plonk => self.prove_plonk(pk, stdin),
+        }
+    }
+}
+
+impl BackendProof for ZkPoExProof {
+    fn mode(&self) -> ProofMode {
+        ZkPoExProof::mode(self)
+    }
+
+    fn public_value_bytes(&self) -> &[u8] {
+        self.public_values().as_slice()
+    }
+
+    fn bytes(&self) -> Option<String> {
+        ZkPoExProof::bytes(self)
+    }
+}
+
+impl ProofBackend for ProverClient {
+    type ProvingKey = SP1ProvingKey;
+    type VerifyingKey = SP1VerifyingKey;
+    type Proof = ZkPoExProof;
+
+    fn setup(&self, elf: &[u8], cache_dir: Option<&Path>) -> (SP1ProvingKey, SP1VerifyingKey) {
+        match cache_dir {
+            Some(cache_dir) => setup_cached(self, elf, cache_dir),
+            None => ProverClient::setup(self, elf),
+        }
+    }
+
+    fn vkey(&self, vk: &SP1VerifyingKey) -> String {
+        vk.bytes32()
+    }
+
+    fn execute(&self, elf: &[u8], stdin: SP1Stdin) -> anyhow::Result<(Vec<u8>, ProgramReport)> {
+        let (public_values, report) = ProverClient::execute(self, elf, stdin)?;
+        Ok((
+            public_values.as_slice().to_vec(),
+            ProgramReport::of(&report),
+        ))
+    }
+
+    fn prove_compressed(&self, pk: &SP1ProvingKey, stdin: SP1Stdin) -> anyhow::Result<ZkPoExProof> {
+        Ok(ZkPoExProof::Compressed(ProverClient::prove_compressed(
+            self, pk, stdin,
+        )?))
+    }
+
+    fn prove_groth16(&self, pk: &SP1ProvingKey, stdin: SP1Stdin) -> anyhow::Result<ZkPoExProof> {
+        Ok(ZkPoExProof::Groth16(ProverClient::prove_groth16(
+            self, pk, stdin,
+        )?))
+    }
+
+    fn prove_plonk(&self, pk: &SP1ProvingKey, stdin: SP1Stdin) -> anyhow::Result<ZkPoExProof> {
+        Ok(ZkPoExProof::Plonk(ProverClient::prove_plonk(
+            self, pk, stdin,
+        )?))
+    }
+
+    fn verify(&self, proof: &ZkPoExProof, vk: &SP1VerifyingKey) -> anyhow::Result<()> {
+        proof.verify(self, vk)
+    }
+}
+
+/// A backend that runs nothing: executing and proving hand back `public_values` whatever the
+/// input, and every proof verifies.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MockBackend {
+    /// The encoded public values, see [`crate::PublicValues::encode`].
+    pub public_values: Vec<u8>,
+    pub report: ProgramReport,
+    pub vkey: String,
+}
+
+/// A proof made up by [`MockBackend`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MockProof {
+    pub mode: ProofMode,
+    pub public_values: Vec<u8>,
+}
+
+impl BackendProof for MockProof {
+    fn mode(&self) -> ProofMode {
+        self.mode
+    }
+
+    fn public_value_bytes(&self) -> &[u8] {
+        &self.public_values
+    }
+
+    fn bytes(&self) -> Option<String> {
+        (self.mode != ProofMode::Compressed).then(|| "0x".to_string())
+    }
+}
+
+impl MockBackend {
+    fn proof(&self, mode: ProofMode) -> anyhow::Result<MockProof> {
+        Ok(MockProof {
+            mode,
+            public_values: self.public_values.clone(),
+        })
+    }
+}
+
+impl ProofBackend for MockBackend {
+    type ProvingKey = ();
+    type VerifyingKey = String;
+    type Proof = MockProof;
+
+    fn setup(&self, _: &[u8], _: Option<&Path>) -> ((), String) {
+        ((), self.vkey.clone())
+    }
+
+    fn vkey(&self, vk: &String) -> String {
+        vk.clone()
+    }
+
+    fn execute(&self, _: &[u8], _: SP1Stdin) -> anyhow::Result<(Vec<u8>, ProgramReport)> {
+        Ok((self.public_values.clone(), self.report.clone()))
+    }
+
+    fn prove_compressed(&self, _: &(), _: SP1Stdin) -> anyhow::Result<MockProof> {
+        self.proof(ProofMode::Compressed)
+    }
+
+    fn prove_groth16(&self, _: &(), _: SP1Stdin) -> anyhow::Result<MockProof> {
+        self.proof(ProofMode::Groth16)
+    }
+
+    fn prove_plonk(&self, _: &(), _: SP1Stdin) -> anyhow::Result<MockProof> {
+        self.proof(ProofMode::Plonk)
+    }
+
+    fn verify(&self, _: &MockProof, _: &String) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,165 @@
+//! Wraps a saved compressed zkpoex proof into a Groth16 one the Solidity verifier accepts, and
+//! updates its fixture, without executing the program or proving the STARK again.
+//!
+//! You can run this script using the following command:
+//! ```shell
+//! RUST_LOG=info cargo run --package zkpoex-script --bin convert --release
+//! ```
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use clap::Parser;
+use zkpoex_script::{
+    client::{prover_client, ProverError},
+    convert_to_groth16,
+    manifest::{Manifest, ManifestError},
+    output::{
+        default_fixtures_dir, fixture_file_name, fixture_name_arg, resolve_dir, write_atomic,
+        write_atomic_with, DEFAULT_OUT_DIR,
+    },
+    proof::{ProofMode, ZkPoExProof},
+    FixtureError, ProveError, SP1ZkPoExProofFixture,
+};
+
+/// The arguments for the convert command.
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct ConvertArgs {
+    /// The directory the prove command wrote the compressed proof and its fixture to.
+    #[clap(long, default_value = DEFAULT_OUT_DIR, value_parser = resolve_dir)]
+    out_dir: PathBuf,
+
+    /// Where to write the fixture the Solidity tests load.
+    #[clap(long, default_value_os_t = default_fixtures_dir(), value_parser = resolve_dir)]
+    fixtures_dir: PathBuf,
+
+    /// The name the prove command was given with `--fixture-name`, if any.
+    #[clap(long, value_parser = fixture_name_arg)]
+    fixture_name: Option<String>,
+}
+
+/// Errors that can occur while converting a proof.
+#[derive(Debug, thiserror::Error)]
+enum ConvertError {
+    #[error("failed to load compressed proof from {}: {source}", .path.display())]
+    LoadProof {
+        path: PathBuf,
+        source: anyhow::Error,
+    },
+    #[error("{}: {source}", .path.display())]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("{}: {source}", .path.display())]
+    Fixture { path: PathBuf, source: FixtureError },
+    #[error("failed to parse {}: {source}", .path.display())]
+    ParseManifest {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+    #[error(transparent)]
+    Manifest(#[from] ManifestError),
+    #[error(transparent)]
+    Prover(#[from] ProverError),
+    #[error(transparent)]
+    Prove(#[from] ProveError),
+}
+
+fn read(path: &Path) -> Result<String, ConvertError> {
+    fs::read_to_string(path).map_err(|source| ConvertError::Io {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+fn write(path: PathBuf, contents: &str) -> Result<(), ConvertError> {
+    write_atomic(&path, contents).map_err(|source| ConvertError::Io { path, source })
+}
+
+fn run(args: &ConvertArgs) -> Result<(), ConvertError> {
+    let proof_path = args.out_dir.join("zkpoex.bincode");
+    let proof = ZkPoExProof::load(ProofMode::Compressed, &proof_path).map_err(|source| {
+        ConvertError::LoadProof {
+            path: proof_path.clone(),
+            source,
+        }
+    })?;
+    let fixture_path = args.out_dir.join("zkpoex_fixture.json");
+    let fixture = SP1ZkPoExProofFixture::from_json(&read(&fixture_path)?).map_err(|source| {
+        ConvertError::Fixture {
+            path: fixture_path,
+            source,
+        }
+    })?;
+
+    let (fixture, proof) = convert_to_groth16(&prover_client()?, proof, &fixture)?;
+
+    write_atomic_with(&proof_path, |staging| {
+        proof.save(staging).map_err(std::io::Error::other)
+    })
+    .map_err(|source| ConvertError::Io {
+        path: proof_path,
+        source,
+    })?;
+    let fixture_json = fixture.to_json();
+    write(args.out_dir.join("zkpoex_fixture.json"), &fixture_json)?;
+    fs::create_dir_all(&args.fixtures_dir).map_err(|source| ConvertError::Io {
+        path: args.fixtures_dir.clone(),
+        source,
+    })?;
+    write(
+        args.fixtures_dir
+            .join(fixture_file_name(args.fixture_name.as_deref(), "")),
+        &fixture_json,
+    )?;
+
+    // The proof and fixture changed, so a manifest listing them has to be rehashed.
+    let manifest_path = args.out_dir.join("manifest.json");
+    if manifest_path.is_file() {
+        let manifest: Manifest =
+            serde_json::from_str(&read(&manifest_path)?).map_err(|source| {
+                ConvertError::ParseManifest {
+                    path: manifest_path.clone(),
+                    source,
+                }
+            })?;
+        let manifest = Manifest::build(
+            &args.out_dir,
+            manifest.round,
+            manifest.vkey,
+            manifest.artifacts.into_iter().map(|artifact| artifact.path),
+        )?;
+        write(
+            manifest_path,
+            &serde_json::to_string_pretty(&manifest).expect("manifest serializes to json"),
+        )?;
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    // Setup the logger.
+    sp1_sdk::utils::setup_logger();
+
+    // Parse the command line arguments.
+    let args = ConvertArgs::parse();
+
+    match run(&args) {
+        Ok(()) => {
+            tracing::info!(
+                "Converted {} to groth16",
+                args.out_dir.join("zkpoex.bincode").display()
+            );
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("Error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,99 @@
+//! Lists the drand rounds around now and when each is disclosable, to line a disclosure round up
+//! with an external deadline.
+//!
+//! You can run this script using the following command:
+//! ```shell
+//! cargo run --package zkpoex-script --bin drand-info --release -- --around +/-5
+//! ```
+
+use std::time::SystemTime;
+
+use clap::Parser;
+use zkpoex_script::{
+    drand::{connect_drand, ConnectError, DrandChain, DEFAULT_DRAND_URL},
+    drand_round::{round_at, rounds_around, RoundError},
+    logging::{setup_logging, LogFormat},
+};
+
+/// The arguments for the drand-info command.
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct DrandInfoArgs {
+    #[clap(
+        long,
+        default_value = "5",
+        value_parser = parse_around,
+        help = "how many rounds to list on either side of the current one, e.g. +/-5"
+    )]
+    around: u64,
+
+    #[clap(long, default_value = DEFAULT_DRAND_URL, help = "drand relay to query")]
+    drand_url: String,
+
+    #[clap(long, help = "expected drand chain hash, appended to --drand-url")]
+    drand_chain_hash: Option<String>,
+}
+
+/// Parses `5` or `+/-5`.
+fn parse_around(s: &str) -> Result<u64, std::num::ParseIntError> {
+    s.strip_prefix("+/-").unwrap_or(s).parse()
+}
+
+/// Errors that can occur while listing drand rounds.
+#[derive(Debug, thiserror::Error)]
+enum DrandInfoError {
+    #[error(transparent)]
+    Connect(#[from] ConnectError),
+    #[error(transparent)]
+    Round(#[from] RoundError),
+}
+
+fn main() -> Result<(), DrandInfoError> {
+    // Setup the logger.
+    setup_logging(LogFormat::Pretty);
+
+    // Parse the command line arguments.
+    let args = DrandInfoArgs::parse();
+
+    let chain = DrandChain {
+        url: args.drand_url,
+        chain_hash: args.drand_chain_hash,
+        ..Default::default()
+    };
+    let (_, info) = connect_drand(&chain)?;
+
+    let now = SystemTime::now();
+    println!("Chain: {}", hex::encode(info.hash()));
+    println!("Period: {}s", info.period());
+    println!("Now: {}", humantime::format_rfc3339_seconds(now));
+
+    let current = round_at(&info, now)?;
+    for (round, at) in rounds_around(&info, now, args.around)? {
+        let marker = if round == current { "  <- current" } else { "" };
+        println!(
+            "{round:>12}  disclosable by {}{marker}",
+            humantime::format_rfc3339_seconds(at)
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn around_accepts_a_plus_minus_prefix() {
+        for around in ["+/-7", "7"] {
+            let args = DrandInfoArgs::try_parse_from(["drand-info", "--around", around]).unwrap();
+            assert_eq!(args.around, 7);
+        }
+        assert!(DrandInfoArgs::try_parse_from(["drand-info", "--around", "-7"]).is_err());
+        assert_eq!(
+            DrandInfoArgs::try_parse_from(["drand-info"])
+                .unwrap()
+                .around,
+            5
+        );
+    }
+}
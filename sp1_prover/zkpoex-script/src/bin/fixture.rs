@@ -0,0 +1,127 @@
+//! Rewrites the fixture of a saved zkpoex proof without re-proving, e.g. after the fixture format
+//! changed.
+//!
+//! You can run this script using the following command:
+//! ```shell
+//! RUST_LOG=info cargo run --package zkpoex-script --bin fixture --release -- --proof-mode groth16
+//! ```
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use clap::Parser;
+use sp1_sdk::{HashableKey, ProverClient};
+use zkpoex_script::{
+    bundle::{BundleError, CipherBundle},
+    elf::{program_elf, ElfError},
+    output::{default_fixtures_dir, resolve_dir, write_atomic, DEFAULT_OUT_DIR},
+    proof::{ProofMode, ZkPoExProof},
+    regenerate::{regenerate_fixture, RegenerateError},
+};
+
+/// The arguments for the fixture command.
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct FixtureArgs {
+    /// The directory the prove command wrote the proof and cipher files to.
+    #[clap(long, default_value = DEFAULT_OUT_DIR, value_parser = resolve_dir)]
+    out_dir: PathBuf,
+
+    #[clap(long, value_enum, default_value_t = ProofMode::default(), help = "mode the proof was produced in")]
+    proof_mode: ProofMode,
+
+    /// Where to write the fixture the Solidity tests load.
+    #[clap(long, default_value_os_t = default_fixtures_dir(), value_parser = resolve_dir)]
+    fixtures_dir: PathBuf,
+
+    #[clap(
+        long,
+        help = "the ELF the proof was made with, if not the embedded one"
+    )]
+    elf: Option<PathBuf>,
+}
+
+/// Errors that can occur while regenerating a fixture.
+#[derive(Debug, thiserror::Error)]
+enum FixtureError {
+    #[error("failed to load proof from {}: {source}", .path.display())]
+    LoadProof {
+        path: PathBuf,
+        source: anyhow::Error,
+    },
+    #[error("{}: {source}", .path.display())]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("{}: {source}", .path.display())]
+    Bundle { path: PathBuf, source: BundleError },
+    #[error(transparent)]
+    Regenerate(#[from] RegenerateError),
+    #[error(transparent)]
+    Elf(#[from] ElfError),
+}
+
+fn read_bundle(path: PathBuf) -> Result<CipherBundle, FixtureError> {
+    let bytes = fs::read(&path).map_err(|source| FixtureError::Io {
+        path: path.clone(),
+        source,
+    })?;
+    CipherBundle::from_bytes(&bytes).map_err(|source| FixtureError::Bundle { path, source })
+}
+
+fn write(dir: &Path, contents: &str) -> Result<(), FixtureError> {
+    let io_error = |path: &Path| {
+        let path = path.to_path_buf();
+        move |source| FixtureError::Io { path, source }
+    };
+    fs::create_dir_all(dir).map_err(io_error(dir))?;
+    let path = dir.join("zkpoex_fixture.json");
+    write_atomic(&path, contents).map_err(io_error(&path))
+}
+
+fn run(args: &FixtureArgs) -> Result<(), FixtureError> {
+    let proof_path = args.out_dir.join("zkpoex.bincode");
+    let proof = ZkPoExProof::load(args.proof_mode, &proof_path).map_err(|source| {
+        FixtureError::LoadProof {
+            path: proof_path,
+            source,
+        }
+    })?;
+    let chacha = read_bundle(args.out_dir.join("zkpoex_chacha"))?;
+    let tlock = read_bundle(args.out_dir.join("zkpoex_tlock"))?;
+
+    // The vkey only depends on the program, so it is recomputed rather than trusted from the old
+    // fixture.
+    let (_, vk) = ProverClient::new().setup(&program_elf(args.elf.as_deref())?);
+
+    let fixture = regenerate_fixture(&proof, &chacha, &tlock, vk.bytes32().to_string())?;
+    let fixture_json = fixture.to_json();
+    write(&args.out_dir, &fixture_json)?;
+    write(&args.fixtures_dir, &fixture_json)
+}
+
+fn main() -> ExitCode {
+    // Setup the logger.
+    sp1_sdk::utils::setup_logger();
+
+    // Parse the command line arguments.
+    let args = FixtureArgs::parse();
+
+    match run(&args) {
+        Ok(()) => {
+            tracing::info!(
+                "Wrote {}",
+                args.fixtures_dir.join("zkpoex_fixture.json").display()
+            );
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("Error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -8,219 +8,1867 @@
 
 use std::{
     fs,
-    ops::Add,
-    path::PathBuf,
+    path::{Path, PathBuf},
+    sync::Arc,
     time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
-use alloy_sol_types::{sol, SolType};
-use clap::Parser;
+use clap::{error::ErrorKind, CommandFactory, Parser};
 use drand_core::chain::ChainInfo;
-use rand::Rng;
-use serde::{Deserialize, Serialize};
-use sp1_sdk::{HashableKey, ProverClient, SP1Stdin};
-
-/// The ELF (executable and linkable format) file for the Succinct RISC-V zkVM.
-///
-/// This file is generated by running `cargo prove build` inside the `program` directory.
-pub const ZKPOEX_ELF: &[u8] = include_bytes!("../../../zk-poex/elf/riscv32im-succinct-zkvm-elf");
+use primitive_types::U256;
+use sp1_sdk::HashableKey;
+use zkpoex_script::{
+    archive::bundle_outputs,
+    bundle::CipherBundle,
+    calldata::{calldata_arg, parse_calldata_file},
+    cipher::{parse_key, parse_nonce, PayloadCipher},
+    client::{ProverConfig, ProverKind},
+    config::{config_path, read_config_args},
+    delay::{parse_delay, parse_disclose_at, DisclosureDelay},
+    disclosure_round,
+    drand::{BeaconScheme, DrandChain, DrandSource, RetryPolicy, DEFAULT_DRAND_URL},
+    drand_cache::{default_drand_cache_dir, DrandCache},
+    drand_round::{block_instant, round_to_time},
+    estimate::{estimate_zkpoex, CostModel, DEFAULT_CYCLES_PER_SECOND},
+    execute_zkpoex,
+    logging::{setup_logging, LogFormat},
+    manifest::Manifest,
+    note::policy_note,
+    output::{
+        abandon_staged, default_fixtures_dir, fixture_file_name, fixture_name_arg, resolve_dir,
+        write_atomic, write_atomic_with, DEFAULT_OUT_DIR, ENC_KEY_FILE,
+    },
+    presets::ChainPreset,
+    progress::{PhaseEvent, ProgressFn},
+    proof::{ProofMode, ZkPoExProof},
+    public_values_json,
+    settings::{BlockchainSettings, DEFAULT_BLOCKCHAIN_SETTINGS},
+    setup::default_setup_cache_dir,
+    split::{KeySplit, SplitTimelock},
+    state::diff_states,
+    with_timeout, Disclosure, Execution, ProveError, ProveOptions, SP1ZkPoExProofFixture,
+    ZkPoExProver,
+};
 
 /// The arguments for the prove command.
+///
+/// Every flag can also be set through the environment variable named after it with a `ZKPOEX_`
+/// prefix, e.g. `ZKPOEX_CALLDATA` or `ZKPOEX_DURATION`, switches with `true` or `false`. A flag
+/// given on the command line wins over the same key in the `--config` file, which wins over the
+/// environment variable, which wins over the default.
 #[derive(Parser, Debug)]
-#[clap(author, version, about, long_about = None)]
+#[clap(author, version, about, long_about = None, args_override_self = true)]
 struct ProveArgs {
-    #[clap(long)]
-    calldata: String,
     #[clap(
         long,
-        default_value = r#"
-    {
-        "gas_price": "0",
-        "origin": "0x0000000000000000000000000000000000000000",
-        "block_hashes": "[]",
-        "block_number": "0",
-        "block_coinbase": "0x0000000000000000000000000000000000000000",
-        "block_timestamp": "0",
-        "block_difficulty": "0",
-        "block_gas_limit": "0",
-        "chain_id": "1",
-        "block_base_fee_per_gas": "0"
-    }
-"#
+        env = "ZKPOEX_CONFIG",
+        help = "read arguments from a TOML file, keyed by flag name; flags given here take precedence"
     )]
-    blockchain_settings: String,
+    config: Option<PathBuf>,
+
+    #[clap(long, env = "ZKPOEX_CALLDATA", value_parser = calldata_arg, required_unless_present = "calldata_file")]
+    calldata: Option<String>,
+
+    #[clap(
+        long,
+        env = "ZKPOEX_CALLDATA_FILE",
+        conflicts_with = "calldata",
+        help = "read calldata from a file: raw bytes, hex, or several hex calldatas one per line or as a JSON array"
+    )]
+    calldata_file: Option<PathBuf>,
+    #[clap(
+        long,
+        env = "ZKPOEX_BLOCKCHAIN_SETTINGS",
+        default_value = DEFAULT_BLOCKCHAIN_SETTINGS,
+        conflicts_with = "blockchain_settings_file"
+    )]
+    blockchain_settings: BlockchainSettings,
+
+    #[clap(
+        long,
+        env = "ZKPOEX_BLOCKCHAIN_SETTINGS_FILE",
+        help = "read the blockchain settings JSON from a file"
+    )]
+    blockchain_settings_file: Option<PathBuf>,
+
+    #[clap(
+        long,
+        env = "ZKPOEX_CHAIN_PRESET",
+        value_enum,
+        conflicts_with_all = ["blockchain_settings", "blockchain_settings_file"],
+        help = "use the settings of a well-known chain"
+    )]
+    chain_preset: Option<ChainPreset>,
+
+    #[clap(
+        long,
+        env = "ZKPOEX_CHAIN_ID",
+        help = "override the chain id of the blockchain settings"
+    )]
+    chain_id: Option<u64>,
+
+    #[clap(
+        long,
+        env = "ZKPOEX_BLOCK_GAS_LIMIT",
+        help = "override the block gas limit of the blockchain settings"
+    )]
+    block_gas_limit: Option<u64>,
+
+    #[clap(
+        long,
+        env = "ZKPOEX_BLOCK_BASE_FEE_PER_GAS",
+        value_parser = |s: &str| U256::from_dec_str(s),
+        help = "override the block base fee of the blockchain settings, in wei"
+    )]
+    block_base_fee_per_gas: Option<U256>,
 
     #[clap(
         short,
         long,
-        help = "disclose after (y/w/d/h/m/s/ms)",
-        default_value = "90d"
+        env = "ZKPOEX_DURATION",
+        help = "disclose after (y/mo/w/d/h/m/s/ms)",
+        default_value = "90d",
+        value_parser = parse_delay,
+        conflicts_with_all = ["round_number", "disclose_at", "disclose_at_block"]
+    )]
+    pub duration: Option<DisclosureDelay>,
+
+    #[clap(
+        long,
+        env = "ZKPOEX_ROUND_NUMBER",
+        help = "disclose at this drand round"
+    )]
+    pub round_number: Option<u64>,
+
+    #[clap(
+        long,
+        env = "ZKPOEX_DISCLOSE_AT",
+        value_parser = parse_disclose_at,
+        conflicts_with = "round_number",
+        help = "disclose at this RFC 3339 instant, e.g. 2025-01-31T12:00:00Z"
+    )]
+    pub disclose_at: Option<SystemTime>,
+
+    #[clap(
+        long,
+        env = "ZKPOEX_DISCLOSE_AT_BLOCK",
+        conflicts_with_all = ["round_number", "disclose_at"],
+        requires = "seconds_per_block",
+        help = "disclose around when this block of the blockchain settings' chain is expected"
+    )]
+    pub disclose_at_block: Option<u64>,
+
+    #[clap(
+        long,
+        env = "ZKPOEX_SECONDS_PER_BLOCK",
+        requires = "disclose_at_block",
+        value_parser = clap::value_parser!(u64).range(1..),
+        help = "average block time used to project --disclose-at-block, e.g. 12 for mainnet"
     )]
-    pub duration: Option<humantime::Duration>,
+    pub seconds_per_block: Option<u64>,
+
+    /// Skip drand and the timelock, writing only the ChaCha ciphertext. The key is then disclosed
+    /// by handing it over, e.g. through the ecdh script.
+    #[clap(
+        long,
+        env = "ZKPOEX_NO_TLOCK",
+        conflicts_with_all = ["round_number", "disclose_at", "disclose_at_block", "split", "dry_run"]
+    )]
+    pub no_tlock: bool,
+
+    #[clap(long, env = "ZKPOEX_DRAND_URL", default_value = DEFAULT_DRAND_URL, help = "drand relay to timelock against")]
+    pub drand_url: String,
+
+    #[clap(
+        long,
+        env = "ZKPOEX_DRAND_CHAIN_HASH",
+        help = "expected drand chain hash, appended to --drand-url"
+    )]
+    pub drand_chain_hash: Option<String>,
+
+    #[clap(long, env = "ZKPOEX_DRAND_RETRIES", default_value_t = RetryPolicy::default().retries, help = "retries for a failing drand request")]
+    pub drand_retries: u32,
+
+    #[clap(
+        long,
+        env = "ZKPOEX_DRAND_TIMEOUT",
+        default_value = "30s",
+        value_parser = humantime::parse_duration,
+        help = "give up on a drand request after this long, retries included"
+    )]
+    pub drand_timeout: Duration,
+
+    #[clap(long, env = "ZKPOEX_DRAND_BEACON_CACHE", default_value_os_t = default_drand_cache_dir(), help = "where to cache the drand chain info")]
+    pub drand_beacon_cache: PathBuf,
+
+    #[clap(
+        long,
+        env = "ZKPOEX_REFRESH_DRAND",
+        help = "fetch the drand chain info even if it is cached"
+    )]
+    pub refresh_drand: bool,
+
+    #[clap(long, env = "ZKPOEX_PROOF_MODE", value_enum, default_value_t = ProofMode::Compressed)]
+    pub proof_mode: ProofMode,
+
+    /// Prove with the program at this ELF rather than the one embedded at build time, to iterate
+    /// on the program without rebuilding the prover.
+    #[clap(long, env = "ZKPOEX_ELF")]
+    pub elf: Option<PathBuf>,
+
+    #[clap(
+        long,
+        env = "ZKPOEX_TIMEOUT",
+        value_parser = humantime::parse_duration,
+        help = "give up on the whole run after this long, drand and the prover network included"
+    )]
+    pub timeout: Option<Duration>,
+
+    #[clap(
+        long,
+        env = "ZKPOEX_ONCHAIN",
+        conflicts_with = "proof_mode",
+        help = "produce a Groth16 proof the Solidity verifier accepts"
+    )]
+    pub onchain: bool,
+
+    #[clap(
+        long,
+        env = "ZKPOEX_VERIFY_AFTER_PROVE",
+        help = "verify each proof before writing it, the default for groth16 proofs"
+    )]
+    pub verify_after_prove: bool,
+
+    #[clap(
+        long,
+        env = "ZKPOEX_NO_VERIFY_AFTER_PROVE",
+        conflicts_with = "verify_after_prove",
+        help = "write groth16 proofs without verifying them first"
+    )]
+    pub no_verify_after_prove: bool,
+
+    #[clap(long, env = "ZKPOEX_CIPHER", value_enum, default_value_t = PayloadCipher::ChaCha20, help = "cipher the exploit is encrypted with")]
+    pub cipher: PayloadCipher,
+
+    #[clap(
+        long,
+        env = "ZKPOEX_SPLIT",
+        requires = "share_round",
+        help = "split the key into k-of-n Shamir shares, each timelocked to its --share-round"
+    )]
+    pub split: Option<KeySplit>,
+
+    #[clap(
+        long,
+        env = "ZKPOEX_SHARE_ROUND",
+        requires = "split",
+        help = "the round to timelock the next key share to, once per share"
+    )]
+    pub share_round: Vec<u64>,
+
+    #[clap(
+        long,
+        env = "ZKPOEX_MAX_CYCLES",
+        help = "execute the program first and don't prove it if it runs more cycles than this"
+    )]
+    pub max_cycles: Option<u64>,
+
+    #[clap(
+        long,
+        env = "ZKPOEX_CYCLES_PER_SECOND",
+        default_value_t = DEFAULT_CYCLES_PER_SECOND,
+        help = "how many cycles this machine proves per second, which --dry-run estimates the proving time from"
+    )]
+    pub cycles_per_second: u64,
+
+    #[clap(long, env = "ZKPOEX_SETUP_CACHE_DIR", default_value_os_t = default_setup_cache_dir(), help = "where to cache the proving key")]
+    pub setup_cache_dir: PathBuf,
+
+    #[clap(
+        long,
+        env = "ZKPOEX_NO_CACHE",
+        help = "regenerate the proving key instead of using the cache"
+    )]
+    pub no_cache: bool,
+
+    #[clap(
+        long,
+        env = "ZKPOEX_DRY_RUN",
+        help = "only report the disclosure round and estimate the proving cost, without proving"
+    )]
+    pub dry_run: bool,
+
+    #[clap(
+        long,
+        env = "ZKPOEX_SKIP_IF_EXISTS",
+        help = "don't prove a calldata whose outputs a previous run already wrote for the same program and inputs"
+    )]
+    pub skip_if_exists: bool,
+
+    #[clap(
+        long,
+        env = "ZKPOEX_EXECUTE_ONLY",
+        conflicts_with = "dry_run",
+        help = "run the program and print its public values, without proving or writing anything"
+    )]
+    pub execute_only: bool,
+
+    #[clap(
+        long,
+        env = "ZKPOEX_REPORT",
+        requires = "execute_only",
+        help = "print how often the program ran every opcode and syscall"
+    )]
+    pub report: bool,
+
+    /// Write the execution report as JSON to this file. A batch writes one per calldata, the index
+    /// suffixed to the file name.
+    #[clap(long, env = "ZKPOEX_REPORT_JSON", requires = "execute_only")]
+    pub report_json: Option<PathBuf>,
+
+    /// Encrypt with this 32 byte hex key instead of a random one, e.g. to pass the same key to the
+    /// ecdh script's `--enc-key`.
+    #[clap(long, env = "ZKPOEX_ENC_KEY", value_parser = parse_key)]
+    pub enc_key: Option<[u8; 32]>,
+
+    /// Encrypt with this hex nonce instead of a random one, 12 bytes or 24 for xchacha20, so the
+    /// outputs can be reproduced. Reusing a nonce with the same key reveals the XOR of both
+    /// exploits, so only ever pass one together with a fresh `--enc-key`.
+    #[clap(long, env = "ZKPOEX_NONCE", value_parser = parse_nonce)]
+    pub nonce: Option<Vec<u8>>,
+
+    /// Where to prove: on this machine, or on the SP1 prover network with the key in
+    /// `SP1_PRIVATE_KEY`. Left to `SP1_PROVER` if not given.
+    #[clap(long, env = "ZKPOEX_PROVER", value_enum)]
+    pub prover: Option<ProverKind>,
+
+    /// The prover network RPC to send proof requests to, instead of the SDK's default.
+    #[clap(long, env = "ZKPOEX_PROVER_RPC")]
+    pub prover_rpc: Option<String>,
+
+    /// A short note on the disclosure policy to record in the fixture, e.g. "Contact
+    /// security@protocol.xyz; disclose after round N". At most 280 characters, without control
+    /// characters.
+    #[clap(long, env = "ZKPOEX_NOTE", value_parser = policy_note)]
+    pub note: Option<String>,
+
+    /// Also write the raw key to `zkpoex_enc_key` in the out dir, where the ecdh script's
+    /// `--key-file` looks for it. Anyone who can read that file can decrypt the exploit before the
+    /// disclosure round, so without this flag the key is only kept in memory and has to be handed
+    /// to the ecdh script with `--enc-key`.
+    #[clap(long, env = "ZKPOEX_PERSIST_KEY")]
+    pub persist_key: bool,
+
+    /// Seed the key and nonce so runs are reproducible. Testing only: the seed reveals the key, so
+    /// never use it for a real disclosure.
+    #[clap(long, env = "ZKPOEX_SEED")]
+    pub seed: Option<u64>,
+
+    #[clap(
+        long,
+        env = "ZKPOEX_PROGRESS",
+        help = "print each proving phase to stderr as it starts and ends"
+    )]
+    pub progress: bool,
+
+    #[clap(
+        long,
+        env = "ZKPOEX_QUIET",
+        help = "don't report the proof, only write the outputs"
+    )]
+    pub quiet: bool,
+
+    #[clap(
+        long,
+        env = "ZKPOEX_STATE_DIFF",
+        conflicts_with_all = ["quiet", "json_output"],
+        help = "summarize the balance and storage changes between the before and after states"
+    )]
+    pub state_diff: bool,
+
+    #[clap(
+        long,
+        env = "ZKPOEX_LOG_FORMAT",
+        value_enum,
+        default_value_t = LogFormat::Pretty,
+        help = "format of the log lines, json for log aggregators"
+    )]
+    pub log_format: LogFormat,
+
+    #[clap(
+        long,
+        env = "ZKPOEX_JSON_OUTPUT",
+        conflicts_with = "quiet",
+        help = "report each proof as a single JSON line with its vkey, public values and round"
+    )]
+    pub json_output: bool,
+
+    #[clap(
+        long,
+        env = "ZKPOEX_OUT_DIR",
+        default_value = DEFAULT_OUT_DIR,
+        value_parser = resolve_dir,
+        help = "where to write the proof, keys and fixture"
+    )]
+    pub out_dir: PathBuf,
+
+    #[clap(
+        long,
+        env = "ZKPOEX_FIXTURES_DIR",
+        default_value_os_t = default_fixtures_dir(),
+        value_parser = resolve_dir,
+        help = "where to write the fixture for the Solidity tests"
+    )]
+    pub fixtures_dir: PathBuf,
+
+    #[clap(
+        long,
+        env = "ZKPOEX_FIXTURE_NAME",
+        value_parser = fixture_name_arg,
+        help = "name the Solidity fixture zkpoex_<name>_fixture.json, for projects with several verifier contracts"
+    )]
+    pub fixture_name: Option<String>,
+
+    #[clap(
+        long,
+        env = "ZKPOEX_BUNDLE",
+        value_parser = resolve_dir,
+        help = "also pack the proof, ciphertexts, fixture and manifest into this .tar.zst"
+    )]
+    pub bundle: Option<PathBuf>,
 }
 
-/// A fixture that can be used to test the verification of SP1 zkVM proofs inside Solidity.
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct SP1ZkPoExProofFixture {
-    key: [u8; 32],
-    nonce: [u8; 12],
-    round: u64,
-    before: String,
-    after: String,
-    hash_private_inputs: String,
-    chacha_cipher: Vec<u8>,
-    tlock_cipher: Vec<u8>,
-    calldata: String,
-    blockchain_settings: String,
-    vkey: String,
+impl ProveArgs {
+    /// Parses `argv` with the arguments of its `--config` file, or the one `ZKPOEX_CONFIG` names,
+    /// placed before the command line ones so those win.
+    fn parse_with_config(argv: Vec<String>) -> Result<Self, ProveError> {
+        let env_config = std::env::var("ZKPOEX_CONFIG").ok();
+        let Some(path) = config_path(&argv).or(env_config.as_deref()) else {
+            return Ok(Self::parse_from(argv));
+        };
+        let config = read_config_args(&Self::command(), Path::new(path))?;
+
+        let mut merged = argv[..1].to_vec();
+        merged.extend(config);
+        merged.extend_from_slice(&argv[1..]);
+        Ok(Self::parse_from(merged))
+    }
+
+    /// The `--nonce`, checked to be as long as the `--cipher` takes.
+    fn nonce(&self) -> Result<Option<Vec<u8>>, clap::Error> {
+        if let Some(nonce) = &self.nonce {
+            self.cipher
+                .check_nonce(nonce)
+                .map_err(|e| Self::command().error(ErrorKind::ValueValidation, e))?;
+        }
+        Ok(self.nonce.clone())
+    }
+
+    /// The disclosure round requested on the command line, preferring an explicit round number,
+    /// or an immediate disclosure with `--no-tlock`.
+    fn disclosure(&self) -> Result<Disclosure, clap::Error> {
+        if self.no_tlock {
+            return Ok(Disclosure::Immediate);
+        }
+        if let (Some(block), Some(seconds_per_block)) =
+            (self.disclose_at_block, self.seconds_per_block)
+        {
+            // Projected from the settings' block, so actual block times shift the disclosure.
+            let invalid = |e: String| Self::command().error(ErrorKind::ValueValidation, e);
+            let settings = self
+                .blockchain_settings()
+                .map_err(|e| invalid(e.to_string()))?;
+            return block_instant(&settings, block, seconds_per_block)
+                .map(Disclosure::At)
+                .map_err(|e| invalid(e.to_string()));
+        }
+
+        match (self.round_number, self.disclose_at, self.duration) {
+            (Some(round), _, _) => Ok(Disclosure::AtRound(round)),
+            (None, Some(at), _) => Ok(Disclosure::At(at)),
+            (None, None, Some(delay)) => delay
+                .disclosure(SystemTime::now())
+                .map_err(|e| Self::command().error(ErrorKind::ValueValidation, e)),
+            (None, None, None) => Err(Self::command().error(
+                ErrorKind::MissingRequiredArgument,
+                "one of --duration, --disclose-at, --disclose-at-block or --round-number is required",
+            )),
+        }
+    }
+
+    /// `--onchain` is shorthand for `--proof-mode groth16`.
+    fn proof_mode(&self) -> ProofMode {
+        if self.onchain {
+            ProofMode::Groth16
+        } else {
+            self.proof_mode
+        }
+    }
+
+    /// Groth16 proofs go on-chain as they are, so they are checked unless asked not to.
+    fn verify_after_prove(&self) -> bool {
+        self.verify_after_prove
+            || (!self.no_verify_after_prove && self.proof_mode() == ProofMode::Groth16)
+    }
+
+    /// The blockchain settings from the preset, file or JSON argument, with any per-field overrides
+    /// applied on top.
+    fn blockchain_settings(&self) -> Result<BlockchainSettings, ProveError> {
+        let mut settings = match (&self.chain_preset, &self.blockchain_settings_file) {
+            (Some(preset), _) => preset.settings(),
+            (None, Some(path)) => BlockchainSettings::from_file(path)?,
+            (None, None) => self.blockchain_settings.clone(),
+        };
+
+        if let Some(chain_id) = self.chain_id {
+            settings.chain_id = chain_id;
+        }
+        if let Some(gas_limit) = self.block_gas_limit {
+            settings.block_gas_limit = gas_limit;
+        }
+        if let Some(base_fee) = self.block_base_fee_per_gas {
+            settings.block_base_fee_per_gas = base_fee;
+        }
+        Ok(settings)
+    }
+
+    /// The calldatas to prove, either the one given inline or every entry of `--calldata-file`.
+    fn calldatas(&self) -> Result<Vec<String>, ProveError> {
+        let Some(path) = &self.calldata_file else {
+            let calldata = self.calldata.clone().expect("clap requires --calldata");
+            return Ok(vec![calldata]);
+        };
+        let contents = fs::read(path).map_err(|source| ProveError::Io {
+            path: path.clone(),
+            source,
+        })?;
+        Ok(parse_calldata_file(&contents)?)
+    }
+
+    fn output(&self) -> Output {
+        match (self.quiet, self.json_output) {
+            (_, true) => Output::Json,
+            (true, false) => Output::Quiet,
+            (false, false) => Output::Human {
+                state_diff: self.state_diff,
+            },
+        }
+    }
+
+    fn output_dirs(&self) -> OutputDirs {
+        OutputDirs {
+            out_dir: self.out_dir.clone(),
+            fixtures_dir: self.fixtures_dir.clone(),
+            fixture_name: self.fixture_name.clone(),
+            bundle: self.bundle.clone(),
+            persist_key: self.persist_key,
+        }
+    }
+
+    fn split(&self) -> Option<SplitTimelock> {
+        self.split.map(|split| SplitTimelock {
+            split,
+            rounds: self.share_round.clone(),
+        })
+    }
+
+    fn drand(&self) -> DrandChain {
+        DrandChain {
+            url: self.drand_url.clone(),
+            chain_hash: self.drand_chain_hash.clone(),
+            retry: RetryPolicy {
+                retries: self.drand_retries,
+                timeout: self.drand_timeout,
+                ..Default::default()
+            },
+            cache: Some(DrandCache {
+                dir: self.drand_beacon_cache.clone(),
+                refresh: self.refresh_drand,
+                ..Default::default()
+            }),
+        }
+    }
 }
 
-fn main() {
+fn main() -> Result<(), ProveError> {
+    // Parse the command line arguments.
+    let args = ProveArgs::parse_with_config(std::env::args().collect())?;
+
     // Setup the logger.
-    sp1_sdk::utils::setup_logger();
+    setup_logging(args.log_format);
 
-    // Parse the command line arguments.
-    let args = ProveArgs::parse();
+    // Files written so far are complete, only the ones being written when the run is interrupted
+    // or given up on are partial.
+    let dirs = args.output_dirs();
+    let interrupted: Vec<_> = dirs.all().map(Path::to_path_buf).collect();
+    let handler = ctrlc::set_handler(move || {
+        let _commit = abandon_staged(interrupted.iter().map(PathBuf::as_path));
+        eprintln!("Interrupted, removed partially written outputs");
+        std::process::exit(130);
+    });
+    if let Err(e) = handler {
+        tracing::warn!("failed to install the Ctrl-C handler: {e}");
+    }
 
-    let mut rng = rand::thread_rng();
+    let Some(timeout) = args.timeout else {
+        return run(&args);
+    };
+    let result = with_timeout(timeout, move || run(&args));
+    if let Err(ProveError::ProveTimeout { .. }) = result {
+        let _commit = abandon_staged(dirs.all());
+    }
+    result
+}
 
-    let key: [u8; 32] = rng.gen();
-    let nonce: [u8; 12] = rng.gen();
+fn run(args: &ProveArgs) -> Result<(), ProveError> {
+    let options = ProveOptions {
+        disclosure: args.disclosure().unwrap_or_else(|e| e.exit()),
+        drand: args.drand(),
+        proof_mode: args.proof_mode(),
+        elf: args.elf.clone(),
+        cipher: args.cipher,
+        setup_cache: (!args.no_cache).then(|| args.setup_cache_dir.clone()),
+        enc_key: args.enc_key,
+        nonce: args.nonce().unwrap_or_else(|e| e.exit()),
+        seed: args.seed,
+        verify_after_prove: args.verify_after_prove(),
+        split: args.split(),
+        max_cycles: args.max_cycles,
+        policy_note: args.note.clone(),
+        prover: ProverConfig {
+            kind: args.prover,
+            rpc: args.prover_rpc.clone(),
+        },
+    };
 
-    let client: drand_core::HttpClient =
-        "https://api.drand.sh/dbd506d6ef76e5f386f41c651dcb808c5bcbd75471cc4eafa3f4df7ad4e4c493"
-            .try_into()
-            .unwrap();
-    let info = client.chain_info().unwrap();
+    if args.nonce.is_some() {
+        tracing::warn!(
+            "--nonce is used as is: encrypting another exploit under the same key and nonce \
+             reveals both, never reuse the pair"
+        );
+    }
+    if args.seed.is_some() {
+        tracing::warn!("--seed makes the encryption key predictable, only use it for testing");
+    }
+
+    let blockchain_settings = args.blockchain_settings()?;
 
-    let drand_master_key = info.public_key();
+    let calldatas = args.calldatas()?;
 
-    let round = {
-        let d = args
-            .duration
-            .expect("duration is expected if round_number isn't specified")
-            .into();
-        round_after(&info, d)
+    if args.dry_run {
+        let model = CostModel {
+            cycles_per_second: args.cycles_per_second,
+            ..Default::default()
+        };
+        return dry_run(&options, &calldatas, &blockchain_settings, &model);
+    }
+
+    if args.execute_only {
+        let batch = calldatas.len() > 1;
+        for (i, calldata) in calldatas.iter().enumerate() {
+            let Execution {
+                public_values,
+                cycles,
+                report,
+            } = execute_zkpoex(calldata, &blockchain_settings, &options)?;
+            println!("Calldata: {}", calldata);
+            println!("Before: {}", public_values.before);
+            println!("After: {}", public_values.after);
+            println!("Hash Private Inputs: {}", public_values.hash_private_inputs);
+            println!("Cycles: {}", cycles);
+            if args.state_diff {
+                report_state_diff(&public_values.before, &public_values.after);
+            }
+            if args.report {
+                print!("{report}");
+            }
+            if let Some(path) = &args.report_json {
+                let suffix = output_suffix(batch.then_some(i));
+                write(suffixed_path(path, &suffix), report.to_json())?;
+            }
+        }
+        return Ok(());
+    }
+
+    let progress = args.progress.then(|| -> ProgressFn {
+        Arc::new(|phase, event| match event {
+            PhaseEvent::Started => eprintln!("[{phase}] started"),
+            PhaseEvent::Finished(elapsed) => eprintln!(
+                "[{phase}] finished in {}",
+                humantime::format_duration(Duration::from_millis(elapsed.as_millis() as u64))
+            ),
+        })
+    });
+    let (proof_mode, cipher) = (options.proof_mode, options.cipher);
+    let prover = ZkPoExProver::with_progress(options, progress)?;
+    let dirs = args.output_dirs();
+    let output = args.output();
+
+    let vkey = prover.vk().bytes32();
+    if let Some(elf) = &args.elf {
+        // Differs from the vkey the Solidity verifier is deployed with.
+        tracing::info!("Loaded {} with verification key {vkey}", elf.display());
+    }
+    let settings_json = blockchain_settings.to_json();
+    let skip = |calldata: &str, suffix: &str| {
+        let inputs = DeterministicInputs {
+            vkey: &vkey,
+            calldata,
+            blockchain_settings: &settings_json,
+            proof_mode,
+            cipher,
+        };
+        let skip = args.skip_if_exists && outputs_exist(&dirs, suffix, &inputs);
+        if skip {
+            tracing::info!("Outputs for calldata {calldata} are up to date, skipping");
+        }
+        skip
     };
 
-    let mut tlock_cipher = vec![];
-    tlock::encrypt(&mut tlock_cipher, &key[..], &drand_master_key, round).unwrap();
-
-    // Setup the prover client.
-    let client = ProverClient::new();
-
-    // Setup the program.
-    let (pk, vk) = client.setup(ZKPOEX_ELF);
-
-    // Setup the inputs.;
-    let mut stdin = SP1Stdin::new();
-    stdin.write(&(
-        key,
-        nonce,
-        args.calldata.clone(),
-        args.blockchain_settings.clone(),
-        drand_master_key,
-        round,
-    ));
-
-    // Generate the proof.
-    let proof = client
-        .prove_compressed(&pk, stdin)
-        .expect("failed to generate proof");
-
-    let _ = fs::create_dir_all(PathBuf::from("./data"));
-    std::fs::write(PathBuf::from("./data/zkpoex_enc_key"), key).expect("failed to write fixture");
-
-    let (before, after, hash_private_inputs, chacha_cipher, _): (
-        String,
-        String,
-        String,
-        Vec<u8>,
-        String,
-        // Vec<u8>,
-        // u64,
-    ) = bincode::deserialize(proof.public_values.as_slice())
-        .expect("failed to deserialize public values");
-
-    std::fs::write(PathBuf::from("./data/zkpoex_chacha"), &chacha_cipher)
-        .expect("failed to write fixture");
-
-    std::fs::write(PathBuf::from("./data/zkpoex_tlock"), &tlock_cipher)
-        .expect("failed to write fixture");
-
-    // Create the testing fixture so we can test things end-ot-end.
-    let fixture = SP1ZkPoExProofFixture {
-        before,
-        after,
-        hash_private_inputs,
-        key,
-        nonce,
-        round,
-        chacha_cipher,
-        tlock_cipher,
-        calldata: args.calldata,
-        blockchain_settings: args.blockchain_settings,
-        vkey: vk.bytes32().to_string(),
+    // A single calldata keeps the plain output names, larger files are a batch in which every
+    // entry gets its own, index suffixed, set of outputs.
+    if let [calldata] = calldatas.as_slice() {
+        if skip(calldata, &output_suffix(None)) {
+            return Ok(());
+        }
+        let (fixture, proof) = prover.prove(calldata, &blockchain_settings)?;
+        return write_outputs(
+            &dirs,
+            output,
+            prover.chain_info(),
+            &fixture,
+            &proof,
+            &output_suffix(None),
+        );
+    }
+
+    let mut failed = vec![];
+    for (i, calldata) in calldatas.iter().enumerate() {
+        if skip(calldata, &output_suffix(Some(i))) {
+            continue;
+        }
+        let result = prover
+            .prove(calldata, &blockchain_settings)
+            .and_then(|(fixture, proof)| {
+                write_outputs(
+                    &dirs,
+                    output,
+                    prover.chain_info(),
+                    &fixture,
+                    &proof,
+                    &output_suffix(Some(i)),
+                )
+            });
+        if let Err(e) = result {
+            tracing::error!("failed to prove calldata {calldata}: {e}");
+            failed.push(calldata);
+        }
+    }
+
+    tracing::info!(
+        "Proved {} of {} calldatas",
+        calldatas.len() - failed.len(),
+        calldatas.len()
+    );
+    for calldata in &failed {
+        println!("Rejected: {}", calldata);
+    }
+
+    if !failed.is_empty() {
+        return Err(ProveError::BatchRejected {
+            rejected: failed.len(),
+            total: calldatas.len(),
+        });
+    }
+    Ok(())
+}
+
+/// The suffix distinguishing the outputs of batch entry `index` from each other.
+fn output_suffix(index: Option<usize>) -> String {
+    index.map(|i| format!("_{i}")).unwrap_or_default()
+}
+
+/// The archive batch entry `suffix` is bundled into: `--bundle` with the suffix before its
+/// extension.
+fn bundle_path(bundle: &Path, suffix: &str) -> PathBuf {
+    if suffix.is_empty() {
+        return bundle.to_path_buf();
+    }
+    let name = bundle.file_name().unwrap_or_default().to_string_lossy();
+    let (stem, extension) = match name.strip_suffix(".tar.zst") {
+        Some(stem) => (stem, ".tar.zst"),
+        None => (name.as_ref(), ""),
     };
+    bundle.with_file_name(format!("{stem}{suffix}{extension}"))
+}
 
-    let _ = proof.save("./zkpoex.bincode");
-
-    // The verification key is used to verify that the proof corresponds to the execution of the
-    // program on the given input.
-    //
-    // Note that the verification key stays the same regardless of the input.
-    println!("Verification Key: {}", fixture.vkey);
-
-    // The public values are the values whicha are publically commited to by the zkVM.
-    //
-    // If you need to expose the inputs or outputs of your program, you should commit them in
-    // the public values.
-    println!("Public Values: {}", proof.public_values.bytes());
-
-    // Save the fixture to a file.
-    let fixture_path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../contracts/src/fixtures");
-    std::fs::create_dir_all(&fixture_path).expect("failed to create fixture path");
-    std::fs::write(
-        fixture_path.join("zkpoex_fixture.json"),
-        serde_json::to_string_pretty(&fixture).unwrap(),
-    )
-    .expect("failed to write fixture");
+/// `path` with `suffix` before its extension.
+fn suffixed_path(path: &Path, suffix: &str) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match path.extension() {
+        Some(extension) => format!("{stem}{suffix}.{}", extension.to_string_lossy()),
+        None => format!("{stem}{suffix}"),
+    };
+    path.with_file_name(name)
 }
 
-pub fn round_at(chain_info: &ChainInfo, t: SystemTime) -> u64 {
-    let since_epoch = t.duration_since(UNIX_EPOCH).unwrap();
-    let t_unix = since_epoch.as_secs();
-    current_round(
-        t_unix,
-        Duration::from_secs(chain_info.period()),
-        chain_info.genesis_time(),
-    )
+fn write(path: impl Into<PathBuf>, contents: impl AsRef<[u8]>) -> Result<(), ProveError> {
+    let path = path.into();
+    write_atomic(&path, contents).map_err(|source| ProveError::Io { path, source })
+}
+
+fn create_dir(path: impl Into<PathBuf>) -> Result<(), ProveError> {
+    let path = path.into();
+    fs::create_dir_all(&path).map_err(|source| ProveError::Io { path, source })
+}
+
+/// How a run reports the proofs it produced.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Output {
+    /// Log lines, with a summary of the state changes if `state_diff` is set.
+    Human {
+        state_diff: bool,
+    },
+    Quiet,
+    Json,
+}
+
+/// Where the outputs of a run go.
+struct OutputDirs {
+    /// The proof, the key, its ciphertexts and a copy of the fixture.
+    out_dir: PathBuf,
+    /// The fixture the Solidity tests load.
+    fixtures_dir: PathBuf,
+    /// Names the fixture in `fixtures_dir` after the contract it is for.
+    fixture_name: Option<String>,
+    /// The archive the outputs listed in the manifest are packed into, if any.
+    bundle: Option<PathBuf>,
+    /// Whether the raw key is written to the out dir too.
+    persist_key: bool,
+}
+
+impl OutputDirs {
+    /// Every directory a run writes to.
+    fn all(&self) -> impl Iterator<Item = &Path> {
+        let bundle_dir = self.bundle.as_deref().and_then(Path::parent);
+        [self.out_dir.as_path(), self.fixtures_dir.as_path()]
+            .into_iter()
+            .chain(bundle_dir)
+    }
+}
+
+/// The inputs that determine a proof, unlike the key, nonce and round which change every run.
+#[derive(Debug, PartialEq, Eq)]
+struct DeterministicInputs<'a> {
+    vkey: &'a str,
+    calldata: &'a str,
+    blockchain_settings: &'a str,
+    proof_mode: ProofMode,
+    cipher: PayloadCipher,
+}
+
+impl<'a> DeterministicInputs<'a> {
+    fn of(fixture: &'a SP1ZkPoExProofFixture) -> Self {
+        Self {
+            vkey: &fixture.vkey,
+            calldata: &fixture.calldata,
+            blockchain_settings: &fixture.blockchain_settings,
+            proof_mode: fixture.proof_mode,
+            cipher: fixture.cipher,
+        }
+    }
+}
+
+/// Whether a previous run left the proof and a fixture proven from `inputs` behind under `suffix`.
+/// An unreadable fixture counts as missing, so it is proven again.
+fn outputs_exist(dirs: &OutputDirs, suffix: &str, inputs: &DeterministicInputs) -> bool {
+    if !dirs
+        .out_dir
+        .join(format!("zkpoex{suffix}.bincode"))
+        .is_file()
+    {
+        return false;
+    }
+    fs::read_to_string(dirs.out_dir.join(format!("zkpoex_fixture{suffix}.json")))
+        .ok()
+        .and_then(|json| SP1ZkPoExProofFixture::from_json(&json).ok())
+        .is_some_and(|fixture| DeterministicInputs::of(&fixture) == *inputs)
 }
 
-pub fn round_after(chain_info: &ChainInfo, d: Duration) -> u64 {
-    let t = SystemTime::now().add(d);
-    round_at(chain_info, t)
+/// Writes every output of one proof. Each file is staged and renamed into place, so a killed run
+/// leaves either the previous or the new version behind, never half of one.
+fn write_outputs(
+    dirs: &OutputDirs,
+    output: Output,
+    info: Option<&ChainInfo>,
+    fixture: &SP1ZkPoExProofFixture,
+    proof: &ZkPoExProof,
+    suffix: &str,
+) -> Result<(), ProveError> {
+    let scheme = info
+        .map(BeaconScheme::of)
+        .transpose()
+        .map_err(ProveError::Chain)?;
+    write_fixture_files(dirs, fixture, scheme, suffix)?;
+
+    let proof_path = dirs.out_dir.join(format!("zkpoex{suffix}.bincode"));
+    write_atomic_with(&proof_path, |staging| {
+        proof.save(staging).map_err(std::io::Error::other)
+    })
+    .map_err(|source| ProveError::Io {
+        path: proof_path,
+        source,
+    })?;
+
+    // For consumers that can't decode the bincode the program commits.
+    let public_values_json =
+        public_values_json(proof.public_values().as_slice()).map_err(ProveError::PublicValues)?;
+    write(
+        dirs.out_dir
+            .join(format!("zkpoex_public_values{suffix}.json")),
+        public_values_json,
+    )?;
+
+    // The key stays out of the manifest: it is the one output that must not be shared.
+    let mut artifacts = vec![
+        format!("zkpoex{suffix}.bincode"),
+        format!("zkpoex_chacha{suffix}"),
+        format!("zkpoex_public_values{suffix}.json"),
+    ];
+    artifacts.extend(
+        tlock_bundles(fixture, scheme, suffix)
+            .into_iter()
+            .map(|(name, _)| name),
+    );
+    artifacts.push(format!("zkpoex_fixture{suffix}.json"));
+    let manifest = Manifest::build(
+        &dirs.out_dir,
+        fixture.round,
+        fixture.vkey.clone(),
+        artifacts,
+    )?;
+    let manifest_name = format!("manifest{suffix}.json");
+    write(
+        dirs.out_dir.join(&manifest_name),
+        serde_json::to_string_pretty(&manifest).expect("manifest serializes to json"),
+    )?;
+
+    if let Some(bundle) = &dirs.bundle {
+        bundle_outputs(&dirs.out_dir, &manifest_name, &bundle_path(bundle, suffix))?;
+    }
+
+    let disclosable_at = disclosable_at(info, fixture.round);
+    report(
+        output,
+        fixture,
+        &proof.public_values().bytes(),
+        disclosable_at,
+    );
+    Ok(())
 }
 
-pub fn current_round(now: u64, period: Duration, genesis: u64) -> u64 {
-    let (next_round, _) = next_round(now, period, genesis);
+/// When the key timelocked to `round` can be disclosed, as both the proof report and the dry run
+/// print it.
+fn disclosable_at(info: Option<&ChainInfo>, round: Option<u64>) -> Option<SystemTime> {
+    info.zip(round)
+        .map(|(info, round)| round_to_time(info, round))
+}
+
+/// Reports a proof that was written: as log lines for people, or as one JSON line for scripts.
+fn report(
+    output: Output,
+    fixture: &SP1ZkPoExProofFixture,
+    public_values: &str,
+    disclosable_at: Option<SystemTime>,
+) {
+    match output {
+        Output::Quiet => {}
+        Output::Json => println!("{}", summary_json(fixture, public_values)),
+        Output::Human { state_diff } => {
+            if let (Some(ms), Some(cycles)) = (fixture.proving_duration_ms, fixture.cycles) {
+                tracing::info!("Proved {} cycles in {}ms", cycles, ms);
+            }
+
+            // The verification key is used to verify that the proof corresponds to the execution
+            // of the program on the given input.
+            //
+            // Note that the verification key stays the same regardless of the input.
+            tracing::info!("Verification Key: {}", fixture.vkey);
+
+            // Lets auditors check the timelock window without looking up the drand chain.
+            match (fixture.round, disclosable_at) {
+                (Some(round), Some(at)) => tracing::info!(
+                    "Disclosure Round: {} (disclosable by {})",
+                    round,
+                    humantime::format_rfc3339_seconds(at)
+                ),
+                _ => tracing::info!("Disclosure Round: none, the key isn't timelocked"),
+            }
+
+            // The public values are the values whicha are publically commited to by the zkVM.
+            //
+            // If you need to expose the inputs or outputs of your program, you should commit them
+            // in the public values.
+            tracing::info!("Public Values: {}", public_values);
+
+            if state_diff {
+                report_state_diff(&fixture.before, &fixture.after);
+            }
 
-    if next_round <= 1 {
-        next_round
+            // The proof proves to the verifier that the program was executed with some inputs
+            // that led to the give public values.
+            if let Some(proof) = &fixture.proof {
+                tracing::info!("Proof Bytes: {}", proof);
+            }
+        }
+    }
+}
+
+/// Logs what the exploit changed, or why the states couldn't be read.
+fn report_state_diff(before: &str, after: &str) {
+    match diff_states(before, after) {
+        Ok(diff) => tracing::info!("State Diff:\n{}", diff),
+        Err(e) => tracing::warn!("failed to read the before/after states: {e}"),
+    }
+}
+
+fn summary_json(fixture: &SP1ZkPoExProofFixture, public_values: &str) -> serde_json::Value {
+    serde_json::json!({
+        "vkey": fixture.vkey,
+        "publicValues": public_values,
+        "round": fixture.round,
+        "proof": fixture.proof,
+    })
+}
+
+/// Writes both ciphertexts, the fixture and, if asked to, the key, creating the directories if
+/// needed.
+fn write_fixture_files(
+    dirs: &OutputDirs,
+    fixture: &SP1ZkPoExProofFixture,
+    scheme: Option<BeaconScheme>,
+    suffix: &str,
+) -> Result<(), ProveError> {
+    let out_dir = &dirs.out_dir;
+    create_dir(out_dir)?;
+    let key_path = out_dir.join(format!("{ENC_KEY_FILE}{suffix}"));
+    if dirs.persist_key {
+        write(key_path, fixture.key)?;
     } else {
-        next_round - 1
+        // A key left over from an earlier run no longer matches the new ciphertexts.
+        match fs::remove_file(&key_path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                return Err(ProveError::Io {
+                    path: key_path,
+                    source: e,
+                })
+            }
+            _ => {}
+        }
+    }
+
+    // Frame both ciphertexts with the nonce, round and beacon scheme so they can be disclosed on
+    // their own.
+    let chacha = CipherBundle::new(
+        fixture.cipher,
+        scheme,
+        fixture.nonce.clone(),
+        fixture.round.unwrap_or_default(),
+        fixture.chacha_cipher.clone(),
+    );
+    write(
+        out_dir.join(format!("zkpoex_chacha{suffix}")),
+        chacha.to_bytes(),
+    )?;
+
+    for (name, tlock) in tlock_bundles(fixture, scheme, suffix) {
+        write(out_dir.join(name), tlock.to_bytes())?;
     }
+
+    // The copy in the out dir keeps the plain name, which convert and --skip-if-exists look for.
+    let fixture_json = fixture.to_json();
+    write(out_dir.join(fixture_file_name(None, suffix)), &fixture_json)?;
+
+    create_dir(&dirs.fixtures_dir)?;
+    write(
+        dirs.fixtures_dir
+            .join(fixture_file_name(dirs.fixture_name.as_deref(), suffix)),
+        fixture_json,
+    )
+}
+
+/// The timelocked key, or every share of it if it was split, each framed with its own round. None
+/// if the key isn't timelocked.
+fn tlock_bundles(
+    fixture: &SP1ZkPoExProofFixture,
+    scheme: Option<BeaconScheme>,
+    suffix: &str,
+) -> Vec<(String, CipherBundle)> {
+    let bundle = |round, tlock_cipher: &[u8]| {
+        CipherBundle::new(
+            fixture.cipher,
+            scheme,
+            fixture.nonce.clone(),
+            round,
+            tlock_cipher.to_vec(),
+        )
+    };
+
+    if fixture.tlock_shares.is_empty() {
+        return fixture
+            .round
+            .map(|round| {
+                (
+                    format!("zkpoex_tlock{suffix}"),
+                    bundle(round, &fixture.tlock_cipher),
+                )
+            })
+            .into_iter()
+            .collect();
+    }
+    fixture
+        .tlock_shares
+        .iter()
+        .enumerate()
+        .map(|(i, share)| {
+            (
+                format!("zkpoex_tlock{suffix}_share{i}"),
+                bundle(share.round, &share.tlock_cipher),
+            )
+        })
+        .collect()
+}
+
+/// Reports which drand round the options resolve to, when it will be published, and what proving
+/// each calldata would cost.
+fn dry_run(
+    options: &ProveOptions,
+    calldatas: &[String],
+    blockchain_settings: &BlockchainSettings,
+    model: &CostModel,
+) -> Result<(), ProveError> {
+    let info = options.drand.chain_info()?;
+
+    let round = disclosure_round(&info, options.disclosure).map_err(ProveError::Round)?;
+    let published_at = disclosable_at(Some(&info), Some(round)).expect("the round is given");
+
+    println!("Drand Period: {}s", info.period());
+    println!(
+        "Drand Genesis: {}",
+        humantime::format_rfc3339_seconds(UNIX_EPOCH + Duration::from_secs(info.genesis_time()))
+    );
+    println!("Disclosure Round: {}", round);
+    println!(
+        "Disclosable At: {}",
+        humantime::format_rfc3339_seconds(published_at)
+    );
+
+    for calldata in calldatas {
+        let estimate = estimate_zkpoex(calldata, blockchain_settings, options, model)?;
+        println!("Estimate for {}: {}", calldata, estimate);
+    }
+    Ok(())
 }
 
-pub fn next_round(now: u64, period: Duration, genesis: u64) -> (u64, u64) {
-    if now < genesis {
-        return (1, genesis);
+#[cfg(test)]
+mod tests {
+    use std::{
+        ffi::OsString,
+        sync::{Mutex, MutexGuard, PoisonError},
+    };
+
+    use zkpoex_script::{digest::PrivateInputsDigest, split::TlockShare, FIXTURE_SCHEMA_VERSION};
+
+    use super::*;
+
+    /// Held by the tests that depend on the current directory or the environment, since some of
+    /// them change it and clap reads the `ZKPOEX_*` variables while parsing.
+    static PROCESS: Mutex<()> = Mutex::new(());
+
+    fn lock_process() -> MutexGuard<'static, ()> {
+        // The tests restore what they change before asserting, so a failed one leaves it intact.
+        PROCESS.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// `ProveArgs::try_parse_from`, out of the way of tests changing the environment.
+    fn parse_args<I, T>(argv: I) -> Result<ProveArgs, clap::Error>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<OsString> + Clone,
+    {
+        let _process = lock_process();
+        ProveArgs::try_parse_from(argv)
+    }
+
+    #[test]
+    fn dry_run_and_proof_report_agree_on_the_disclosure_instant() {
+        let info = zkpoex_script::drand::MockDrand::default()
+            .chain_info()
+            .unwrap();
+        // Both print `disclosable_at`, which is when drand publishes the round: 999 periods
+        // after genesis for round 1000.
+        assert_eq!(
+            disclosable_at(Some(&info), Some(1_000)),
+            Some(UNIX_EPOCH + Duration::from_secs(info.genesis_time() + 999 * info.period()))
+        );
+        assert_eq!(disclosable_at(Some(&info), None), None);
     }
 
-    let from_genesis = now - genesis;
-    let next_round = (((from_genesis as f64) / (period.as_secs() as f64)).floor() + 1f64) as u64;
-    let next_time = genesis + next_round * period.as_secs();
+    /// The scheme of the default drand chain.
+    const FASTNET: BeaconScheme = BeaconScheme::UnchainedOnG1;
 
-    (next_round, next_time)
+    #[test]
+    fn duration_conflicts_with_round_number() {
+        let err = parse_args([
+            "prove",
+            "--calldata",
+            "63d9b770",
+            "--duration",
+            "1d",
+            "--round-number",
+            "12345678",
+        ])
+        .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ArgumentConflict);
+    }
+
+    #[test]
+    fn command_line_overrides_the_config_file() {
+        let path = std::env::temp_dir().join(format!("zkpoex-config-{}.toml", std::process::id()));
+        fs::write(
+            &path,
+            "calldata = \"63d9b770\"\nduration = \"30d\"\ndrand_retries = 5\nonchain = true\n",
+        )
+        .unwrap();
+        let argv = [
+            "prove",
+            "--config",
+            path.to_str().unwrap(),
+            "--duration",
+            "1d",
+        ];
+        let args = {
+            let _process = lock_process();
+            ProveArgs::parse_with_config(argv.map(String::from).to_vec())
+        };
+        fs::remove_file(&path).unwrap();
+
+        let args = args.unwrap();
+        assert_eq!(args.calldata.as_deref(), Some("63d9b770"));
+        assert_eq!(
+            args.duration,
+            Some(DisclosureDelay::Fixed(Duration::from_secs(24 * 60 * 60)))
+        );
+        assert_eq!(args.drand_retries, 5);
+        assert_eq!(args.proof_mode(), ProofMode::Groth16);
+    }
+
+    #[test]
+    fn environment_fills_in_absent_flags() {
+        let _process = lock_process();
+        std::env::set_var("ZKPOEX_MAX_CYCLES", "1000");
+        std::env::set_var("ZKPOEX_SEED", "7");
+        let args = ProveArgs::try_parse_from(["prove", "--calldata", "63d9b770", "--seed", "8"]);
+        std::env::remove_var("ZKPOEX_MAX_CYCLES");
+        std::env::remove_var("ZKPOEX_SEED");
+
+        let args = args.unwrap();
+        assert_eq!(args.max_cycles, Some(1000));
+        assert_eq!(args.seed, Some(8));
+    }
+
+    #[test]
+    fn log_format_defaults_to_pretty() {
+        let args = parse_args(["prove", "--calldata", "63d9b770"]).unwrap();
+        assert_eq!(args.log_format, LogFormat::Pretty);
+
+        let args = parse_args(["prove", "--calldata", "63d9b770", "--log-format", "json"]).unwrap();
+        assert_eq!(args.log_format, LogFormat::Json);
+    }
+
+    #[test]
+    fn malformed_calldata_is_rejected_at_parse_time() {
+        let err = parse_args(["prove", "--calldata", "0x63d9b77"]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ValueValidation);
+    }
+
+    #[test]
+    fn batch_entries_get_distinct_outputs() {
+        let calldatas = parse_calldata_file(b"63d9b770\ndeadbeef").unwrap();
+        let suffixes: Vec<_> = (0..calldatas.len())
+            .map(|i| output_suffix(Some(i)))
+            .collect();
+        assert_eq!(suffixes, ["_0", "_1"]);
+        assert_eq!(output_suffix(None), "");
+
+        let (dirs, _root) = temp_output_dirs("batch");
+        for (calldata, suffix) in calldatas.iter().zip(&suffixes) {
+            let fixture = SP1ZkPoExProofFixture {
+                calldata: calldata.clone(),
+                ..test_fixture()
+            };
+            write_fixture_files(&dirs, &fixture, Some(FASTNET), suffix).unwrap();
+        }
+        for (calldata, suffix) in calldatas.iter().zip(&suffixes) {
+            for dir in [&dirs.out_dir, &dirs.fixtures_dir] {
+                let path = dir.join(format!("zkpoex_fixture{suffix}.json"));
+                let fixture =
+                    SP1ZkPoExProofFixture::from_json(&fs::read_to_string(&path).unwrap()).unwrap();
+                assert_eq!(&fixture.calldata, calldata, "{}", path.display());
+            }
+        }
+    }
+
+    #[test]
+    fn batch_entries_get_distinct_bundles() {
+        let bundle = Path::new("/tmp/disclosure.tar.zst");
+        assert_eq!(bundle_path(bundle, ""), bundle);
+        assert_eq!(
+            bundle_path(bundle, "_3"),
+            Path::new("/tmp/disclosure_3.tar.zst")
+        );
+        assert_eq!(
+            bundle_path(Path::new("/tmp/disclosure"), "_3"),
+            Path::new("/tmp/disclosure_3")
+        );
+    }
+
+    #[test]
+    fn batch_entries_get_distinct_reports() {
+        let report = Path::new("/tmp/report.json");
+        assert_eq!(suffixed_path(report, ""), report);
+        assert_eq!(suffixed_path(report, "_3"), Path::new("/tmp/report_3.json"));
+
+        let err = parse_args(["prove", "--calldata", "63d9b770", "--report"]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::MissingRequiredArgument);
+    }
+
+    #[test]
+    fn calldata_conflicts_with_calldata_file() {
+        let err = parse_args([
+            "prove",
+            "--calldata",
+            "63d9b770",
+            "--calldata-file",
+            "calldata.txt",
+        ])
+        .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ArgumentConflict);
+    }
+
+    #[test]
+    fn round_number_overrides_default_duration() {
+        let args = parse_args(["prove", "--calldata", "63d9b770", "--round-number", "42"]).unwrap();
+        assert_eq!(args.disclosure().unwrap(), Disclosure::AtRound(42));
+    }
+
+    #[test]
+    fn onchain_selects_groth16() {
+        let args = parse_args(["prove", "--calldata", "63d9b770"]).unwrap();
+        assert_eq!(args.proof_mode(), ProofMode::Compressed);
+
+        let args = parse_args(["prove", "--calldata", "63d9b770", "--onchain"]).unwrap();
+        assert_eq!(args.proof_mode(), ProofMode::Groth16);
+
+        let err = parse_args([
+            "prove",
+            "--calldata",
+            "63d9b770",
+            "--onchain",
+            "--proof-mode",
+            "plonk",
+        ])
+        .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ArgumentConflict);
+    }
+
+    #[test]
+    fn groth16_verifies_after_proving_by_default() {
+        let verify = |extra: &[&str]| {
+            let args = ["prove", "--calldata", "63d9b770"].iter().chain(extra);
+            parse_args(args).unwrap().verify_after_prove()
+        };
+        assert!(!verify(&[]));
+        assert!(verify(&["--verify-after-prove"]));
+        assert!(verify(&["--onchain"]));
+        assert!(!verify(&["--onchain", "--no-verify-after-prove"]));
+    }
+
+    #[test]
+    fn disclose_at_overrides_default_duration() {
+        let args = parse_args([
+            "prove",
+            "--calldata",
+            "63d9b770",
+            "--disclose-at",
+            "2999-01-01T00:00:00Z",
+        ])
+        .unwrap();
+        assert!(matches!(args.disclosure().unwrap(), Disclosure::At(_)));
+
+        let err = parse_args([
+            "prove",
+            "--calldata",
+            "63d9b770",
+            "--disclose-at",
+            "2000-01-01T00:00:00Z",
+        ])
+        .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ValueValidation);
+    }
+
+    #[test]
+    fn disclose_at_block_is_projected_from_the_settings_block() {
+        let args = parse_args([
+            "prove",
+            "--calldata",
+            "63d9b770",
+            "--disclose-at-block",
+            "10",
+            "--seconds-per-block",
+            "12",
+        ])
+        .unwrap();
+        // The default settings describe block 0 at the unix epoch.
+        assert_eq!(
+            args.disclosure().unwrap(),
+            Disclosure::At(UNIX_EPOCH + Duration::from_secs(120))
+        );
+
+        let err = parse_args([
+            "prove",
+            "--calldata",
+            "63d9b770",
+            "--disclose-at-block",
+            "10",
+        ])
+        .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::MissingRequiredArgument);
+    }
+
+    /// Executes against the default drand chain, so it needs the network.
+    #[test]
+    #[ignore]
+    fn execute_only_writes_no_proof() {
+        let out_dir = std::env::temp_dir().join(format!("zkpoex-execute-{}", std::process::id()));
+        let args = parse_args([
+            "prove",
+            "--calldata",
+            "63d9b770",
+            "--execute-only",
+            "--out-dir",
+            out_dir.to_str().unwrap(),
+        ])
+        .unwrap();
+
+        run(&args).unwrap();
+        assert!(!out_dir.exists());
+    }
+
+    /// A temporary directory for the outputs of a test, removed when dropped, even if the test
+    /// fails.
+    struct TempRoot(PathBuf);
+
+    impl Drop for TempRoot {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    /// Fresh output directories under a temporary root named after `name`, removed along with the
+    /// returned guard. Nothing is bundled and the key isn't persisted.
+    fn temp_output_dirs(name: &str) -> (OutputDirs, TempRoot) {
+        let root = std::env::temp_dir().join(format!("zkpoex-{name}-{}", std::process::id()));
+        // Left behind by a run that was killed before it could clean up.
+        let _ = fs::remove_dir_all(&root);
+        let dirs = OutputDirs {
+            out_dir: root.join("out"),
+            fixtures_dir: root.join("fixtures"),
+            fixture_name: None,
+            bundle: None,
+            persist_key: false,
+        };
+        (dirs, TempRoot(root))
+    }
+
+    fn test_fixture() -> SP1ZkPoExProofFixture {
+        SP1ZkPoExProofFixture {
+            schema_version: FIXTURE_SCHEMA_VERSION,
+            key: [1; 32],
+            cipher: PayloadCipher::ChaCha20,
+            nonce: vec![2; 12],
+            round: Some(42),
+            before: String::new(),
+            after: String::new(),
+            hash_private_inputs: PrivateInputsDigest([0; 32]),
+            chacha_cipher: vec![3; 8],
+            tlock_cipher: vec![4; 8],
+            tlock_shares: vec![],
+            calldata: "63d9b770".to_string(),
+            blockchain_settings: BlockchainSettings::default().to_json(),
+            vkey: "0x00".to_string(),
+            proof_mode: ProofMode::Compressed,
+            proof: None,
+            proving_duration_ms: None,
+            cycles: None,
+            report: None,
+            policy_note: None,
+        }
+    }
+
+    #[test]
+    fn outputs_land_in_custom_directories() {
+        let (mut dirs, _root) = temp_output_dirs("outputs");
+        dirs.persist_key = true;
+        let fixture = test_fixture();
+
+        write_fixture_files(&dirs, &fixture, Some(FASTNET), "_7").unwrap();
+        for name in [
+            "zkpoex_enc_key_7",
+            "zkpoex_chacha_7",
+            "zkpoex_tlock_7",
+            "zkpoex_fixture_7.json",
+        ] {
+            assert!(dirs.out_dir.join(name).is_file(), "{name}");
+        }
+        assert!(dirs.fixtures_dir.join("zkpoex_fixture_7.json").is_file());
+    }
+
+    #[test]
+    fn fixture_name_names_the_solidity_fixture() {
+        let args =
+            parse_args(["prove", "--calldata", "63d9b770", "--fixture-name", "vault"]).unwrap();
+        let (dirs, _root) = temp_output_dirs("named");
+        let dirs = OutputDirs {
+            fixture_name: args.output_dirs().fixture_name,
+            ..dirs
+        };
+
+        write_fixture_files(&dirs, &test_fixture(), Some(FASTNET), "").unwrap();
+        assert!(dirs
+            .fixtures_dir
+            .join("zkpoex_vault_fixture.json")
+            .is_file());
+        assert!(!dirs.fixtures_dir.join("zkpoex_fixture.json").exists());
+        assert!(dirs.out_dir.join("zkpoex_fixture.json").is_file());
+    }
+
+    #[test]
+    fn key_is_only_written_with_persist_key() {
+        let (mut dirs, _root) = temp_output_dirs("persist");
+        dirs.persist_key = true;
+        let fixture = test_fixture();
+        let key_path = dirs.out_dir.join("zkpoex_enc_key");
+        // The fixture JSON is shared either way, so it never holds the key.
+        let assert_fixtures_keyless = |dirs: &OutputDirs| {
+            for dir in [&dirs.out_dir, &dirs.fixtures_dir] {
+                let json = fs::read_to_string(dir.join("zkpoex_fixture.json")).unwrap();
+                let json: serde_json::Value = serde_json::from_str(&json).unwrap();
+                assert!(json.get("key").is_none(), "{}", dir.display());
+            }
+        };
+
+        write_fixture_files(&dirs, &fixture, Some(FASTNET), "").unwrap();
+        assert_eq!(fs::read(&key_path).unwrap(), fixture.key);
+        assert_fixtures_keyless(&dirs);
+
+        dirs.persist_key = false;
+        write_fixture_files(&dirs, &fixture, Some(FASTNET), "").unwrap();
+        assert!(!key_path.exists());
+        assert_fixtures_keyless(&dirs);
+    }
+
+    #[test]
+    fn unpersisted_key_leaves_no_trace_in_the_written_files() {
+        use zkpoex_script::{
+            backend::MockBackend, disclose::key_hash, drand::MockDrand, inputs_hash,
+            report::ProgramReport, PublicValues,
+        };
+
+        let key = [0x5a; 32];
+        let settings = BlockchainSettings::default();
+        let public_values = PublicValues {
+            before: "{}".to_string(),
+            after: "{}".to_string(),
+            hash_private_inputs: PrivateInputsDigest([1; 32]),
+            chacha_cipher: vec![2, 3],
+            key_hash: key_hash(&key),
+            inputs_hash: inputs_hash("63d9b770", &settings.to_json()),
+        };
+        let backend = MockBackend {
+            public_values: public_values.encode(),
+            report: ProgramReport::default(),
+            vkey: "0x00".to_string(),
+        };
+        let options = ProveOptions {
+            disclosure: Disclosure::After(Duration::from_secs(3600)),
+            enc_key: Some(key),
+            setup_cache: None,
+            ..Default::default()
+        };
+        let prover =
+            ZkPoExProver::with_backend(backend, options, None, &MockDrand::default()).unwrap();
+        let (fixture, _) = prover.prove("63d9b770", &settings).unwrap();
+
+        let (dirs, _root) = temp_output_dirs("no-key");
+        write_fixture_files(&dirs, &fixture, Some(FASTNET), "").unwrap();
+
+        // The key as raw bytes, as hex, or as the JSON array serde writes a byte array as.
+        let traces = [
+            key.to_vec(),
+            hex::encode(key).into_bytes(),
+            serde_json::to_string(&key).unwrap().into_bytes(),
+        ];
+        for dir in [&dirs.out_dir, &dirs.fixtures_dir] {
+            for entry in fs::read_dir(dir).unwrap() {
+                let path = entry.unwrap().path();
+                // Compared without whitespace, so pretty-printed arrays are caught too.
+                let contents: Vec<u8> = fs::read(&path)
+                    .unwrap()
+                    .into_iter()
+                    .filter(|b| !b.is_ascii_whitespace())
+                    .collect();
+                for trace in &traces {
+                    assert!(
+                        !contents.windows(trace.len()).any(|w| w == trace),
+                        "{} holds the key",
+                        path.display()
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn outputs_land_where_intended_from_any_directory() {
+        let _process = lock_process();
+        let (_, root) = temp_output_dirs("cwd");
+        fs::create_dir_all(&root.0).unwrap();
+        let elsewhere = root.0.canonicalize().unwrap();
+
+        let original = std::env::current_dir().unwrap();
+        std::env::set_current_dir(&elsewhere).unwrap();
+        let args = ProveArgs::try_parse_from(["prove", "--calldata", "63d9b770"]);
+        std::env::set_current_dir(original).unwrap();
+        let args = args.unwrap();
+
+        // Outputs go next to where the prover runs, the fixture into the repository's contracts.
+        assert_eq!(args.out_dir, elsewhere.join("data"));
+        assert_eq!(
+            args.fixtures_dir,
+            Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("..")
+                .join("contracts")
+                .join("src")
+                .join("fixtures")
+        );
+
+        // Back in the original directory, the outputs still go where the arguments pointed.
+        let dirs = OutputDirs {
+            fixtures_dir: elsewhere.join("fixtures"),
+            ..args.output_dirs()
+        };
+        write_fixture_files(&dirs, &test_fixture(), Some(FASTNET), "").unwrap();
+        assert!(elsewhere.join("data").join("zkpoex_fixture.json").is_file());
+    }
+
+    #[test]
+    fn second_run_with_identical_inputs_is_skipped() {
+        let (dirs, _root) = temp_output_dirs("skip");
+        let fixture = test_fixture();
+        let settings = fixture.blockchain_settings.clone();
+        let inputs = |calldata: &'static str| DeterministicInputs {
+            vkey: "0x00",
+            calldata,
+            blockchain_settings: &settings,
+            proof_mode: ProofMode::Compressed,
+            cipher: PayloadCipher::ChaCha20,
+        };
+
+        assert!(!outputs_exist(&dirs, "", &inputs("63d9b770")));
+
+        // What the first run leaves behind.
+        write_fixture_files(&dirs, &fixture, Some(FASTNET), "").unwrap();
+        write(dirs.out_dir.join("zkpoex.bincode"), b"proof").unwrap();
+
+        assert!(outputs_exist(&dirs, "", &inputs("63d9b770")));
+        assert!(!outputs_exist(&dirs, "", &inputs("deadbeef")));
+        assert!(!outputs_exist(&dirs, "_0", &inputs("63d9b770")));
+    }
+
+    #[test]
+    fn split_key_writes_one_tlock_file_per_share() {
+        let mut fixture = test_fixture();
+        fixture.tlock_cipher = vec![];
+        fixture.tlock_shares = [100, 200, 300]
+            .map(|round| TlockShare {
+                round,
+                tlock_cipher: vec![5; 8],
+            })
+            .to_vec();
+
+        let bundles = tlock_bundles(&fixture, Some(FASTNET), "_1");
+        let names: Vec<_> = bundles.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "zkpoex_tlock_1_share0",
+                "zkpoex_tlock_1_share1",
+                "zkpoex_tlock_1_share2"
+            ]
+        );
+        assert_eq!(bundles[2].1.round, 300);
+
+        let args = parse_args([
+            "prove",
+            "--calldata",
+            "63d9b770",
+            "--split",
+            "2-of-3",
+            "--share-round",
+            "100",
+            "--share-round",
+            "200",
+            "--share-round",
+            "300",
+        ])
+        .unwrap();
+        assert_eq!(args.split().unwrap().rounds, [100, 200, 300]);
+
+        let err = parse_args(["prove", "--calldata", "63d9b770", "--split", "2-of-3"]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::MissingRequiredArgument);
+    }
+
+    #[test]
+    fn no_tlock_writes_no_tlock_cipher() {
+        let args = parse_args(["prove", "--calldata", "63d9b770", "--no-tlock"]).unwrap();
+        assert_eq!(args.disclosure().unwrap(), Disclosure::Immediate);
+        let err = parse_args([
+            "prove",
+            "--calldata",
+            "63d9b770",
+            "--no-tlock",
+            "--round-number",
+            "42",
+        ])
+        .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ArgumentConflict);
+
+        let (dirs, _root) = temp_output_dirs("no-tlock");
+        let mut fixture = test_fixture();
+        fixture.round = None;
+        fixture.tlock_cipher = vec![];
+
+        assert!(tlock_bundles(&fixture, None, "").is_empty());
+        write_fixture_files(&dirs, &fixture, None, "").unwrap();
+        assert!(dirs.out_dir.join("zkpoex_chacha").is_file());
+        assert!(!dirs.out_dir.join("zkpoex_tlock").exists());
+
+        let json: serde_json::Value = serde_json::from_str(
+            &fs::read_to_string(dirs.out_dir.join("zkpoex_fixture.json")).unwrap(),
+        )
+        .unwrap();
+        assert!(json.get("tlockCipher").is_none());
+        assert!(json.get("round").is_none());
+    }
+
+    #[test]
+    fn enc_key_must_be_32_bytes_of_hex() {
+        let hex = "07".repeat(32);
+        let args = parse_args(["prove", "--calldata", "63d9b770", "--enc-key", &hex]).unwrap();
+        assert_eq!(args.enc_key, Some([7; 32]));
+
+        for invalid in ["0707", "zz"] {
+            let err =
+                parse_args(["prove", "--calldata", "63d9b770", "--enc-key", invalid]).unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::ValueValidation, "{invalid}");
+        }
+    }
+
+    #[test]
+    fn supplied_nonce_ends_up_in_the_bundle_unchanged() {
+        let nonce = "0x0102030405060708090a0b0c";
+        let args = parse_args(["prove", "--calldata", "63d9b770", "--nonce", nonce]).unwrap();
+        let nonce = args.nonce().unwrap().unwrap();
+        assert_eq!(nonce, (1..=12).collect::<Vec<u8>>());
+
+        let xchacha = parse_args([
+            "prove",
+            "--calldata",
+            "63d9b770",
+            "--nonce",
+            "0102030405060708090a0b0c",
+            "--cipher",
+            "xchacha20",
+        ])
+        .unwrap();
+        assert_eq!(
+            xchacha.nonce().unwrap_err().kind(),
+            ErrorKind::ValueValidation
+        );
+
+        let (dirs, _root) = temp_output_dirs("nonce");
+        let fixture = SP1ZkPoExProofFixture {
+            nonce: nonce.clone(),
+            ..test_fixture()
+        };
+        write_fixture_files(&dirs, &fixture, Some(FASTNET), "").unwrap();
+        let chacha = fs::read(dirs.out_dir.join("zkpoex_chacha")).unwrap();
+        assert_eq!(CipherBundle::from_bytes(&chacha).unwrap().nonce, nonce);
+    }
+
+    #[test]
+    fn relative_out_dir_resolves_against_the_current_directory() {
+        let _process = lock_process();
+        let args =
+            ProveArgs::try_parse_from(["prove", "--calldata", "63d9b770", "--out-dir", "run-1"])
+                .unwrap();
+        assert_eq!(args.out_dir, std::env::current_dir().unwrap().join("run-1"));
+    }
+
+    #[test]
+    fn json_output_is_valid_json() {
+        let args = parse_args(["prove", "--calldata", "63d9b770", "--json-output"]).unwrap();
+        assert_eq!(args.output(), Output::Json);
+
+        let line = summary_json(&test_fixture(), "0x1234").to_string();
+        assert!(!line.contains('\n'));
+        let summary: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(summary["vkey"], "0x00");
+        assert_eq!(summary["publicValues"], "0x1234");
+        assert_eq!(summary["round"], 42);
+    }
+
+    #[test]
+    fn chain_preset_sets_chain_id() {
+        let args = parse_args([
+            "prove",
+            "--calldata",
+            "63d9b770",
+            "--chain-preset",
+            "mainnet",
+        ])
+        .unwrap();
+        assert_eq!(args.blockchain_settings().unwrap().chain_id, 1);
+    }
+
+    #[test]
+    fn explicit_flags_override_the_preset() {
+        let args = parse_args([
+            "prove",
+            "--calldata",
+            "63d9b770",
+            "--chain-preset",
+            "sepolia",
+            "--chain-id",
+            "31337",
+            "--block-base-fee-per-gas",
+            "7",
+        ])
+        .unwrap();
+        let settings = args.blockchain_settings().unwrap();
+        assert_eq!(settings.chain_id, 31337);
+        assert_eq!(settings.block_base_fee_per_gas, U256::from(7));
+        assert_eq!(settings.block_gas_limit, 30_000_000);
+    }
 }
@@ -0,0 +1,212 @@
+//! Timelocks the key of a proven exploit again to a later drand round, e.g. when the disclosure
+//! deadline slips, without proving again. Only the tlock bundle is rewritten: the proof and the
+//! ChaCha ciphertext stay valid as they are.
+//!
+//! You can run this script using the following command:
+//! ```shell
+//! cargo run --package zkpoex-script --bin reseal --release -- --duration 30d
+//! ```
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::ExitCode,
+    time::SystemTime,
+};
+
+use clap::{ArgGroup, Parser};
+use zkpoex_script::{
+    bundle::{BundleError, CipherBundle},
+    cipher::parse_key,
+    delay::{parse_delay, DelayError, DisclosureDelay},
+    disclose::key_hash,
+    disclosure_round,
+    drand::{ConnectError, DrandChain, DrandSource, DEFAULT_DRAND_URL},
+    drand_round::RoundError,
+    logging::{setup_logging, LogFormat},
+    manifest::{Manifest, ManifestError},
+    output::{resolve_dir, write_atomic, DEFAULT_OUT_DIR, ENC_KEY_FILE},
+    reseal::{reseal, ResealError},
+    Disclosure, PublicValues, PublicValuesError,
+};
+
+/// The arguments for the reseal command.
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+#[clap(group(ArgGroup::new("until").required(true).args(["round_number", "duration"])))]
+struct ResealArgs {
+    /// The directory the prove command wrote its outputs to.
+    #[clap(long, default_value = DEFAULT_OUT_DIR, value_parser = resolve_dir)]
+    out_dir: PathBuf,
+
+    /// The key as hex, rather than from `zkpoex_enc_key` in the out dir.
+    #[clap(long, value_parser = parse_key)]
+    enc_key: Option<[u8; 32]>,
+
+    #[clap(long, help = "the drand round to timelock the key to")]
+    round_number: Option<u64>,
+
+    #[clap(
+        long,
+        value_parser = parse_delay,
+        help = "timelock the key to the round reached after this long from now, e.g. 30d or 6mo"
+    )]
+    duration: Option<DisclosureDelay>,
+
+    #[clap(long, default_value = DEFAULT_DRAND_URL, help = "drand relay of the chain the key is timelocked on")]
+    drand_url: String,
+
+    #[clap(long, help = "expected drand chain hash, appended to --drand-url")]
+    drand_chain_hash: Option<String>,
+}
+
+/// Errors that can occur while resealing a key.
+#[derive(Debug, thiserror::Error)]
+enum ResealCommandError {
+    #[error("{}: {source}", .path.display())]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("{}: {source}", .path.display())]
+    Bundle { path: PathBuf, source: BundleError },
+    #[error("{}: {source}", .path.display())]
+    PublicValues {
+        path: PathBuf,
+        source: PublicValuesError,
+    },
+    #[error("{} holds {len} bytes, not a 32 byte key", .path.display())]
+    KeyLength { path: PathBuf, len: usize },
+    /// The key given isn't the one the proof commits to, so the bundle would never open.
+    #[error("the key is not the one {} was proven with", .path.display())]
+    WrongKey { path: PathBuf },
+    #[error("failed to parse {}: {source}", .path.display())]
+    ParseManifest {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+    #[error(transparent)]
+    Delay(#[from] DelayError),
+    #[error(transparent)]
+    Connect(#[from] ConnectError),
+    #[error(transparent)]
+    Round(#[from] RoundError),
+    #[error(transparent)]
+    Reseal(#[from] ResealError),
+    #[error(transparent)]
+    Manifest(#[from] ManifestError),
+}
+
+fn read(path: &Path) -> Result<Vec<u8>, ResealCommandError> {
+    fs::read(path).map_err(|source| ResealCommandError::Io {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+fn write(path: PathBuf, contents: impl AsRef<[u8]>) -> Result<(), ResealCommandError> {
+    write_atomic(&path, contents).map_err(|source| ResealCommandError::Io { path, source })
+}
+
+/// The key from `--enc-key`, or the one prove wrote with `--persist-key`, checked against the
+/// key hash the proof commits to if its public values are there.
+fn key(args: &ResealArgs) -> Result<[u8; 32], ResealCommandError> {
+    let key = match args.enc_key {
+        Some(key) => key,
+        None => {
+            let path = args.out_dir.join(ENC_KEY_FILE);
+            let bytes = read(&path)?;
+            bytes
+                .try_into()
+                .map_err(|bytes: Vec<u8>| ResealCommandError::KeyLength {
+                    path,
+                    len: bytes.len(),
+                })?
+        }
+    };
+
+    let path = args.out_dir.join("zkpoex_public_values.json");
+    if path.is_file() {
+        let json = fs::read_to_string(&path).map_err(|source| ResealCommandError::Io {
+            path: path.clone(),
+            source,
+        })?;
+        let public_values =
+            PublicValues::from_json(&json).map_err(|source| ResealCommandError::PublicValues {
+                path: path.clone(),
+                source,
+            })?;
+        if public_values.key_hash != key_hash(&key) {
+            return Err(ResealCommandError::WrongKey { path });
+        }
+    }
+    Ok(key)
+}
+
+fn run(args: &ResealArgs) -> Result<u64, ResealCommandError> {
+    let key = key(args)?;
+    let tlock_path = args.out_dir.join("zkpoex_tlock");
+    let tlock = CipherBundle::from_bytes(&read(&tlock_path)?).map_err(|source| {
+        ResealCommandError::Bundle {
+            path: tlock_path.clone(),
+            source,
+        }
+    })?;
+
+    let chain = DrandChain {
+        url: args.drand_url.clone(),
+        chain_hash: args.drand_chain_hash.clone(),
+        ..Default::default()
+    };
+    let info = chain.chain_info()?;
+    let disclosure = match (args.round_number, args.duration) {
+        (Some(round), _) => Disclosure::AtRound(round),
+        (None, Some(delay)) => delay.disclosure(SystemTime::now())?,
+        (None, None) => unreachable!("clap requires --round-number or --duration"),
+    };
+    let round = disclosure_round(&info, disclosure)?;
+
+    let resealed = reseal(&tlock, &key, round, &info)?;
+    write(tlock_path, resealed.to_bytes())?;
+
+    // The tlock bundle changed, so a manifest listing it has to be rehashed.
+    let manifest_path = args.out_dir.join("manifest.json");
+    if manifest_path.is_file() {
+        let json = read(&manifest_path)?;
+        let manifest: Manifest =
+            serde_json::from_slice(&json).map_err(|source| ResealCommandError::ParseManifest {
+                path: manifest_path.clone(),
+                source,
+            })?;
+        let manifest = Manifest::build(
+            &args.out_dir,
+            Some(round),
+            manifest.vkey,
+            manifest.artifacts.into_iter().map(|artifact| artifact.path),
+        )?;
+        write(
+            manifest_path,
+            serde_json::to_string_pretty(&manifest).expect("manifest serializes to json"),
+        )?;
+    }
+    Ok(round)
+}
+
+fn main() -> ExitCode {
+    // Setup the logger.
+    setup_logging(LogFormat::Pretty);
+
+    // Parse the command line arguments.
+    let args = ResealArgs::parse();
+
+    match run(&args) {
+        Ok(round) => {
+            println!("Disclosure Round: {round}");
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("Error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
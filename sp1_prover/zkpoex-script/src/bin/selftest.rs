@@ -0,0 +1,32 @@
+//! Checks that the zkVM, tlock and the ecdh hand-off work here, without generating a proof.
+//!
+//! You can run this script using the following command:
+//! ```shell
+//! cargo run --package zkpoex-script --bin selftest --release
+//! ```
+
+use std::process::ExitCode;
+
+use zkpoex_script::selftest::run_selftest;
+
+fn main() -> ExitCode {
+    // Setup the logger.
+    sp1_sdk::utils::setup_logger();
+
+    let mut failed = false;
+    for (subsystem, result) in run_selftest() {
+        match result {
+            Ok(()) => println!("{subsystem:<8} ok"),
+            Err(e) => {
+                println!("{subsystem:<8} FAILED: {e}");
+                failed = true;
+            }
+        }
+    }
+
+    if failed {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
@@ -0,0 +1,59 @@
+//! Unpacks a disclosure bundle written by `prove --bundle` and checks it against its manifest.
+//!
+//! You can run this script using the following command:
+//! ```shell
+//! cargo run --package zkpoex-script --bin unbundle --release -- disclosure.tar.zst
+//! ```
+
+use std::{path::PathBuf, process::ExitCode};
+
+use clap::Parser;
+use zkpoex_script::{
+    archive::unbundle,
+    logging::{setup_logging, LogFormat},
+    output::{resolve_dir, DEFAULT_OUT_DIR},
+};
+
+/// The arguments for the unbundle command.
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct UnbundleArgs {
+    /// The .tar.zst bundle to unpack.
+    bundle: PathBuf,
+
+    #[clap(
+        long,
+        default_value = DEFAULT_OUT_DIR,
+        value_parser = resolve_dir,
+        help = "where to unpack the proof, ciphertexts, fixture and manifest"
+    )]
+    out_dir: PathBuf,
+}
+
+fn main() -> ExitCode {
+    // Setup the logger.
+    setup_logging(LogFormat::Pretty);
+
+    // Parse the command line arguments.
+    let args = UnbundleArgs::parse();
+
+    match unbundle(&args.bundle, &args.out_dir) {
+        Ok(manifest) => {
+            let round = match manifest.round {
+                Some(round) => format!("round {round}"),
+                None => "an untimelocked key".to_string(),
+            };
+            tracing::info!(
+                "Unpacked {} artifacts for {} into {}",
+                manifest.artifacts.len(),
+                round,
+                args.out_dir.display()
+            );
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("Error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
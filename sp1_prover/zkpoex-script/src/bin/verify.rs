@@ -0,0 +1,117 @@
+//! Verifies a saved zkpoex proof against its fixture without re-proving.
+//!
+//! You can run this script using the following command:
+//! ```shell
+//! RUST_LOG=info cargo run --package zkpoex-script --bin verify --release
+//! ```
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use clap::Parser;
+use sp1_sdk::{HashableKey, ProverClient};
+use zkpoex_script::{
+    audit::check_tlock_round,
+    elf::program_elf,
+    output::{default_fixtures_dir, DEFAULT_OUT_DIR},
+    proof::ZkPoExProof,
+    PublicValues, SP1ZkPoExProofFixture,
+};
+
+/// The arguments for the verify command.
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct VerifyArgs {
+    #[clap(long, default_value_os_t = Path::new(DEFAULT_OUT_DIR).join("zkpoex.bincode"))]
+    proof: PathBuf,
+
+    #[clap(long, help = "defaults to the fixture written by the prove command")]
+    fixture: Option<PathBuf>,
+
+    #[clap(
+        long,
+        help = "the ELF the proof was made with, if not the embedded one"
+    )]
+    elf: Option<PathBuf>,
+}
+
+fn main() -> ExitCode {
+    // Setup the logger.
+    sp1_sdk::utils::setup_logger();
+
+    // Parse the command line arguments.
+    let args = VerifyArgs::parse();
+
+    let fixture_path = args
+        .fixture
+        .unwrap_or_else(|| default_fixtures_dir().join("zkpoex_fixture.json"));
+    let fixture = SP1ZkPoExProofFixture::from_json(
+        &fs::read_to_string(fixture_path).expect("failed to read fixture"),
+    )
+    .expect("failed to parse fixture");
+
+    // The timelock parameters can be audited long before the round is published.
+    match (fixture.round, fixture.tlock_shares.is_empty()) {
+        (Some(round), true) => {
+            if let Err(e) = check_tlock_round(&fixture.tlock_cipher, round) {
+                println!("FAIL: {e}");
+                return ExitCode::FAILURE;
+            }
+            println!("Tlock Round: {}", round);
+        }
+        (None, true) if !fixture.tlock_cipher.is_empty() => {
+            println!("FAIL: fixture has a tlock cipher but no round");
+            return ExitCode::FAILURE;
+        }
+        (None, true) => println!("Tlock Round: none, the key isn't timelocked"),
+        (_, false) => {}
+    }
+    for (i, share) in fixture.tlock_shares.iter().enumerate() {
+        if let Err(e) = check_tlock_round(&share.tlock_cipher, share.round) {
+            println!("FAIL: key share {i}: {e}");
+            return ExitCode::FAILURE;
+        }
+        println!("Tlock Share {i} Round: {}", share.round);
+    }
+
+    let proof = ZkPoExProof::load(fixture.proof_mode, &args.proof).expect("failed to load proof");
+
+    // Re-derive the verification key so a fixture pointing at a different program is caught.
+    let client = ProverClient::new();
+    let elf = program_elf(args.elf.as_deref()).expect("failed to load ELF");
+    let (_, vk) = client.setup(&elf);
+    if vk.bytes32() != fixture.vkey {
+        println!(
+            "FAIL: fixture vkey {} does not match the program vkey {}",
+            fixture.vkey,
+            vk.bytes32()
+        );
+        return ExitCode::FAILURE;
+    }
+
+    if let Err(e) = proof.verify(&client, &vk) {
+        println!("FAIL: {e}");
+        return ExitCode::FAILURE;
+    }
+
+    let public_values = match PublicValues::decode(proof.public_values().as_slice()) {
+        Ok(public_values) => public_values,
+        Err(e) => {
+            println!("FAIL: failed to decode public values: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+    println!("Public Values: {:?}", public_values);
+
+    // The fixture's calldata and settings could have been edited after proving.
+    if let Err(e) = fixture.check_inputs(&public_values) {
+        println!("FAIL: {e}");
+        return ExitCode::FAILURE;
+    }
+
+    println!("PASS");
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,170 @@
+//! Audits a fixture received from someone else, reporting every check as PASS, FAIL or SKIP: that
+//! its vkey is the trusted program's, that its timelock and public values agree with it, and, once
+//! the round is published, that the timelock opens to the key the proof commits to.
+//!
+//! You can run this script using the following command:
+//! ```shell
+//! cargo run --package zkpoex-script --bin verify-fixture --release -- \
+//!     --fixture zkpoex_fixture.json --public-values zkpoex_public_values.json --vkey <vkey>
+//! ```
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use clap::Parser;
+use zkpoex_script::{
+    audit::{audit_fixture, Evidence},
+    disclose::{published_signature, DiscloseError},
+    drand::{connect_drand, ConnectError, DrandChain, DEFAULT_DRAND_URL},
+    logging::{setup_logging, LogFormat},
+    output::default_fixtures_dir,
+    FixtureError, PublicValues, PublicValuesError, SP1ZkPoExProofFixture,
+};
+
+/// The arguments for the verify-fixture command.
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct VerifyFixtureArgs {
+    #[clap(long, help = "defaults to the fixture written by the prove command")]
+    fixture: Option<PathBuf>,
+
+    /// The `zkpoex_public_values.json` written next to the proof, to check the fixture against
+    /// what the proof commits to.
+    #[clap(long)]
+    public_values: Option<PathBuf>,
+
+    /// The vkey of the program you trust the fixture to be proven with.
+    #[clap(long)]
+    vkey: Option<String>,
+
+    /// An ELF you built yourself, to derive the trusted vkey from.
+    #[cfg(feature = "prover")]
+    #[clap(long, conflicts_with = "vkey")]
+    elf: Option<PathBuf>,
+
+    #[clap(long, help = "don't contact drand, skipping the disclosure check")]
+    offline: bool,
+
+    #[clap(long, default_value = DEFAULT_DRAND_URL, help = "drand relay of the chain the key is timelocked on")]
+    drand_url: String,
+
+    #[clap(long, help = "expected drand chain hash, appended to --drand-url")]
+    drand_chain_hash: Option<String>,
+}
+
+/// Errors that keep the checks from running at all.
+#[derive(Debug, thiserror::Error)]
+enum VerifyFixtureError {
+    #[error("{}: {source}", .path.display())]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("{}: {source}", .path.display())]
+    Fixture { path: PathBuf, source: FixtureError },
+    #[error("{}: {source}", .path.display())]
+    PublicValues {
+        path: PathBuf,
+        source: PublicValuesError,
+    },
+    #[error(transparent)]
+    Connect(#[from] ConnectError),
+    #[error(transparent)]
+    Disclose(#[from] DiscloseError),
+    #[cfg(feature = "prover")]
+    #[error(transparent)]
+    Prove(#[from] zkpoex_script::ProveError),
+}
+
+fn read(path: &Path) -> Result<String, VerifyFixtureError> {
+    fs::read_to_string(path).map_err(|source| VerifyFixtureError::Io {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+/// The trusted vkey, from `--vkey` or derived from `--elf`.
+fn trusted_vkey(args: &VerifyFixtureArgs) -> Result<Option<String>, VerifyFixtureError> {
+    #[cfg(feature = "prover")]
+    if let Some(elf) = &args.elf {
+        return Ok(Some(zkpoex_script::program_vkey(
+            Some(elf.as_path()),
+            None,
+        )?));
+    }
+    Ok(args.vkey.clone())
+}
+
+/// The signature of the round `fixture` is timelocked to, if it has been published.
+fn round_signature(
+    args: &VerifyFixtureArgs,
+    fixture: &SP1ZkPoExProofFixture,
+) -> Result<Option<Vec<u8>>, VerifyFixtureError> {
+    let round = match fixture.round {
+        Some(round) if !args.offline && fixture.tlock_shares.is_empty() => round,
+        _ => return Ok(None),
+    };
+    let chain = DrandChain {
+        url: args.drand_url.clone(),
+        chain_hash: args.drand_chain_hash.clone(),
+        ..Default::default()
+    };
+    let (client, info) = connect_drand(&chain)?;
+    match published_signature(round, &info, &client) {
+        Ok(signature) => Ok(Some(signature)),
+        Err(DiscloseError::NotYetAvailable { .. }) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn run(args: &VerifyFixtureArgs) -> Result<bool, VerifyFixtureError> {
+    let fixture_path = args
+        .fixture
+        .clone()
+        .unwrap_or_else(|| default_fixtures_dir().join("zkpoex_fixture.json"));
+    let fixture = SP1ZkPoExProofFixture::from_json(&read(&fixture_path)?).map_err(|source| {
+        VerifyFixtureError::Fixture {
+            path: fixture_path,
+            source,
+        }
+    })?;
+
+    let public_values = match &args.public_values {
+        Some(path) => Some(PublicValues::from_json(&read(path)?).map_err(|source| {
+            VerifyFixtureError::PublicValues {
+                path: path.clone(),
+                source,
+            }
+        })?),
+        None => None,
+    };
+    let evidence = Evidence {
+        vkey: trusted_vkey(args)?,
+        public_values,
+        round_signature: round_signature(args, &fixture)?,
+    };
+
+    let report = audit_fixture(&fixture, &evidence);
+    println!("{report}");
+    Ok(report.passed())
+}
+
+fn main() -> ExitCode {
+    // Setup the logger.
+    setup_logging(LogFormat::Pretty);
+
+    // Parse the command line arguments.
+    let args = VerifyFixtureArgs::parse();
+
+    match run(&args) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(e) => {
+            eprintln!("Error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
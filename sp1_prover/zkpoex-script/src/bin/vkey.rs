@@ -0,0 +1,72 @@
+//! Prints the vkey of the zkpoex program without proving anything, e.g. to deploy the verifier
+//! contract with.
+//!
+//! You can run this script using the following command:
+//! ```shell
+//! cargo run --package zkpoex-script --bin vkey --release -- --elf <path>
+//! ```
+
+use std::{path::PathBuf, process::ExitCode};
+
+use clap::Parser;
+use zkpoex_script::{
+    output::write_atomic, program_vkey, setup::default_setup_cache_dir, ProveError,
+};
+
+/// The arguments for the vkey command.
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct VkeyArgs {
+    /// The program ELF to print the vkey of, rather than the one embedded at build time.
+    #[clap(long)]
+    elf: Option<PathBuf>,
+
+    #[clap(long, help = "also write the vkey to this file")]
+    out: Option<PathBuf>,
+
+    #[clap(long, default_value_os_t = default_setup_cache_dir(), help = "where to cache the proving key")]
+    setup_cache_dir: PathBuf,
+
+    #[clap(long, help = "regenerate the proving key instead of using the cache")]
+    no_cache: bool,
+}
+
+/// Errors that can occur while computing the vkey.
+#[derive(Debug, thiserror::Error)]
+enum VkeyError {
+    #[error(transparent)]
+    Prove(#[from] ProveError),
+    #[error("failed to write {}: {source}", .path.display())]
+    Write {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+}
+
+fn run(args: VkeyArgs) -> Result<(), VkeyError> {
+    let setup_cache = (!args.no_cache).then_some(args.setup_cache_dir.as_path());
+    let vkey = program_vkey(args.elf.as_deref(), setup_cache)?;
+    println!("{vkey}");
+
+    if let Some(path) = args.out {
+        write_atomic(&path, format!("{vkey}\n"))
+            .map_err(|source| VkeyError::Write { path, source })?;
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    // Setup the logger.
+    sp1_sdk::utils::setup_logger();
+
+    // Parse the command line arguments.
+    let args = VkeyArgs::parse();
+
+    match run(args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,267 @@
+//! A small framed container so ciphertexts carry the cipher, nonce and round needed to disclose
+//! them.
+//!
+//! Layout: `MAGIC | version: u8 | cipher: u8 | scheme: u8 | nonce | round: u64 (big endian) |
+//! ciphertext`, where the nonce is as long as the cipher needs and the scheme is the drand beacon
+//! scheme the round is signed with. Version 2 bundles have no scheme byte, and version 1 bundles
+//! no cipher byte either and are always ChaCha20.
+
+use crate::{
+    cipher::{CipherError, PayloadCipher},
+    drand::BeaconScheme,
+};
+
+pub const MAGIC: &[u8; 4] = b"zkpx";
+pub const VERSION: u8 = 3;
+
+/// The scheme byte of a bundle that doesn't know its scheme.
+const UNKNOWN_SCHEME: u8 = 0xff;
+
+/// The header of the shortest bundle, a version 1 one.
+const MIN_HEADER_LEN: usize = MAGIC.len() + 1 + 12 + 8;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CipherBundle {
+    pub version: u8,
+    pub cipher: PayloadCipher,
+    /// The beacon scheme `round` is signed with, unknown for bundles older than version 3 and for
+    /// keys that aren't timelocked.
+    pub scheme: Option<BeaconScheme>,
+    pub nonce: Vec<u8>,
+    pub round: u64,
+    pub ciphertext: Vec<u8>,
+}
+
+/// The contents of a cipher file, which may predate [`CipherBundle`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CipherFile {
+    Bundle(CipherBundle),
+    /// A raw ciphertext without nonce or round, as written by older versions of the prover.
+    Legacy(Vec<u8>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum BundleError {
+    #[error("cipher bundle is {len} bytes, shorter than its header")]
+    Truncated { len: usize },
+    #[error("unsupported cipher bundle version {0}")]
+    UnsupportedVersion(u8),
+    #[error("unknown drand beacon scheme byte {0}")]
+    UnknownScheme(u8),
+    #[error(transparent)]
+    Cipher(#[from] CipherError),
+}
+
+impl CipherBundle {
+    pub fn new(
+        cipher: PayloadCipher,
+        scheme: Option<BeaconScheme>,
+        nonce: Vec<u8>,
+        round: u64,
+        ciphertext: Vec<u8>,
+    ) -> Self {
+        Self {
+            version: VERSION,
+            cipher,
+            scheme,
+            nonce,
+            round,
+            ciphertext,
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes =
+            Vec::with_capacity(MAGIC.len() + 3 + self.nonce.len() + 8 + self.ciphertext.len());
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(self.version);
+        if self.version > 1 {
+            bytes.push(self.cipher.id());
+        }
+        if self.version > 2 {
+            bytes.push(self.scheme.map_or(UNKNOWN_SCHEME, BeaconScheme::to_byte));
+        }
+        bytes.extend_from_slice(&self.nonce);
+        bytes.extend_from_slice(&self.round.to_be_bytes());
+        bytes.extend_from_slice(&self.ciphertext);
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, BundleError> {
+        let truncated = BundleError::Truncated { len: bytes.len() };
+        if bytes.len() < MIN_HEADER_LEN || !bytes.starts_with(MAGIC) {
+            return Err(truncated);
+        }
+
+        let (version, rest) = (bytes[MAGIC.len()], &bytes[MAGIC.len() + 1..]);
+        let (cipher, scheme, rest) = match version {
+            1 => (PayloadCipher::ChaCha20, None, rest),
+            2 => (PayloadCipher::from_id(rest[0])?, None, &rest[1..]),
+            3 => {
+                let scheme = match rest[1] {
+                    UNKNOWN_SCHEME => None,
+                    byte => Some(
+                        BeaconScheme::from_byte(byte).ok_or(BundleError::UnknownScheme(byte))?,
+                    ),
+                };
+                (PayloadCipher::from_id(rest[0])?, scheme, &rest[2..])
+            }
+            version => return Err(BundleError::UnsupportedVersion(version)),
+        };
+
+        if rest.len() < cipher.nonce_len() + 8 {
+            return Err(truncated);
+        }
+        let (nonce, rest) = rest.split_at(cipher.nonce_len());
+        let (round, ciphertext) = rest.split_at(8);
+
+        Ok(Self {
+            version,
+            cipher,
+            scheme,
+            nonce: nonce.to_vec(),
+            round: u64::from_be_bytes(round.try_into().unwrap()),
+            ciphertext: ciphertext.to_vec(),
+        })
+    }
+}
+
+impl CipherFile {
+    /// Anything not starting with [`MAGIC`] is taken to be a legacy raw ciphertext.
+    pub fn parse(bytes: &[u8]) -> Result<Self, BundleError> {
+        if bytes.starts_with(MAGIC) {
+            CipherBundle::from_bytes(bytes).map(CipherFile::Bundle)
+        } else {
+            Ok(CipherFile::Legacy(bytes.to_vec()))
+        }
+    }
+
+    pub fn ciphertext(&self) -> &[u8] {
+        match self {
+            CipherFile::Bundle(bundle) => &bundle.ciphertext,
+            CipherFile::Legacy(ciphertext) => ciphertext,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FASTNET: BeaconScheme = BeaconScheme::UnchainedOnG1;
+
+    fn chacha(nonce: u8, round: u64, ciphertext: Vec<u8>) -> CipherBundle {
+        CipherBundle::new(
+            PayloadCipher::ChaCha20,
+            Some(FASTNET),
+            vec![nonce; 12],
+            round,
+            ciphertext,
+        )
+    }
+
+    #[test]
+    fn bundle_round_trips() {
+        let bundle = chacha(3, 12345678, vec![1, 2, 3, 4]);
+        assert_eq!(CipherBundle::from_bytes(&bundle.to_bytes()), Ok(bundle));
+
+        let bundle = CipherBundle::new(
+            PayloadCipher::XChaCha20,
+            Some(FASTNET),
+            vec![3; 24],
+            7,
+            vec![1, 2],
+        );
+        assert_eq!(CipherBundle::from_bytes(&bundle.to_bytes()), Ok(bundle));
+    }
+
+    #[test]
+    fn empty_ciphertext_round_trips() {
+        let bundle = chacha(0, 1, vec![]);
+        assert_eq!(CipherBundle::from_bytes(&bundle.to_bytes()), Ok(bundle));
+    }
+
+    #[test]
+    fn version_1_bundle_is_chacha20() {
+        let mut bytes = MAGIC.to_vec();
+        bytes.push(1);
+        bytes.extend_from_slice(&[3; 12]);
+        bytes.extend_from_slice(&42u64.to_be_bytes());
+        bytes.extend_from_slice(&[1, 2, 3]);
+
+        let bundle = CipherBundle::from_bytes(&bytes).unwrap();
+        assert_eq!(bundle.cipher, PayloadCipher::ChaCha20);
+        assert_eq!(bundle.scheme, None);
+        assert_eq!(bundle.nonce, [3; 12]);
+        assert_eq!(bundle.round, 42);
+        assert_eq!(bundle.ciphertext, [1, 2, 3]);
+        assert_eq!(bundle.to_bytes(), bytes);
+    }
+
+    #[test]
+    fn scheme_is_recorded() {
+        let bundle = chacha(3, 42, vec![1]);
+        let bytes = bundle.to_bytes();
+        assert_eq!(bytes[MAGIC.len() + 2], FASTNET.to_byte());
+        assert_eq!(
+            CipherBundle::from_bytes(&bytes).unwrap().scheme,
+            Some(FASTNET)
+        );
+
+        // A version 2 bundle is the same without the scheme byte.
+        let mut v2 = bytes.clone();
+        v2[MAGIC.len()] = 2;
+        v2.remove(MAGIC.len() + 2);
+        let v2 = CipherBundle::from_bytes(&v2).unwrap();
+        assert_eq!(v2.scheme, None);
+        assert_eq!(v2.ciphertext, [1]);
+
+        let mut unknown = bytes;
+        unknown[MAGIC.len() + 2] = 0x7f;
+        assert_eq!(
+            CipherBundle::from_bytes(&unknown),
+            Err(BundleError::UnknownScheme(0x7f))
+        );
+    }
+
+    #[test]
+    fn legacy_raw_ciphertext_is_detected() {
+        let raw = vec![0xde, 0xad, 0xbe, 0xef];
+        assert_eq!(CipherFile::parse(&raw), Ok(CipherFile::Legacy(raw)));
+    }
+
+    #[test]
+    fn truncated_bundle_is_rejected() {
+        let bytes = chacha(3, 1, vec![]).to_bytes();
+        let len = bytes.len() - 1;
+        assert_eq!(
+            CipherFile::parse(&bytes[..len]),
+            Err(BundleError::Truncated { len })
+        );
+
+        // An XChaCha20 header cut short inside its longer nonce.
+        let bytes = CipherBundle::new(
+            PayloadCipher::XChaCha20,
+            Some(FASTNET),
+            vec![3; 24],
+            1,
+            vec![],
+        )
+        .to_bytes();
+        let len = bytes.len() - 1;
+        assert_eq!(
+            CipherFile::parse(&bytes[..len]),
+            Err(BundleError::Truncated { len })
+        );
+    }
+
+    #[test]
+    fn unknown_version_is_rejected() {
+        let mut bytes = chacha(3, 1, vec![]).to_bytes();
+        bytes[MAGIC.len()] = VERSION + 1;
+        assert_eq!(
+            CipherBundle::from_bytes(&bytes),
+            Err(BundleError::UnsupportedVersion(VERSION + 1))
+        );
+    }
+}
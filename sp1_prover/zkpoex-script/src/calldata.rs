@@ -0,0 +1,169 @@
+//! Validating exploit calldata before a proving cycle is spent on it.
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum CalldataError {
+    #[error("calldata has an odd number of hex digits ({0})")]
+    OddLength(usize),
+    /// A non-hex character at the given position of the input, counting any `0x` prefix.
+    #[error("invalid hex character {c:?} at position {index}")]
+    InvalidCharacter { c: char, index: usize },
+    /// A calldata list that is neither a JSON array of strings nor one calldata per line.
+    #[error("malformed calldata list: {0}")]
+    MalformedList(String),
+    /// An invalid entry of a calldata list.
+    #[error("calldata #{index}: {error}")]
+    Entry {
+        index: usize,
+        error: Box<CalldataError>,
+    },
+}
+
+/// Decodes hex calldata, with or without a `0x` prefix.
+pub fn parse_calldata(s: &str) -> Result<Vec<u8>, CalldataError> {
+    let (offset, digits) = match s.strip_prefix("0x") {
+        Some(digits) => (2, digits),
+        None => (0, s),
+    };
+
+    if let Some((index, c)) = digits.char_indices().find(|(_, c)| !c.is_ascii_hexdigit()) {
+        return Err(CalldataError::InvalidCharacter {
+            c,
+            index: offset + index,
+        });
+    }
+
+    if digits.len() % 2 != 0 {
+        return Err(CalldataError::OddLength(digits.len()));
+    }
+
+    Ok(hex::decode(digits).expect("validated hex"))
+}
+
+/// Validates hex calldata and brings it into the one form the program is given: lowercase, without
+/// the `0x` prefix it can't read. Calldatas that decode to the same bytes thus commit to the same
+/// inputs hash and can be told to be duplicates.
+pub fn canonical_calldata(s: &str) -> Result<String, CalldataError> {
+    parse_calldata(s)?;
+    Ok(s.strip_prefix("0x").unwrap_or(s).to_ascii_lowercase())
+}
+
+/// Clap value parser for calldata, giving its [`canonical_calldata`] form.
+pub fn calldata_arg(s: &str) -> Result<String, CalldataError> {
+    canonical_calldata(s)
+}
+
+/// Parses a list of calldatas given either as a JSON array of strings or one per line, validating
+/// each like [`calldata_arg`]. Blank lines are skipped.
+pub fn parse_calldata_list(contents: &str) -> Result<Vec<String>, CalldataError> {
+    let entries: Vec<String> = if contents.trim_start().starts_with('[') {
+        serde_json::from_str(contents).map_err(|e| CalldataError::MalformedList(e.to_string()))?
+    } else {
+        contents
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(str::to_string)
+            .collect()
+    };
+
+    entries
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| {
+            calldata_arg(entry).map_err(|error| CalldataError::Entry {
+                index,
+                error: Box::new(error),
+            })
+        })
+        .collect()
+}
+
+/// Parses the contents of a calldata file. Text is read as a calldata list, like
+/// [`parse_calldata_list`], so a single hex blob is a list of one. Anything else, such as the raw
+/// ABI encoding with its zero padding, is taken to be the calldata bytes themselves.
+pub fn parse_calldata_file(contents: &[u8]) -> Result<Vec<String>, CalldataError> {
+    let is_text = contents
+        .iter()
+        .all(|b| b.is_ascii_graphic() || b.is_ascii_whitespace());
+    match std::str::from_utf8(contents) {
+        Ok(text) if is_text => parse_calldata_list(text),
+        _ => Ok(vec![hex::encode(contents)]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prefix_is_optional() {
+        assert_eq!(
+            parse_calldata("0x63d9b770"),
+            Ok(vec![0x63, 0xd9, 0xb7, 0x70])
+        );
+        assert_eq!(parse_calldata("63d9b770"), Ok(vec![0x63, 0xd9, 0xb7, 0x70]));
+        assert_eq!(calldata_arg("0x63d9b770"), Ok("63d9b770".to_string()));
+    }
+
+    #[test]
+    fn uppercase_hex_is_accepted() {
+        assert_eq!(
+            parse_calldata("0x63D9B770"),
+            Ok(vec![0x63, 0xd9, 0xb7, 0x70])
+        );
+    }
+
+    #[test]
+    fn equal_calldatas_have_one_canonical_form() {
+        assert_eq!(canonical_calldata("0xDEAD"), Ok("dead".to_string()));
+        assert_eq!(canonical_calldata("0xDEAD"), canonical_calldata("dead"));
+        assert_eq!(canonical_calldata("0xDeAd"), canonical_calldata("0xdead"));
+    }
+
+    #[test]
+    fn malformed_calldata_is_rejected() {
+        assert_eq!(
+            parse_calldata("0x63d9g770"),
+            Err(CalldataError::InvalidCharacter { c: 'g', index: 6 })
+        );
+        assert_eq!(parse_calldata("63d9b77"), Err(CalldataError::OddLength(7)));
+    }
+
+    #[test]
+    fn calldata_list_accepts_lines_and_json() {
+        let expected = vec!["63d9b770".to_string(), "deadbeef".to_string()];
+        assert_eq!(
+            parse_calldata_list("0x63d9b770\n\ndeadbeef\n"),
+            Ok(expected.clone())
+        );
+        assert_eq!(
+            parse_calldata_list(r#"["63d9b770", "0xdeadbeef"]"#),
+            Ok(expected)
+        );
+    }
+
+    #[test]
+    fn calldata_list_reports_bad_entry() {
+        assert_eq!(
+            parse_calldata_list("63d9b770\n63d9b77"),
+            Err(CalldataError::Entry {
+                index: 1,
+                error: Box::new(CalldataError::OddLength(7)),
+            })
+        );
+    }
+
+    #[test]
+    fn calldata_file_accepts_hex_and_raw_bytes() {
+        let raw = [0x63, 0xd9, 0xb7, 0x70, 0x00, 0x00, 0x00, 0x01];
+        assert_eq!(
+            parse_calldata_file(&raw),
+            Ok(vec!["63d9b77000000001".to_string()])
+        );
+        assert_eq!(
+            parse_calldata_file(b"0x63d9b77000000001\n"),
+            Ok(vec!["63d9b77000000001".to_string()])
+        );
+        assert!(parse_calldata_file(b"0x63d9b77").is_err());
+    }
+}
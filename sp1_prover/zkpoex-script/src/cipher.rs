@@ -0,0 +1,402 @@
+//! The ciphers the exploit payload can be encrypted with.
+//!
+//! The program is told which one to use by [`PayloadCipher::id`], and encrypts with the key and
+//! nonce the host drew for it. Each suite is a [`Cipher`], so decrypting is the same whichever one
+//! the bundle records.
+
+use std::marker::PhantomData;
+
+use aes_gcm::Aes256Gcm;
+use chacha20::{
+    cipher::{KeyIvInit, StreamCipher},
+    ChaCha20, XChaCha20,
+};
+use chacha20poly1305::{
+    aead::{generic_array::GenericArray, Aead, Payload},
+    ChaCha20Poly1305, KeyInit,
+};
+use clap::ValueEnum;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PayloadCipher {
+    /// ChaCha20 with a 12 byte nonce.
+    #[default]
+    #[clap(name = "chacha20")]
+    ChaCha20,
+    /// XChaCha20 with a 24 byte nonce, long enough to draw at random without worrying about
+    /// collisions.
+    #[clap(name = "xchacha20")]
+    XChaCha20,
+    /// ChaCha20-Poly1305, which also authenticates the payload together with the round and vkey
+    /// it was proven for.
+    #[clap(name = "chacha20poly1305")]
+    ChaCha20Poly1305,
+    /// AES-256-GCM, for environments standardized on AES. Authenticates like ChaCha20-Poly1305.
+    #[clap(name = "aes-256-gcm")]
+    #[serde(rename = "aes-256-gcm")]
+    Aes256Gcm,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum CipherError {
+    #[error("unknown payload cipher id {0}")]
+    UnknownId(u8),
+    #[error("{cipher:?} takes a {expected} byte nonce, got {actual} bytes")]
+    NonceLength {
+        cipher: PayloadCipher,
+        expected: usize,
+        actual: usize,
+    },
+    #[error(transparent)]
+    Integrity(#[from] IntegrityError),
+}
+
+/// The authentication tag of an AEAD payload didn't verify: the ciphertext was
+/// tampered with, or it is being decrypted for a different round or vkey than it was made for.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("payload failed authentication")]
+pub struct IntegrityError;
+
+/// A key given on the command line that isn't 32 bytes of hex.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum KeyError {
+    #[error("key is not hex: {0}")]
+    Hex(#[from] hex::FromHexError),
+    #[error("key is {0} bytes, expected 32")]
+    Length(usize),
+}
+
+/// Parses a 32 byte payload key from hex, with or without a `0x` prefix.
+pub fn parse_key(s: &str) -> Result<[u8; 32], KeyError> {
+    let bytes = hex::decode(s.strip_prefix("0x").unwrap_or(s))?;
+    <[u8; 32]>::try_from(bytes.as_slice()).map_err(|_| KeyError::Length(bytes.len()))
+}
+
+/// Parses a nonce from hex, with or without a `0x` prefix. Its length depends on the cipher, see
+/// [`PayloadCipher::check_nonce`].
+pub fn parse_nonce(s: &str) -> Result<Vec<u8>, hex::FromHexError> {
+    hex::decode(s.strip_prefix("0x").unwrap_or(s))
+}
+
+/// The key material the program encrypts the payload with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PayloadKey {
+    pub cipher: PayloadCipher,
+    pub key: [u8; 32],
+    pub nonce: Vec<u8>,
+}
+
+/// The associated data that ties an authenticated payload to its proof: the disclosure round,
+/// big endian, followed by the vkey as the fixture records it.
+pub fn associated_data(round: u64, vkey: &str) -> Vec<u8> {
+    let mut data = round.to_be_bytes().to_vec();
+    data.extend_from_slice(vkey.as_bytes());
+    data
+}
+
+/// Encryption with a 32 byte key and a nonce of the length the suite takes, which the caller has
+/// checked.
+pub trait Cipher {
+    /// Encrypts `plaintext`, authenticating `associated_data` along with it if the suite can.
+    fn seal(
+        &self,
+        key: &[u8; 32],
+        nonce: &[u8],
+        associated_data: &[u8],
+        plaintext: &[u8],
+    ) -> Vec<u8>;
+
+    /// Inverts [`Cipher::seal`] for the same `associated_data`.
+    fn open(
+        &self,
+        key: &[u8; 32],
+        nonce: &[u8],
+        associated_data: &[u8],
+        ciphertext: &[u8],
+    ) -> Result<Vec<u8>, IntegrityError>;
+}
+
+/// A stream cipher, which ignores the associated data and decrypts by applying the same keystream
+/// again.
+struct Stream<C>(PhantomData<C>);
+
+impl<C> Stream<C> {
+    const SUITE: Self = Stream(PhantomData);
+}
+
+impl<C: KeyIvInit + StreamCipher> Cipher for Stream<C> {
+    fn seal(&self, key: &[u8; 32], nonce: &[u8], _: &[u8], plaintext: &[u8]) -> Vec<u8> {
+        let mut output = plaintext.to_vec();
+        C::new(
+            GenericArray::from_slice(key),
+            GenericArray::from_slice(nonce),
+        )
+        .apply_keystream(&mut output);
+        output
+    }
+
+    fn open(
+        &self,
+        key: &[u8; 32],
+        nonce: &[u8],
+        associated_data: &[u8],
+        ciphertext: &[u8],
+    ) -> Result<Vec<u8>, IntegrityError> {
+        Ok(self.seal(key, nonce, associated_data, ciphertext))
+    }
+}
+
+/// An authenticated cipher, which appends a tag over the ciphertext and the associated data.
+struct Authenticated<A>(PhantomData<A>);
+
+impl<A> Authenticated<A> {
+    const SUITE: Self = Authenticated(PhantomData);
+}
+
+impl<A: Aead + KeyInit> Cipher for Authenticated<A> {
+    fn seal(
+        &self,
+        key: &[u8; 32],
+        nonce: &[u8],
+        associated_data: &[u8],
+        plaintext: &[u8],
+    ) -> Vec<u8> {
+        A::new(GenericArray::from_slice(key))
+            .encrypt(
+                GenericArray::from_slice(nonce),
+                Payload {
+                    msg: plaintext,
+                    aad: associated_data,
+                },
+            )
+            .expect("payload is within the AEAD length limit")
+    }
+
+    fn open(
+        &self,
+        key: &[u8; 32],
+        nonce: &[u8],
+        associated_data: &[u8],
+        ciphertext: &[u8],
+    ) -> Result<Vec<u8>, IntegrityError> {
+        A::new(GenericArray::from_slice(key))
+            .decrypt(
+                GenericArray::from_slice(nonce),
+                Payload {
+                    msg: ciphertext,
+                    aad: associated_data,
+                },
+            )
+            .map_err(|_| IntegrityError)
+    }
+}
+
+impl PayloadCipher {
+    pub fn nonce_len(self) -> usize {
+        match self {
+            PayloadCipher::ChaCha20
+            | PayloadCipher::ChaCha20Poly1305
+            | PayloadCipher::Aes256Gcm => 12,
+            PayloadCipher::XChaCha20 => 24,
+        }
+    }
+
+    /// Whether the cipher authenticates the payload, binding it to the round and vkey.
+    pub fn is_authenticated(self) -> bool {
+        match self {
+            PayloadCipher::ChaCha20 | PayloadCipher::XChaCha20 => false,
+            PayloadCipher::ChaCha20Poly1305 | PayloadCipher::Aes256Gcm => true,
+        }
+    }
+
+    /// The suite that does the encrypting.
+    pub fn suite(self) -> &'static dyn Cipher {
+        match self {
+            PayloadCipher::ChaCha20 => &Stream::<ChaCha20>::SUITE,
+            PayloadCipher::XChaCha20 => &Stream::<XChaCha20>::SUITE,
+            PayloadCipher::ChaCha20Poly1305 => &Authenticated::<ChaCha20Poly1305>::SUITE,
+            PayloadCipher::Aes256Gcm => &Authenticated::<Aes256Gcm>::SUITE,
+        }
+    }
+
+    /// Identifies the cipher to the program and in a [`crate::bundle::CipherBundle`].
+    pub fn id(self) -> u8 {
+        match self {
+            PayloadCipher::ChaCha20 => 0,
+            PayloadCipher::XChaCha20 => 1,
+            PayloadCipher::ChaCha20Poly1305 => 2,
+            PayloadCipher::Aes256Gcm => 3,
+        }
+    }
+
+    pub fn from_id(id: u8) -> Result<Self, CipherError> {
+        match id {
+            0 => Ok(PayloadCipher::ChaCha20),
+            1 => Ok(PayloadCipher::XChaCha20),
+            2 => Ok(PayloadCipher::ChaCha20Poly1305),
+            3 => Ok(PayloadCipher::Aes256Gcm),
+            id => Err(CipherError::UnknownId(id)),
+        }
+    }
+
+    pub fn random_nonce(self, rng: &mut impl Rng) -> Vec<u8> {
+        let mut nonce = vec![0; self.nonce_len()];
+        rng.fill(&mut nonce[..]);
+        nonce
+    }
+
+    /// Encrypts `payload` as the program does. `associated_data` is only authenticated by the
+    /// [`PayloadCipher::is_authenticated`] ciphers; the stream ciphers ignore it.
+    pub fn encrypt(
+        self,
+        key: &[u8; 32],
+        nonce: &[u8],
+        associated_data: &[u8],
+        payload: &[u8],
+    ) -> Result<Vec<u8>, CipherError> {
+        self.check_nonce(nonce)?;
+        Ok(self.suite().seal(key, nonce, associated_data, payload))
+    }
+
+    /// Inverts [`PayloadCipher::encrypt`], failing with [`IntegrityError`] if an authenticated
+    /// payload doesn't verify.
+    pub fn decrypt(
+        self,
+        key: &[u8; 32],
+        nonce: &[u8],
+        associated_data: &[u8],
+        ciphertext: &[u8],
+    ) -> Result<Vec<u8>, CipherError> {
+        self.check_nonce(nonce)?;
+        Ok(self.suite().open(key, nonce, associated_data, ciphertext)?)
+    }
+
+    /// Checks that `nonce` is as long as the cipher takes.
+    pub fn check_nonce(self, nonce: &[u8]) -> Result<(), CipherError> {
+        if nonce.len() != self.nonce_len() {
+            return Err(CipherError::NonceLength {
+                cipher: self,
+                expected: self.nonce_len(),
+                actual: nonce.len(),
+            });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAYLOAD: &[u8] = b"[exploiter bytecode,addresses,storage]";
+
+    #[test]
+    fn xchacha20_round_trips() {
+        let key = [7; 32];
+        let nonce = PayloadCipher::XChaCha20.random_nonce(&mut rand::thread_rng());
+        assert_eq!(nonce.len(), 24);
+
+        let xchacha = PayloadCipher::XChaCha20;
+        let cipher = xchacha.encrypt(&key, &nonce, &[], PAYLOAD).unwrap();
+        assert_ne!(cipher, PAYLOAD);
+        assert_eq!(
+            xchacha.decrypt(&key, &nonce, &[], &cipher).unwrap(),
+            PAYLOAD
+        );
+
+        // The first 12 bytes of the nonce alone don't give the same keystream.
+        assert_ne!(
+            PayloadCipher::ChaCha20
+                .decrypt(&key, &nonce[..12], &[], &cipher)
+                .unwrap(),
+            PAYLOAD
+        );
+    }
+
+    #[test]
+    fn flipped_byte_fails_authentication() {
+        let key = [7; 32];
+        let nonce = [9; 12];
+        let aead = PayloadCipher::ChaCha20Poly1305;
+        let ad = associated_data(42, "0x00ab");
+
+        let mut cipher = aead.encrypt(&key, &nonce, &ad, PAYLOAD).unwrap();
+        assert_eq!(aead.decrypt(&key, &nonce, &ad, &cipher).unwrap(), PAYLOAD);
+
+        // The same ciphertext doesn't open for another round.
+        let other_round = associated_data(43, "0x00ab");
+        assert_eq!(
+            aead.decrypt(&key, &nonce, &other_round, &cipher),
+            Err(CipherError::Integrity(IntegrityError))
+        );
+
+        cipher[3] ^= 1;
+        assert_eq!(
+            aead.decrypt(&key, &nonce, &ad, &cipher),
+            Err(CipherError::Integrity(IntegrityError))
+        );
+    }
+
+    #[test]
+    fn aes_256_gcm_round_trips_and_rejects_other_suites() {
+        let key = [7; 32];
+        let nonce = [9; 12];
+        let aes = PayloadCipher::Aes256Gcm;
+        let ad = associated_data(42, "0x00ab");
+
+        let cipher = aes.encrypt(&key, &nonce, &ad, PAYLOAD).unwrap();
+        assert_eq!(aes.decrypt(&key, &nonce, &ad, &cipher).unwrap(), PAYLOAD);
+        assert_ne!(
+            cipher,
+            PayloadCipher::ChaCha20Poly1305
+                .encrypt(&key, &nonce, &ad, PAYLOAD)
+                .unwrap()
+        );
+
+        // A bundle claiming the wrong AEAD doesn't open.
+        assert_eq!(
+            PayloadCipher::ChaCha20Poly1305.decrypt(&key, &nonce, &ad, &cipher),
+            Err(CipherError::Integrity(IntegrityError))
+        );
+    }
+
+    #[test]
+    fn ids_and_nonce_lengths_are_checked() {
+        for cipher in PayloadCipher::value_variants() {
+            assert_eq!(PayloadCipher::from_id(cipher.id()), Ok(*cipher));
+        }
+        assert_eq!(PayloadCipher::from_id(4), Err(CipherError::UnknownId(4)));
+        assert_eq!(
+            PayloadCipher::XChaCha20.encrypt(&[0; 32], &[0; 12], &[], b""),
+            Err(CipherError::NonceLength {
+                cipher: PayloadCipher::XChaCha20,
+                expected: 24,
+                actual: 12,
+            })
+        );
+    }
+
+    #[test]
+    fn key_must_be_32_bytes_of_hex() {
+        let hex = "07".repeat(32);
+        assert_eq!(parse_key(&hex), Ok([7; 32]));
+        assert_eq!(parse_key(&format!("0x{hex}")), Ok([7; 32]));
+        assert_eq!(parse_key(&"07".repeat(31)), Err(KeyError::Length(31)));
+        assert!(matches!(parse_key(&"zz".repeat(32)), Err(KeyError::Hex(_))));
+    }
+
+    #[test]
+    fn cipher_parses_from_the_command_line() {
+        for (value, cipher) in [
+            ("chacha20", PayloadCipher::ChaCha20),
+            ("xchacha20", PayloadCipher::XChaCha20),
+            ("chacha20poly1305", PayloadCipher::ChaCha20Poly1305),
+            ("aes-256-gcm", PayloadCipher::Aes256Gcm),
+        ] {
+            assert_eq!(PayloadCipher::from_str(value, false), Ok(cipher));
+        }
+    }
+}
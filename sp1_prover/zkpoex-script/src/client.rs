@@ -0,0 +1,268 @@
+//! Turning SP1 prover failures into errors that say what to fix.
+//!
+//! The SDK reports a misconfigured prover by panicking, or with an opaque `anyhow` error, from deep
+//! inside `ProverClient::new`, `setup` or `prove_*`. The usual causes are few (a missing
+//! `SP1_PRIVATE_KEY`, an unreachable prover network, no GPU for the CUDA prover), so the messages
+//! are matched against them to point at the likely fix.
+//!
+//! Which prover runs is up to `SP1_PROVER` unless a [`ProverConfig`] picks one, e.g. the prover
+//! network so a laptop can offload proving. The network's credentials are checked up front, since
+//! the SDK only notices they are missing or malformed once it is asked to prove.
+
+use std::panic::{self, AssertUnwindSafe};
+
+use clap::ValueEnum;
+use sp1_sdk::ProverClient;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ProverError {
+    #[error("failed to set up the SP1 prover: {reason} ({hint})")]
+    ProverSetupFailed { reason: String, hint: &'static str },
+    #[error("the SP1 prover network is unreachable: {reason} ({hint})")]
+    ProverNetworkUnreachable { reason: String, hint: &'static str },
+    /// The prover network refused the request's signature, or the key can't sign at all.
+    #[error("the SP1 prover network rejected the credentials: {reason} ({hint})")]
+    ProverAuthFailed { reason: String, hint: &'static str },
+}
+
+/// The prover to run on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ProverKind {
+    /// Prove on this machine.
+    Local,
+    /// Send proof requests to the SP1 prover network, signed with `SP1_PRIVATE_KEY`.
+    Network,
+}
+
+impl ProverKind {
+    /// The `SP1_PROVER` value selecting the same prover.
+    pub fn name(self) -> &'static str {
+        match self {
+            ProverKind::Local => "local",
+            ProverKind::Network => "network",
+        }
+    }
+}
+
+/// Which prover to construct. The signing key for the network is only ever read from
+/// `SP1_PRIVATE_KEY`, so it doesn't end up in shell history or config files.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProverConfig {
+    /// `None` leaves the choice to `SP1_PROVER`, as `ProverClient::new` does.
+    pub kind: Option<ProverKind>,
+    /// The prover network RPC, instead of the SDK's default or `PROVER_NETWORK_RPC`.
+    pub rpc: Option<String>,
+}
+
+impl ProverConfig {
+    /// The `SP1_PROVER` value in effect, given the one set in the environment.
+    pub fn sp1_prover<'a>(&self, env: Option<&'a str>) -> Option<&'a str> {
+        match self.kind {
+            Some(kind) => Some(kind.name()),
+            None => env,
+        }
+    }
+}
+
+const NETWORK_HINT: &str =
+    "check the connection to the prover network, or unset SP1_PROVER to prove locally";
+const PRIVATE_KEY_HINT: &str = "SP1_PROVER=network needs SP1_PRIVATE_KEY to be set";
+const AUTH_HINT: &str =
+    "SP1_PRIVATE_KEY must be the 32 byte hex key of an account registered with the prover network";
+const GPU_HINT: &str = "SP1_PROVER=cuda needs a CUDA capable GPU, unset it to prove on the CPU";
+const TOOLCHAIN_HINT: &str =
+    "check the SP1 toolchain is installed with sp1up and the ELF was rebuilt with cargo prove build";
+
+/// Checks the prover selected by `SP1_PROVER` has what it needs, before the SDK panics over it.
+pub fn check_prover_env(
+    sp1_prover: Option<&str>,
+    private_key: Option<&str>,
+) -> Result<(), ProverError> {
+    if sp1_prover != Some("network") {
+        return Ok(());
+    }
+    let private_key = private_key.unwrap_or_default();
+    if private_key.is_empty() {
+        return Err(ProverError::ProverSetupFailed {
+            reason: "SP1_PRIVATE_KEY is not set".to_string(),
+            hint: PRIVATE_KEY_HINT,
+        });
+    }
+    let digits = private_key.strip_prefix("0x").unwrap_or(private_key);
+    if digits.len() != 64 || hex::decode(digits).is_err() {
+        return Err(ProverError::ProverAuthFailed {
+            reason: "SP1_PRIVATE_KEY is not a 32 byte hex key".to_string(),
+            hint: AUTH_HINT,
+        });
+    }
+    Ok(())
+}
+
+/// `ProverClient::new`, failing with a [`ProverError`] instead of panicking when the prover
+/// selected by the environment is misconfigured.
+pub fn prover_client() -> Result<ProverClient, ProverError> {
+    prover_client_with(&ProverConfig::default())
+}
+
+/// Like [`prover_client`], constructing the prover `config` selects.
+pub fn prover_client_with(config: &ProverConfig) -> Result<ProverClient, ProverError> {
+    let env = std::env::var("SP1_PROVER").ok();
+    let sp1_prover = config.sp1_prover(env.as_deref());
+    check_prover_env(sp1_prover, std::env::var("SP1_PRIVATE_KEY").ok().as_deref())?;
+    if let (Some("network"), Some(rpc)) = (sp1_prover, &config.rpc) {
+        // The SDK only takes the RPC from the environment.
+        std::env::set_var("PROVER_NETWORK_RPC", rpc);
+    }
+    catch_prover_panic(|| match config.kind {
+        Some(ProverKind::Local) => ProverClient::local(),
+        Some(ProverKind::Network) => ProverClient::network(),
+        None => ProverClient::new(),
+    })
+}
+
+/// Runs `f`, a call into the SDK, turning a panic into the [`ProverError`] its message points to.
+pub fn catch_prover_panic<T>(f: impl FnOnce() -> T) -> Result<T, ProverError> {
+    panic::catch_unwind(AssertUnwindSafe(f)).map_err(|payload| {
+        let reason = payload
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "the SDK panicked".to_string());
+        classify(reason)
+    })
+}
+
+/// The [`ProverError`] a proving failure stands for, if it is one of the known misconfigurations.
+pub fn classify_proof_error(e: &anyhow::Error) -> Option<ProverError> {
+    match classify(format!("{e:#}")) {
+        e @ ProverError::ProverNetworkUnreachable { .. } => Some(e),
+        ProverError::ProverSetupFailed { hint, .. } if hint == TOOLCHAIN_HINT => None,
+        e => Some(e),
+    }
+}
+
+fn classify(reason: String) -> ProverError {
+    let lower = reason.to_lowercase();
+    let mentions = |words: &[&str]| words.iter().any(|word| lower.contains(word));
+
+    if mentions(&[
+        "unauthorized",
+        "unauthenticated",
+        "401",
+        "forbidden",
+        "invalid signature",
+    ]) {
+        ProverError::ProverAuthFailed {
+            reason,
+            hint: AUTH_HINT,
+        }
+    } else if mentions(&["private_key", "private key"]) {
+        ProverError::ProverSetupFailed {
+            reason,
+            hint: PRIVATE_KEY_HINT,
+        }
+    } else if mentions(&[
+        "connect",
+        "network",
+        "dns",
+        "timed out",
+        "unreachable",
+        "rpc",
+    ]) {
+        ProverError::ProverNetworkUnreachable {
+            reason,
+            hint: NETWORK_HINT,
+        }
+    } else if mentions(&["cuda", "gpu", "nvidia"]) {
+        ProverError::ProverSetupFailed {
+            reason,
+            hint: GPU_HINT,
+        }
+    } else {
+        ProverError::ProverSetupFailed {
+            reason,
+            hint: TOOLCHAIN_HINT,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn setup_panic_becomes_a_typed_error() {
+        let err = catch_prover_panic::<()>(|| panic!("SP1_PRIVATE_KEY must be set")).unwrap_err();
+        assert!(matches!(
+            err,
+            ProverError::ProverSetupFailed {
+                hint: PRIVATE_KEY_HINT,
+                ..
+            }
+        ));
+        assert_eq!(
+            err.to_string(),
+            "failed to set up the SP1 prover: SP1_PRIVATE_KEY must be set \
+             (SP1_PROVER=network needs SP1_PRIVATE_KEY to be set)"
+        );
+
+        let err = catch_prover_panic::<()>(|| panic!("failed to connect to rpc")).unwrap_err();
+        assert!(matches!(err, ProverError::ProverNetworkUnreachable { .. }));
+
+        assert_eq!(catch_prover_panic(|| 7).unwrap(), 7);
+    }
+
+    #[test]
+    fn network_prover_needs_a_private_key() {
+        assert!(matches!(
+            check_prover_env(Some("network"), None),
+            Err(ProverError::ProverSetupFailed { .. })
+        ));
+        assert!(check_prover_env(Some("network"), Some(&format!("0x{}", "01".repeat(32)))).is_ok());
+        assert!(matches!(
+            check_prover_env(Some("network"), Some("0x01")),
+            Err(ProverError::ProverAuthFailed { .. })
+        ));
+        assert!(check_prover_env(Some("local"), None).is_ok());
+        assert!(check_prover_env(None, None).is_ok());
+    }
+
+    #[test]
+    fn selecting_the_network_without_credentials_explains_the_fix() {
+        let config = ProverConfig {
+            kind: Some(ProverKind::Network),
+            rpc: Some("https://rpc.example".to_string()),
+        };
+        // Even if the environment asks for the local prover.
+        let err = check_prover_env(config.sp1_prover(Some("local")), None).unwrap_err();
+        assert!(
+            err.to_string().contains("SP1_PRIVATE_KEY is not set"),
+            "{err}"
+        );
+
+        let local = ProverConfig {
+            kind: Some(ProverKind::Local),
+            rpc: None,
+        };
+        assert!(check_prover_env(local.sp1_prover(Some("network")), None).is_ok());
+    }
+
+    #[test]
+    fn rejected_credentials_are_an_auth_error() {
+        let e = anyhow::anyhow!("request failed: 401 Unauthorized");
+        assert!(matches!(
+            classify_proof_error(&e),
+            Some(ProverError::ProverAuthFailed { .. })
+        ));
+    }
+
+    #[test]
+    fn only_known_proof_failures_are_classified() {
+        let e = anyhow::anyhow!("connection refused");
+        assert!(matches!(
+            classify_proof_error(&e),
+            Some(ProverError::ProverNetworkUnreachable { .. })
+        ));
+        assert!(classify_proof_error(&anyhow::anyhow!("program panicked")).is_none());
+    }
+}
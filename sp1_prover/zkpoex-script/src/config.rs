@@ -0,0 +1,161 @@
+//! Reading prover arguments from a TOML file, for runs that should be repeatable.
+//!
+//! Every key names a long flag of the command, in kebab or snake case, and its value becomes the
+//! flag's value: strings and numbers as they are, `true` as a bare switch (`false` leaves it out),
+//! arrays as the flag repeated, and tables as JSON, so `blockchain-settings` can be written inline.
+//! The arguments are placed before the command line ones, so a flag given on the command line
+//! replaces the same key from the file.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use clap::Command;
+use serde_json::Value as JsonValue;
+use toml::Value;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigError {
+    #[error("failed to read config {}: {source}", .path.display())]
+    Read {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("failed to parse config: {0}")]
+    Parse(#[from] toml::de::Error),
+    #[error("unknown config key {0:?}")]
+    UnknownKey(String),
+    #[error("config key {0:?} can't hold a date")]
+    UnsupportedValue(String),
+}
+
+/// The arguments the config file at `path` stands for, checked against the flags of `command`.
+pub fn read_config_args(command: &Command, path: &Path) -> Result<Vec<String>, ConfigError> {
+    let contents = fs::read_to_string(path).map_err(|source| ConfigError::Read {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    config_args(command, &contents)
+}
+
+/// The arguments the TOML `contents` stand for, checked against the flags of `command`.
+pub fn config_args(command: &Command, contents: &str) -> Result<Vec<String>, ConfigError> {
+    let table: toml::Table = contents.parse()?;
+
+    let mut args = vec![];
+    for (key, value) in table {
+        let flag = key.replace('_', "-");
+        // A config can't point at another config.
+        let known = flag != "config"
+            && command
+                .get_arguments()
+                .any(|arg| arg.get_long() == Some(flag.as_str()));
+        if !known {
+            return Err(ConfigError::UnknownKey(key));
+        }
+
+        let values = match value {
+            Value::Array(values) => values,
+            value => vec![value],
+        };
+        for value in values {
+            match value {
+                Value::Boolean(false) => {}
+                Value::Boolean(true) => args.push(format!("--{flag}")),
+                Value::String(s) => args.extend([format!("--{flag}"), s]),
+                Value::Integer(n) => args.extend([format!("--{flag}"), n.to_string()]),
+                Value::Float(n) => args.extend([format!("--{flag}"), n.to_string()]),
+                value @ (Value::Table(_) | Value::Array(_)) => {
+                    let json: JsonValue = value
+                        .try_into()
+                        .map_err(|_| ConfigError::UnsupportedValue(key.clone()))?;
+                    args.extend([format!("--{flag}"), json.to_string()]);
+                }
+                Value::Datetime(_) => return Err(ConfigError::UnsupportedValue(key)),
+            }
+        }
+    }
+    Ok(args)
+}
+
+/// The path given to `--config` in `argv`, if any. This is looked up before clap parses the
+/// arguments, since the file may supply the ones clap requires.
+pub fn config_path(argv: &[String]) -> Option<&str> {
+    argv.iter()
+        .enumerate()
+        .find_map(|(i, arg)| match arg.as_str() {
+            "--config" => argv.get(i + 1).map(String::as_str),
+            arg => arg.strip_prefix("--config="),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Arg;
+
+    use super::*;
+
+    fn command() -> Command {
+        Command::new("prove").args([
+            Arg::new("calldata").long("calldata"),
+            Arg::new("round_number").long("round-number"),
+            Arg::new("blockchain_settings").long("blockchain-settings"),
+            Arg::new("onchain")
+                .long("onchain")
+                .action(clap::ArgAction::SetTrue),
+        ])
+    }
+
+    #[test]
+    fn keys_become_flags() {
+        let args = config_args(
+            &command(),
+            r#"
+                calldata = "63d9b770"
+                round_number = 42
+                onchain = true
+                blockchain-settings = { chain_id = 1 }
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            args,
+            [
+                "--blockchain-settings",
+                r#"{"chain_id":1}"#,
+                "--calldata",
+                "63d9b770",
+                "--onchain",
+                "--round-number",
+                "42",
+            ]
+        );
+    }
+
+    #[test]
+    fn unknown_keys_are_rejected() {
+        assert!(matches!(
+            config_args(&command(), "calldta = \"63d9b770\""),
+            Err(ConfigError::UnknownKey(key)) if key == "calldta"
+        ));
+        assert!(matches!(
+            config_args(&command(), "config = \"other.toml\""),
+            Err(ConfigError::UnknownKey(_))
+        ));
+    }
+
+    #[test]
+    fn config_path_is_found_in_either_form() {
+        let argv = |args: &[&str]| args.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            config_path(&argv(&["prove", "--config", "ci.toml"])),
+            Some("ci.toml")
+        );
+        assert_eq!(
+            config_path(&argv(&["prove", "--config=ci.toml"])),
+            Some("ci.toml")
+        );
+        assert_eq!(config_path(&argv(&["prove", "--dry-run"])), None);
+    }
+}
@@ -0,0 +1,189 @@
+//! Parsing how long to wait before disclosure, including calendar units.
+//!
+//! Responsible-disclosure windows are usually agreed in months or years, which don't have a fixed
+//! length. `6mo` and `1y` are therefore added to the current date on the calendar (so one month
+//! from January 31st is the last day of February), while every other unit is a fixed duration
+//! parsed by `humantime`.
+
+use std::time::{Duration, SystemTime};
+
+use chrono::{DateTime, Months, Utc};
+
+use crate::Disclosure;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DisclosureDelay {
+    Fixed(Duration),
+    Months(u32),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("invalid disclosure delay: {0}")]
+pub struct DelayError(String);
+
+/// The average length of a Gregorian month, which bounds calendar delays.
+const AVERAGE_MONTH: Duration = Duration::from_secs(2_629_746);
+
+/// The longest delay [`parse_delay`] accepts: ten years. Anything longer is almost certainly a
+/// typo, and would timelock the key for good.
+pub const DEFAULT_MAX_DELAY: Duration = Duration::from_secs(120 * AVERAGE_MONTH.as_secs());
+
+pub fn parse_delay(s: &str) -> Result<DisclosureDelay, DelayError> {
+    parse_delay_within(s, DEFAULT_MAX_DELAY)
+}
+
+/// Like [`parse_delay`], rejecting delays longer than `max`. Calendar units count as average
+/// months for the comparison.
+pub fn parse_delay_within(s: &str, max: Duration) -> Result<DisclosureDelay, DelayError> {
+    let delay = parse_unbounded(s)?;
+    let length = match delay {
+        DisclosureDelay::Fixed(d) => Some(d),
+        DisclosureDelay::Months(months) => AVERAGE_MONTH.checked_mul(months),
+    };
+    match length {
+        Some(length) if length <= max => Ok(delay),
+        _ => Err(DelayError(format!(
+            "{s} is longer than the maximum of {}",
+            humantime::format_duration(max)
+        ))),
+    }
+}
+
+fn parse_unbounded(s: &str) -> Result<DisclosureDelay, DelayError> {
+    let calendar = [("mo", 1), ("y", 12)]
+        .into_iter()
+        .find_map(|(unit, months)| Some((s.trim().strip_suffix(unit)?, months)));
+
+    if let Some((count, months)) = calendar {
+        if let Ok(count) = count.trim().parse::<u32>() {
+            return count
+                .checked_mul(months)
+                .map(DisclosureDelay::Months)
+                .ok_or_else(|| DelayError(format!("{s} is too far in the future")));
+        }
+    }
+
+    humantime::parse_duration(s)
+        .map(DisclosureDelay::Fixed)
+        .map_err(|e| DelayError(format!("{s}: {e}")))
+}
+
+/// Parses an absolute RFC 3339 disclosure instant, which has to lie after `now`: a round that was
+/// already published can't hide anything.
+pub fn parse_instant(s: &str, now: SystemTime) -> Result<SystemTime, DelayError> {
+    let at: SystemTime = DateTime::parse_from_rfc3339(s.trim())
+        .map_err(|e| DelayError(format!("{s}: {e}")))?
+        .into();
+    if at <= now {
+        return Err(DelayError(format!("{s} is in the past")));
+    }
+    Ok(at)
+}
+
+pub fn parse_disclose_at(s: &str) -> Result<SystemTime, DelayError> {
+    parse_instant(s, SystemTime::now())
+}
+
+impl DisclosureDelay {
+    /// The instant this delay ends when counted from `from`.
+    pub fn deadline(self, from: SystemTime) -> Option<SystemTime> {
+        match self {
+            DisclosureDelay::Fixed(d) => from.checked_add(d),
+            DisclosureDelay::Months(months) => DateTime::<Utc>::from(from)
+                .checked_add_months(Months::new(months))
+                .map(SystemTime::from),
+        }
+    }
+
+    pub fn disclosure(self, from: SystemTime) -> Result<Disclosure, DelayError> {
+        match self {
+            DisclosureDelay::Fixed(d) => Ok(Disclosure::After(d)),
+            DisclosureDelay::Months(_) => self
+                .deadline(from)
+                .map(Disclosure::At)
+                .ok_or_else(|| DelayError("deadline is out of range".to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(s: &str) -> SystemTime {
+        DateTime::parse_from_rfc3339(s).unwrap().into()
+    }
+
+    #[test]
+    fn calendar_units_are_parsed() {
+        assert_eq!(parse_delay("6mo"), Ok(DisclosureDelay::Months(6)));
+        assert_eq!(parse_delay("1y"), Ok(DisclosureDelay::Months(12)));
+        assert_eq!(
+            parse_delay("90d"),
+            Ok(DisclosureDelay::Fixed(Duration::from_secs(
+                90 * 24 * 60 * 60
+            )))
+        );
+        assert_eq!(
+            parse_delay("15m"),
+            Ok(DisclosureDelay::Fixed(Duration::from_secs(15 * 60)))
+        );
+        assert!(parse_delay("soon").is_err());
+    }
+
+    #[test]
+    fn absurd_delays_are_rejected() {
+        for s in ["5000y", "9000000d", "60000mo"] {
+            let err = parse_delay(s).unwrap_err();
+            assert!(
+                err.to_string().contains("longer than the maximum"),
+                "{s}: {err}"
+            );
+        }
+        assert_eq!(parse_delay("10y"), Ok(DisclosureDelay::Months(120)));
+        assert!(parse_delay("3653d").is_err());
+        assert!(parse_delay_within("2y", Duration::from_secs(365 * 24 * 60 * 60)).is_err());
+    }
+
+    #[test]
+    fn month_differs_from_thirty_days_at_month_end() {
+        let from = utc("2024-01-31T12:00:00Z");
+        assert_eq!(
+            DisclosureDelay::Months(1).deadline(from),
+            Some(utc("2024-02-29T12:00:00Z"))
+        );
+        assert_eq!(
+            parse_delay("30d").unwrap().deadline(from),
+            Some(utc("2024-03-01T12:00:00Z"))
+        );
+    }
+
+    #[test]
+    fn year_honours_leap_days() {
+        assert_eq!(
+            DisclosureDelay::Months(12).deadline(utc("2024-02-29T00:00:00Z")),
+            Some(utc("2025-02-28T00:00:00Z"))
+        );
+    }
+
+    #[test]
+    fn future_instant_is_accepted() {
+        let now = utc("2024-06-01T00:00:00Z");
+        assert_eq!(
+            parse_instant("2025-01-31T12:00:00Z", now),
+            Ok(utc("2025-01-31T12:00:00Z"))
+        );
+        assert_eq!(
+            parse_instant("2025-01-31T14:00:00+02:00", now),
+            Ok(utc("2025-01-31T12:00:00Z"))
+        );
+    }
+
+    #[test]
+    fn past_instant_is_rejected() {
+        let now = utc("2024-06-01T00:00:00Z");
+        let err = parse_instant("2024-05-31T23:59:59Z", now).unwrap_err();
+        assert!(err.to_string().contains("in the past"));
+        assert!(parse_instant("2024-06-01", now).is_err());
+    }
+}
@@ -0,0 +1,122 @@
+//! Comparing fixtures field by field, for golden-file tests of the deterministic parts of a proof.
+
+use std::collections::BTreeSet;
+
+use serde_json::{Map, Value};
+
+use crate::SP1ZkPoExProofFixture;
+
+/// A fixture field whose values differ, named as in the fixture JSON.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldDiff {
+    pub field: String,
+    pub left: Value,
+    pub right: Value,
+}
+
+/// Lists the fields in which `a` and `b` differ, sorted by name, skipping those in
+/// `ignore`. Fields can be named either as in Rust (`hash_private_inputs`) or as in the JSON
+/// (`hashPrivateInputs`).
+pub fn fixture_diff(
+    a: &SP1ZkPoExProofFixture,
+    b: &SP1ZkPoExProofFixture,
+    ignore: &[&str],
+) -> Vec<FieldDiff> {
+    let (a, b) = (fields(a), fields(b));
+    let ignored = |field: &str| {
+        ignore
+            .iter()
+            .any(|name| normalize(name) == normalize(field))
+    };
+
+    let names: BTreeSet<&String> = a.keys().chain(b.keys()).collect();
+
+    names
+        .into_iter()
+        .filter(|name| !ignored(name))
+        .filter_map(|name| {
+            let left = a.get(name).cloned().unwrap_or(Value::Null);
+            let right = b.get(name).cloned().unwrap_or(Value::Null);
+            (left != right).then(|| FieldDiff {
+                field: name.clone(),
+                left,
+                right,
+            })
+        })
+        .collect()
+}
+
+fn fields(fixture: &SP1ZkPoExProofFixture) -> Map<String, Value> {
+    match serde_json::to_value(fixture).expect("fixture serializes to json") {
+        Value::Object(fields) => fields,
+        _ => unreachable!("fixtures serialize to json objects"),
+    }
+}
+
+fn normalize(name: &str) -> String {
+    name.replace('_', "").to_ascii_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        cipher::PayloadCipher, digest::PrivateInputsDigest, proof::ProofMode,
+        settings::BlockchainSettings, FIXTURE_SCHEMA_VERSION,
+    };
+
+    fn fixture(round: u64, nonce: [u8; 12]) -> SP1ZkPoExProofFixture {
+        SP1ZkPoExProofFixture {
+            schema_version: FIXTURE_SCHEMA_VERSION,
+            key: [1; 32],
+            cipher: PayloadCipher::ChaCha20,
+            nonce: nonce.to_vec(),
+            round: Some(round),
+            before: "before".to_string(),
+            after: "after".to_string(),
+            hash_private_inputs: PrivateInputsDigest([7; 32]),
+            chacha_cipher: vec![3; 8],
+            tlock_cipher: vec![4; 8],
+            tlock_shares: vec![],
+            calldata: "63d9b770".to_string(),
+            blockchain_settings: BlockchainSettings::default().to_json(),
+            vkey: "0x00".to_string(),
+            proof_mode: ProofMode::Compressed,
+            proof: None,
+            proving_duration_ms: None,
+            cycles: None,
+            report: None,
+            policy_note: None,
+        }
+    }
+
+    #[test]
+    fn ignored_fields_compare_equal() {
+        let a = fixture(42, [2; 12]);
+        let b = fixture(43, [6; 12]);
+
+        let diff = fixture_diff(&a, &b, &[]);
+        let fields: Vec<_> = diff.iter().map(|d| d.field.as_str()).collect();
+        assert_eq!(fields, ["nonce", "round"]);
+
+        assert!(fixture_diff(&a, &b, &["nonce", "round"]).is_empty());
+    }
+
+    #[test]
+    fn rust_and_json_names_are_both_accepted() {
+        let a = fixture(42, [2; 12]);
+        let mut b = a.clone();
+        b.hash_private_inputs = PrivateInputsDigest([8; 32]);
+        b.proving_duration_ms = Some(1200);
+
+        assert_eq!(
+            fixture_diff(&a, &b, &["hash_private_inputs"]),
+            [FieldDiff {
+                field: "provingDurationMs".to_string(),
+                left: Value::Null,
+                right: Value::from(1200),
+            }]
+        );
+        assert!(fixture_diff(&a, &b, &["hashPrivateInputs", "proving_duration_ms"]).is_empty());
+    }
+}
@@ -0,0 +1,100 @@
+//! The `hash_private_inputs` commitment as a typed value instead of a bare string.
+
+use std::{fmt, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+/// Keccak256 of the exploit's private inputs, as committed by the zkpoex program.
+///
+/// The program commits it as 64 lowercase hex digits without a `0x` prefix, which is also how it
+/// is displayed and serialized, so existing fixtures and public values decode unchanged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct PrivateInputsDigest(pub [u8; 32]);
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum DigestError {
+    #[error("expected 64 hex digits, got {0}")]
+    Length(usize),
+    #[error("invalid hex character {c:?} at position {index}")]
+    InvalidCharacter { c: char, index: usize },
+}
+
+impl fmt::Display for PrivateInputsDigest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&hex::encode(self.0))
+    }
+}
+
+impl FromStr for PrivateInputsDigest {
+    type Err = DigestError;
+
+    /// Parses 64 hex digits, with or without a `0x` prefix.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (offset, digits) = match s.strip_prefix("0x") {
+            Some(digits) => (2, digits),
+            None => (0, s),
+        };
+
+        if let Some((index, c)) = digits.char_indices().find(|(_, c)| !c.is_ascii_hexdigit()) {
+            return Err(DigestError::InvalidCharacter {
+                c,
+                index: offset + index,
+            });
+        }
+
+        let mut digest = [0; 32];
+        hex::decode_to_slice(digits, &mut digest).map_err(|_| DigestError::Length(digits.len()))?;
+        Ok(Self(digest))
+    }
+}
+
+impl TryFrom<String> for PrivateInputsDigest {
+    type Error = DigestError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<PrivateInputsDigest> for String {
+    fn from(digest: PrivateInputsDigest) -> Self {
+        digest.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIGEST: &str = "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470";
+
+    #[test]
+    fn hex_round_trip() {
+        let digest: PrivateInputsDigest = DIGEST.parse().unwrap();
+        assert_eq!(digest.to_string(), DIGEST);
+        assert_eq!(
+            format!("0x{DIGEST}").parse::<PrivateInputsDigest>(),
+            Ok(digest)
+        );
+
+        let json = serde_json::to_string(&digest).unwrap();
+        assert_eq!(json, format!("\"{DIGEST}\""));
+        assert_eq!(
+            serde_json::from_str::<PrivateInputsDigest>(&json).unwrap(),
+            digest
+        );
+    }
+
+    #[test]
+    fn malformed_digest_is_rejected() {
+        assert_eq!(
+            DIGEST[..62].parse::<PrivateInputsDigest>(),
+            Err(DigestError::Length(62))
+        );
+        assert_eq!(
+            format!("0x{}z", &DIGEST[..63]).parse::<PrivateInputsDigest>(),
+            Err(DigestError::InvalidCharacter { c: 'z', index: 65 })
+        );
+    }
+}
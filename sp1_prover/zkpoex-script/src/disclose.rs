@@ -0,0 +1,313 @@
+//! Recovering the exploit once its drand round has been published.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use drand_core::{beacon::RandomnessBeacon, chain::ChainInfo};
+use sha3::{Digest, Keccak256};
+
+use crate::{
+    bundle::CipherBundle,
+    cipher::{associated_data, CipherError, PayloadCipher},
+    drand::{DrandClient, DrandUnavailable},
+    drand_round::{published_round, round_to_time, RoundError},
+    split::{combine, KeyShare, SplitError},
+};
+
+/// Errors that can occur while disclosing an exploit.
+#[derive(Debug, thiserror::Error)]
+pub enum DiscloseError {
+    #[error("failed to fetch drand beacon: {0}")]
+    Drand(DrandUnavailable),
+    #[error("failed to compute the current round: {0}")]
+    Round(RoundError),
+    /// The round the key is timelocked to hasn't been published yet.
+    #[error(
+        "round {round} is not available until {}",
+        humantime::format_rfc3339_seconds(*.available_at)
+    )]
+    NotYetAvailable {
+        round: u64,
+        available_at: SystemTime,
+    },
+    /// The relay served a signature for `round` the chain's public key didn't make, so it can't be
+    /// trusted to open the timelock.
+    #[error("drand signature of round {round} doesn't verify against the chain's public key")]
+    InvalidRoundSignature { round: u64 },
+    #[error("failed to tlock decrypt the key: {0}")]
+    Tlock(anyhow::Error),
+    /// The timelocked key didn't decrypt to a ChaCha20 key.
+    #[error("expected a 32 byte key, got {0} bytes")]
+    InvalidKeyLength(usize),
+    #[error(transparent)]
+    Cipher(#[from] CipherError),
+    #[error(transparent)]
+    Split(#[from] SplitError),
+}
+
+/// The timelocked key doesn't match the `key_hash` a proof commits to.
+#[derive(Debug, thiserror::Error)]
+pub enum MismatchError {
+    #[error("failed to tlock decrypt the key: {0}")]
+    Tlock(anyhow::Error),
+    #[error("the timelocked key hashes to {actual}, but the proof commits to {expected}")]
+    KeyHash { expected: String, actual: String },
+}
+
+/// The `key_hash` the zkpoex program commits for `key`: its keccak256 as lowercase hex.
+pub fn key_hash(key: &[u8]) -> String {
+    hex::encode(Keccak256::digest(key))
+}
+
+/// Decrypts `tlock_cipher` with the drand signature of its round and checks the key it holds
+/// hashes to `expected_key_hash`, taken from the proof's public values rather than a fixture.
+///
+/// This is what ties the timelock to the proof: a match means the key the program encrypted the
+/// exploit with is the one anyone can recover once the round is published.
+pub fn verify_tlock_against_commitment(
+    tlock_cipher: &[u8],
+    round_sig: &[u8],
+    expected_key_hash: &str,
+) -> Result<(), MismatchError> {
+    let mut key = vec![];
+    tlock::decrypt(&mut key, tlock_cipher, round_sig).map_err(MismatchError::Tlock)?;
+
+    let expected = expected_key_hash
+        .strip_prefix("0x")
+        .unwrap_or(expected_key_hash);
+    let actual = key_hash(&key);
+    if !actual.eq_ignore_ascii_case(expected) {
+        return Err(MismatchError::KeyHash {
+            expected: expected.to_string(),
+            actual,
+        });
+    }
+    Ok(())
+}
+
+/// Recovers the payload key from `tlock_cipher` with the signature of `round` and uses it to
+/// decrypt `chacha_cipher` with `cipher`. An authenticated payload only opens for the `vkey` of
+/// the proof it was made with.
+pub fn disclose(
+    tlock_cipher: &[u8],
+    chacha_cipher: &[u8],
+    cipher: PayloadCipher,
+    nonce: &[u8],
+    round: u64,
+    vkey: &str,
+    drand_client: &DrandClient,
+) -> Result<Vec<u8>, DiscloseError> {
+    let info = drand_client.chain_info().map_err(DiscloseError::Drand)?;
+    let key = open_timelock(tlock_cipher, round, &info, drand_client)?;
+    decrypt_payload(key, cipher, nonce, round, vkey, chacha_cipher)
+}
+
+/// Decrypts `chacha_cipher` with the key opened from a timelock. `round` is the disclosure round
+/// the payload was bound to, which a resealed key is no longer timelocked to.
+fn decrypt_payload(
+    key: Vec<u8>,
+    cipher: PayloadCipher,
+    nonce: &[u8],
+    round: u64,
+    vkey: &str,
+    chacha_cipher: &[u8],
+) -> Result<Vec<u8>, DiscloseError> {
+    let key: [u8; 32] = key
+        .try_into()
+        .map_err(|key: Vec<u8>| DiscloseError::InvalidKeyLength(key.len()))?;
+
+    let associated_data = associated_data(round, vkey);
+    Ok(cipher.decrypt(&key, nonce, &associated_data, chacha_cipher)?)
+}
+
+/// Like [`disclose_bundle`] for a key that was split: every share in `tlock_shares` whose round is
+/// published is decrypted, and the key is recovered once there are enough of them. `round` is the
+/// disclosure round the payload was bound to.
+pub fn disclose_split(
+    tlock_shares: &[CipherBundle],
+    chacha: &CipherBundle,
+    round: u64,
+    vkey: &str,
+    drand_client: &DrandClient,
+) -> Result<Vec<u8>, DiscloseError> {
+    let info = drand_client.chain_info().map_err(DiscloseError::Drand)?;
+
+    let mut shares = vec![];
+    for tlock in tlock_shares {
+        match open_timelock(&tlock.ciphertext, tlock.round, &info, drand_client) {
+            Ok(share) => shares.push(KeyShare::from_bytes(&share)?),
+            // Other shares may make up for the ones still locked.
+            Err(DiscloseError::NotYetAvailable { round, .. }) => {
+                tracing::info!("key share for round {round} is still locked")
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    decrypt_payload(
+        combine(&shares)?,
+        chacha.cipher,
+        &chacha.nonce,
+        round,
+        vkey,
+        &chacha.ciphertext,
+    )
+}
+
+/// Decrypts `tlock_cipher` with the signature of `round`, if that round has been published.
+fn open_timelock(
+    tlock_cipher: &[u8],
+    round: u64,
+    info: &ChainInfo,
+    drand_client: &DrandClient,
+) -> Result<Vec<u8>, DiscloseError> {
+    let signature = published_signature(round, info, drand_client)?;
+    let mut plaintext = vec![];
+    tlock::decrypt(&mut plaintext, tlock_cipher, &signature).map_err(DiscloseError::Tlock)?;
+    Ok(plaintext)
+}
+
+/// The signature of `round` of the chain `info` describes, checked against its public key, if that
+/// round has been published.
+pub fn published_signature(
+    round: u64,
+    info: &ChainInfo,
+    drand_client: &DrandClient,
+) -> Result<Vec<u8>, DiscloseError> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let period = Duration::from_secs(info.period());
+    let published =
+        published_round(now, period, info.genesis_time()).map_err(DiscloseError::Round)?;
+    if round > published {
+        return Err(DiscloseError::NotYetAvailable {
+            round,
+            available_at: round_to_time(info, round),
+        });
+    }
+
+    let beacon = drand_client.get(round).map_err(DiscloseError::Drand)?;
+    check_round_signature(&beacon, round, info)?;
+    Ok(beacon.signature())
+}
+
+/// Checks `beacon` is the one of `round` and that its signature was made with the public key of
+/// the chain, rather than taking the relay's word for it.
+fn check_round_signature(
+    beacon: &RandomnessBeacon,
+    round: u64,
+    info: &ChainInfo,
+) -> Result<(), DiscloseError> {
+    match beacon.verify(info.clone()) {
+        Ok(true) if beacon.round() == round => Ok(()),
+        _ => Err(DiscloseError::InvalidRoundSignature { round }),
+    }
+}
+
+/// Like [`disclose`], taking the cipher, nonce and round from the bundles written by the prover.
+/// The key is opened at the round of `tlock`, which is later than that of `chacha` if it was
+/// resealed.
+pub fn disclose_bundle(
+    tlock: &CipherBundle,
+    chacha: &CipherBundle,
+    vkey: &str,
+    drand_client: &DrandClient,
+) -> Result<Vec<u8>, DiscloseError> {
+    let info = drand_client.chain_info().map_err(DiscloseError::Drand)?;
+    let key = open_timelock(&tlock.ciphertext, tlock.round, &info, drand_client)?;
+    decrypt_payload(
+        key,
+        chacha.cipher,
+        &chacha.nonce,
+        chacha.round,
+        vkey,
+        &chacha.ciphertext,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use sha2::Sha256;
+
+    use super::*;
+    use crate::drand::{DrandSource, MockDrand, VECTOR_PUBLIC_KEY, VECTOR_ROUND, VECTOR_SIGNATURE};
+
+    /// A beacon of the default fastnet chain with the given signature.
+    fn beacon(round: u64, signature: &[u8]) -> RandomnessBeacon {
+        serde_json::from_value(serde_json::json!({
+            "round": round,
+            "randomness": hex::encode(Sha256::digest(signature)),
+            "signature": hex::encode(signature),
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn tampered_round_signature_is_rejected() {
+        let info = MockDrand::default().chain_info().unwrap();
+        let mut signature = hex::decode(VECTOR_SIGNATURE).unwrap();
+
+        check_round_signature(&beacon(VECTOR_ROUND, &signature), VECTOR_ROUND, &info).unwrap();
+        // A genuine signature served for another round.
+        assert!(matches!(
+            check_round_signature(&beacon(VECTOR_ROUND, &signature), VECTOR_ROUND + 1, &info),
+            Err(DiscloseError::InvalidRoundSignature { round }) if round == VECTOR_ROUND + 1
+        ));
+
+        signature[7] ^= 1;
+        assert!(matches!(
+            check_round_signature(&beacon(VECTOR_ROUND, &signature), VECTOR_ROUND, &info),
+            Err(DiscloseError::InvalidRoundSignature { round }) if round == VECTOR_ROUND
+        ));
+    }
+
+    #[test]
+    fn chacha20_decrypt_inverts_encryption() {
+        let key = [7; 32];
+        let nonce = [9; 12];
+        let payload = b"[exploiter bytecode,addresses,storage]";
+
+        let chacha = PayloadCipher::ChaCha20;
+        let chacha_cipher = chacha.encrypt(&key, &nonce, &[], payload).unwrap();
+        assert_ne!(&chacha_cipher[..], &payload[..]);
+        assert_eq!(
+            chacha.decrypt(&key, &nonce, &[], &chacha_cipher).unwrap(),
+            payload
+        );
+    }
+
+    #[test]
+    fn key_hash_matches_the_program() {
+        // keccak256 of 32 zero bytes.
+        assert_eq!(
+            key_hash(&[0; 32]),
+            "290decd9548b62a8d60345a988386fc84ba6bc95484008f6362f93160ef3e563"
+        );
+    }
+
+    #[test]
+    fn timelocked_key_matches_its_commitment() {
+        let key = [7; 32];
+        let mut tlock_cipher = vec![];
+        tlock::encrypt(
+            &mut tlock_cipher,
+            &key[..],
+            &hex::decode(VECTOR_PUBLIC_KEY).unwrap(),
+            VECTOR_ROUND,
+        )
+        .unwrap();
+        let signature = hex::decode(VECTOR_SIGNATURE).unwrap();
+
+        verify_tlock_against_commitment(&tlock_cipher, &signature, &key_hash(&key)).unwrap();
+        verify_tlock_against_commitment(
+            &tlock_cipher,
+            &signature,
+            &format!("0x{}", key_hash(&key)),
+        )
+        .unwrap();
+        assert!(matches!(
+            verify_tlock_against_commitment(&tlock_cipher, &signature, &key_hash(&[8; 32])),
+            Err(MismatchError::KeyHash { .. })
+        ));
+    }
+}
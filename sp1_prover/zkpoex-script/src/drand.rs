@@ -0,0 +1,759 @@
+//! Resolving the drand network the disclosure key is timelocked to.
+
+use std::time::{Duration, Instant};
+
+use drand_core::{beacon::RandomnessBeacon, chain::ChainInfo, DrandError, HttpClient};
+use serde::de::DeserializeOwned;
+
+use crate::drand_cache::DrandCache;
+
+/// The drand chain used when none is specified.
+pub const DEFAULT_DRAND_URL: &str =
+    "https://api.drand.sh/dbd506d6ef76e5f386f41c651dcb808c5bcbd75471cc4eafa3f4df7ad4e4c493";
+
+/// Beacon schemes `tlock::encrypt` can produce decryptable ciphertexts for. Chained beacons sign
+/// over the previous signature, so their round signature can't be predicted from the round alone.
+pub const TLOCK_SCHEMES: &[&str] = &[
+    "pedersen-bls-unchained",
+    "bls-unchained-on-g1",
+    "bls-unchained-g1-rfc9380",
+];
+
+/// How a drand chain signs its rounds, as its `schemeID` names it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BeaconScheme {
+    /// `pedersen-bls-chained`: every round signs the previous signature too.
+    Chained,
+    /// `pedersen-bls-unchained`: public key on G1, signatures on G2.
+    Unchained,
+    /// `bls-unchained-on-g1`: public key on G2, signatures on G1, as the default fastnet chain.
+    UnchainedOnG1,
+    /// `bls-unchained-g1-rfc9380`: like `bls-unchained-on-g1`, hashing to G1 as RFC 9380 does.
+    UnchainedG1Rfc9380,
+}
+
+impl BeaconScheme {
+    pub const ALL: [BeaconScheme; 4] = [
+        BeaconScheme::Chained,
+        BeaconScheme::Unchained,
+        BeaconScheme::UnchainedOnG1,
+        BeaconScheme::UnchainedG1Rfc9380,
+    ];
+
+    /// The `schemeID` drand reports for the scheme.
+    pub fn id(self) -> &'static str {
+        match self {
+            BeaconScheme::Chained => "pedersen-bls-chained",
+            BeaconScheme::Unchained => "pedersen-bls-unchained",
+            BeaconScheme::UnchainedOnG1 => "bls-unchained-on-g1",
+            BeaconScheme::UnchainedG1Rfc9380 => "bls-unchained-g1-rfc9380",
+        }
+    }
+
+    /// The scheme `tlock` can encrypt towards that `scheme_id` names.
+    pub fn from_id(scheme_id: &str) -> Result<Self, ChainError> {
+        Self::ALL
+            .into_iter()
+            .find(|scheme| scheme.id() == scheme_id && TLOCK_SCHEMES.contains(&scheme.id()))
+            .ok_or_else(|| ChainError::UnsupportedScheme {
+                expected: TLOCK_SCHEMES,
+                actual: scheme_id.to_string(),
+            })
+    }
+
+    /// The scheme of `info`, checked to be one tlock can encrypt towards with the public key
+    /// `info` reports.
+    ///
+    /// `tlock::encrypt` picks the pairing from the group the public key is on, so a key on the
+    /// other group than the scheme signs with would give ciphertexts no round signature opens.
+    pub fn of(info: &ChainInfo) -> Result<Self, ChainError> {
+        let scheme = Self::from_id(&info.scheme_id())?;
+        let actual = info.public_key().len();
+        if actual != scheme.public_key_len() {
+            return Err(ChainError::PublicKeyLength {
+                scheme: scheme.id(),
+                expected: scheme.public_key_len(),
+                actual,
+            });
+        }
+        Ok(scheme)
+    }
+
+    /// The length of a compressed public key of the scheme: 48 bytes on G1, 96 on G2.
+    pub fn public_key_len(self) -> usize {
+        match self {
+            BeaconScheme::Chained | BeaconScheme::Unchained => 48,
+            BeaconScheme::UnchainedOnG1 | BeaconScheme::UnchainedG1Rfc9380 => 96,
+        }
+    }
+
+    /// The byte a [`CipherBundle`](crate::bundle::CipherBundle) records the scheme as.
+    pub fn to_byte(self) -> u8 {
+        match self {
+            BeaconScheme::Chained => 0,
+            BeaconScheme::Unchained => 1,
+            BeaconScheme::UnchainedOnG1 => 2,
+            BeaconScheme::UnchainedG1Rfc9380 => 3,
+        }
+    }
+
+    pub fn from_byte(byte: u8) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|scheme| scheme.to_byte() == byte)
+    }
+}
+
+/// A drand network given by its relay URL and, optionally, its chain hash.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DrandChain {
+    pub url: String,
+    pub chain_hash: Option<String>,
+    pub retry: RetryPolicy,
+    /// Where to cache the chain info, or `None` to fetch it every time.
+    pub cache: Option<DrandCache>,
+}
+
+impl Default for DrandChain {
+    fn default() -> Self {
+        Self {
+            url: DEFAULT_DRAND_URL.to_string(),
+            chain_hash: None,
+            retry: RetryPolicy::default(),
+            cache: None,
+        }
+    }
+}
+
+impl DrandChain {
+    /// The endpoint of the chain, with the chain hash appended when one is given.
+    pub fn endpoint(&self) -> String {
+        match &self.chain_hash {
+            Some(hash) => format!("{}/{}", self.url.trim_end_matches('/'), hash),
+            None => self.url.clone(),
+        }
+    }
+
+    pub fn client(&self) -> DrandClient {
+        DrandClient::new(
+            HttpRelay::new(&self.endpoint(), self.retry.timeout),
+            self.retry,
+        )
+    }
+
+    /// Checks that `info` describes this chain and that tlock can encrypt towards it.
+    pub fn check(&self, info: &ChainInfo) -> Result<(), ChainError> {
+        if let Some(expected) = &self.chain_hash {
+            let actual = hex::encode(info.hash());
+            if !expected.eq_ignore_ascii_case(&actual) {
+                return Err(ChainError::ChainHashMismatch {
+                    expected: expected.clone(),
+                    actual,
+                });
+            }
+        }
+
+        BeaconScheme::of(info).map(|_| ())
+    }
+}
+
+/// How long to keep retrying a drand relay before giving up on it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt.
+    pub retries: u32,
+    /// Total time to spend on one request, backoff included.
+    pub timeout: Duration,
+    /// The wait before the first retry, doubled after each further failure.
+    pub backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            retries: 3,
+            timeout: Duration::from_secs(30),
+            backoff: Duration::from_millis(500),
+        }
+    }
+}
+
+/// A drand relay that kept failing for the whole [`RetryPolicy`], or failed in a way retrying
+/// won't fix.
+#[derive(Debug, thiserror::Error)]
+#[error("drand relay unavailable after {attempts} attempts: {last}")]
+pub struct DrandUnavailable<E = RelayError> {
+    pub attempts: u32,
+    pub last: E,
+}
+
+/// Runs `request` until it succeeds, backing off exponentially in between, and gives up once
+/// either the retries or the timeout of `policy` are used up.
+pub fn with_retries<T, E>(
+    policy: &RetryPolicy,
+    request: impl FnMut() -> Result<T, E>,
+) -> Result<T, DrandUnavailable<E>> {
+    with_retries_if(policy, |_| true, request)
+}
+
+/// Like [`with_retries`], giving up straight away on an error `retryable` rejects.
+pub fn with_retries_if<T, E>(
+    policy: &RetryPolicy,
+    retryable: impl Fn(&E) -> bool,
+    mut request: impl FnMut() -> Result<T, E>,
+) -> Result<T, DrandUnavailable<E>> {
+    let start = Instant::now();
+    let mut backoff = policy.backoff;
+    let mut attempts = 0;
+    loop {
+        attempts += 1;
+        match request() {
+            Ok(value) => return Ok(value),
+            Err(last)
+                if !retryable(&last)
+                    || attempts > policy.retries
+                    || start.elapsed() + backoff > policy.timeout =>
+            {
+                return Err(DrandUnavailable { attempts, last });
+            }
+            Err(_) => {
+                std::thread::sleep(backoff);
+                backoff = backoff.saturating_mul(2);
+            }
+        }
+    }
+}
+
+/// How many bytes of a relay's answer to keep in a [`RelayError`], enough for an error page's
+/// gist without flooding the log with it.
+const BODY_SNIPPET_LEN: usize = 200;
+
+/// Why a request to a drand relay failed.
+#[derive(Debug, thiserror::Error)]
+pub enum RelayError {
+    /// The relay is throttling us, so backing off and retrying is worth it.
+    #[error("drand relay rate limited the request (HTTP 429): {body}")]
+    RateLimited { body: String },
+    /// The relay or a proxy in front of it failed, e.g. while it restarts.
+    #[error("drand relay failed with HTTP {status}: {body}")]
+    Server { status: u16, body: String },
+    /// Any other error status, e.g. 404 for a chain the relay doesn't follow.
+    #[error("drand relay answered HTTP {status}: {body}")]
+    Status { status: u16, body: String },
+    /// The relay answered with something other than what was asked for, which it will again.
+    #[error("malformed drand response ({source}): {body}")]
+    Malformed {
+        source: serde_json::Error,
+        body: String,
+    },
+    /// No answer at all, e.g. the connection was refused or timed out.
+    #[error("failed to reach drand relay: {0}")]
+    Transport(String),
+}
+
+impl RelayError {
+    /// Whether the same request could succeed if made again a little later.
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            RelayError::RateLimited { .. } | RelayError::Server { .. } | RelayError::Transport(_)
+        )
+    }
+}
+
+/// At most [`BODY_SNIPPET_LEN`] bytes of `body`, cut at a character boundary.
+fn snippet(body: &str) -> String {
+    let body = body.trim();
+    if body.len() <= BODY_SNIPPET_LEN {
+        return body.to_string();
+    }
+    let mut end = BODY_SNIPPET_LEN;
+    while !body.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}...", &body[..end])
+}
+
+/// Interprets an answer of a relay with `status` and `body` as a `T`.
+pub fn parse_response<T: DeserializeOwned>(status: u16, body: &str) -> Result<T, RelayError> {
+    match status {
+        200..=299 => serde_json::from_str(body).map_err(|source| RelayError::Malformed {
+            source,
+            body: snippet(body),
+        }),
+        429 => Err(RelayError::RateLimited {
+            body: snippet(body),
+        }),
+        500..=599 => Err(RelayError::Server {
+            status,
+            body: snippet(body),
+        }),
+        _ => Err(RelayError::Status {
+            status,
+            body: snippet(body),
+        }),
+    }
+}
+
+/// The requests a [`DrandClient`] makes of a relay. Only the HTTP API is used outside of tests.
+pub trait DrandTransport {
+    fn chain_info(&self) -> Result<ChainInfo, RelayError>;
+    fn get(&self, round: u64) -> Result<RandomnessBeacon, RelayError>;
+}
+
+/// The HTTP API of a drand relay, keeping the status and body of failed requests, which
+/// [`HttpClient`] doesn't report.
+pub struct HttpRelay {
+    endpoint: String,
+    agent: ureq::Agent,
+}
+
+impl HttpRelay {
+    /// A relay serving the chain at `endpoint`, giving up on a request after `timeout`.
+    pub fn new(endpoint: &str, timeout: Duration) -> Self {
+        Self {
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            agent: ureq::AgentBuilder::new().timeout(timeout).build(),
+        }
+    }
+
+    fn fetch<T: DeserializeOwned>(&self, path: &str) -> Result<T, RelayError> {
+        let url = format!("{}/{path}", self.endpoint);
+        let response = match self.agent.get(&url).call() {
+            Ok(response) => response,
+            // ureq reports error statuses as errors, along with the response.
+            Err(ureq::Error::Status(_, response)) => response,
+            Err(e) => return Err(RelayError::Transport(e.to_string())),
+        };
+        let status = response.status();
+        let body = response
+            .into_string()
+            .map_err(|e| RelayError::Transport(e.to_string()))?;
+        parse_response(status, &body)
+    }
+}
+
+impl DrandTransport for HttpRelay {
+    fn chain_info(&self) -> Result<ChainInfo, RelayError> {
+        self.fetch("info")
+    }
+
+    fn get(&self, round: u64) -> Result<RandomnessBeacon, RelayError> {
+        self.fetch(&format!("public/{round}"))
+    }
+}
+
+/// A drand relay whose requests are retried according to the chain's [`RetryPolicy`]. Rate
+/// limiting and server errors are backed off from, while a malformed answer fails straight away.
+pub struct DrandClient<T = HttpRelay> {
+    client: T,
+    retry: RetryPolicy,
+}
+
+impl<T: DrandTransport> DrandClient<T> {
+    pub fn new(client: T, retry: RetryPolicy) -> Self {
+        Self { client, retry }
+    }
+
+    pub fn chain_info(&self) -> Result<ChainInfo, DrandUnavailable> {
+        with_retries_if(&self.retry, RelayError::is_transient, || {
+            self.client.chain_info()
+        })
+    }
+
+    pub fn get(&self, round: u64) -> Result<RandomnessBeacon, DrandUnavailable> {
+        with_retries_if(&self.retry, RelayError::is_transient, || {
+            self.client.get(round)
+        })
+    }
+}
+
+/// Errors that can occur while connecting to a drand chain.
+#[derive(Debug, thiserror::Error)]
+pub enum ConnectError {
+    #[error("failed to connect to drand: {0}")]
+    Client(DrandError),
+    #[error(transparent)]
+    Unavailable(DrandUnavailable),
+    #[error("unusable drand chain: {0}")]
+    Chain(ChainError),
+}
+
+/// Connects to `chain` and fetches its info, checking that it is the expected chain and that tlock
+/// can encrypt towards it.
+pub fn connect_drand(chain: &DrandChain) -> Result<(DrandClient, ChainInfo), ConnectError> {
+    connect_with(chain, chain.client())
+}
+
+/// Like [`connect_drand`], over an already constructed client.
+pub fn connect_with<T: DrandTransport>(
+    chain: &DrandChain,
+    client: DrandClient<T>,
+) -> Result<(DrandClient<T>, ChainInfo), ConnectError> {
+    let info = client.chain_info().map_err(ConnectError::Unavailable)?;
+    chain.check(&info).map_err(ConnectError::Chain)?;
+    tracing::debug!(
+        "drand chain {} has a {}s period and genesis at {}",
+        hex::encode(info.hash()),
+        info.period(),
+        info.genesis_time()
+    );
+    Ok((client, info))
+}
+
+/// Where the prover learns the drand chain it timelocks to. [`DrandChain`] fetches it from a relay
+/// with retries, [`MockDrand`] makes it up so the round pipeline can be tested offline.
+pub trait DrandSource {
+    /// The chain info, checked to be usable for tlock.
+    fn chain_info(&self) -> Result<ChainInfo, ConnectError>;
+}
+
+impl DrandSource for DrandChain {
+    fn chain_info(&self) -> Result<ChainInfo, ConnectError> {
+        let fetch = || connect_drand(self).map(|(_, info)| info);
+        let Some(cache) = &self.cache else {
+            return fetch();
+        };
+        let info = cache.chain_info(&self.endpoint(), fetch)?;
+        // The entry may have been written by a build that accepted other chains.
+        self.check(&info).map_err(ConnectError::Chain)?;
+        Ok(info)
+    }
+}
+
+impl DrandSource for HttpClient {
+    fn chain_info(&self) -> Result<ChainInfo, ConnectError> {
+        let info = HttpClient::chain_info(self).map_err(ConnectError::Client)?;
+        BeaconScheme::of(&info).map_err(ConnectError::Chain)?;
+        Ok(info)
+    }
+}
+
+/// A drand chain with a fixed period, genesis and public key, reporting the default fastnet chain
+/// unless told otherwise.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MockDrand {
+    /// Seconds between rounds.
+    pub period: u64,
+    /// Unix time of round 1.
+    pub genesis: u64,
+    pub public_key: Vec<u8>,
+    /// The `schemeID` the chain reports.
+    pub scheme_id: String,
+}
+
+impl Default for MockDrand {
+    fn default() -> Self {
+        Self {
+            period: FASTNET_PERIOD,
+            genesis: FASTNET_GENESIS,
+            public_key: hex::decode(FASTNET_PUBLIC_KEY).expect("valid hex"),
+            scheme_id: BeaconScheme::UnchainedOnG1.id().to_string(),
+        }
+    }
+}
+
+/// The public key of the chain behind [`DEFAULT_DRAND_URL`].
+const FASTNET_PUBLIC_KEY: &str = "a0b862a7527fee3a731bcb59280ab6abd62d5c0b6ea03dc4ddf6612fdfc9d01f01c31542541771903475eb1ec6615f8d0df0b8b6dce385811d6dcf8cbefb8759e5e616a3dfd054c928940766d9a5b9db91e3b697e5d70a975181e007f87fca5e";
+/// Seconds between the rounds of the chain behind [`DEFAULT_DRAND_URL`].
+pub const FASTNET_PERIOD: u64 = 3;
+/// Unix time of round 1 of the chain behind [`DEFAULT_DRAND_URL`].
+pub const FASTNET_GENESIS: u64 = 1677685200;
+
+/// The chain info the relay behind [`DEFAULT_DRAND_URL`] reports, to stand in for it offline.
+pub fn fastnet_chain_info() -> ChainInfo {
+    serde_json::from_value(fastnet_chain_json()).expect("fastnet chain info deserializes")
+}
+
+fn fastnet_chain_json() -> serde_json::Value {
+    serde_json::json!({
+        "public_key": FASTNET_PUBLIC_KEY,
+        "period": FASTNET_PERIOD,
+        "genesis_time": FASTNET_GENESIS,
+        "hash": "dbd506d6ef76e5f386f41c651dcb808c5bcbd75471cc4eafa3f4df7ad4e4c493",
+        "groupHash": "a81e9d63f614ccdb144b8ff79fbd4d5a2d22055c0bfe4ee9a8092003dab1c6c0",
+        "schemeID": BeaconScheme::UnchainedOnG1.id(),
+        "metadata": { "beaconID": "fastnet" }
+    })
+}
+
+/// Round 1000 of the default fastnet chain and the signature its relay published for it, which
+/// stand in for the beacon so tlock can be checked offline.
+pub const VECTOR_PUBLIC_KEY: &str = FASTNET_PUBLIC_KEY;
+pub const VECTOR_ROUND: u64 = 1000;
+pub const VECTOR_SIGNATURE: &str = "9544ddce2fdbe8688d6f5b4f98eed5d63eee3902e7e162050ac0f45905a55657714880adabe3c3096b92767d886567d0";
+
+impl DrandSource for MockDrand {
+    fn chain_info(&self) -> Result<ChainInfo, ConnectError> {
+        let mut info = fastnet_chain_json();
+        info["public_key"] = hex::encode(&self.public_key).into();
+        info["period"] = self.period.into();
+        info["genesis_time"] = self.genesis.into();
+        info["schemeID"] = self.scheme_id.clone().into();
+        info["metadata"]["beaconID"] = "mock".into();
+        let info = serde_json::from_value(info).expect("mock chain info deserializes");
+        BeaconScheme::of(&info).map_err(ConnectError::Chain)?;
+        Ok(info)
+    }
+}
+
+/// Errors for a drand chain that can't be used for timelock encryption.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ChainError {
+    #[error(
+        "drand scheme {actual} is not supported by tlock, expected one of {}",
+        .expected.join(", ")
+    )]
+    UnsupportedScheme {
+        expected: &'static [&'static str],
+        actual: String,
+    },
+    /// The public key is on the other group than the scheme signs with.
+    #[error("drand scheme {scheme} takes a {expected} byte public key, got {actual} bytes")]
+    PublicKeyLength {
+        scheme: &'static str,
+        expected: usize,
+        actual: usize,
+    },
+    #[error("expected drand chain {expected}, got {actual}")]
+    ChainHashMismatch { expected: String, actual: String },
+}
+
+pub fn check_scheme(scheme: &str) -> Result<(), ChainError> {
+    BeaconScheme::from_id(scheme).map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chained_scheme_is_rejected() {
+        let err = check_scheme("pedersen-bls-chained").unwrap_err();
+        assert!(err.to_string().contains("pedersen-bls-chained"));
+        assert!(err.to_string().contains("bls-unchained-on-g1"));
+    }
+
+    #[test]
+    fn chain_hash_is_appended_to_url() {
+        let chain = DrandChain {
+            url: "https://api.drand.sh/".to_string(),
+            chain_hash: Some("52db9ba7".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(chain.endpoint(), "https://api.drand.sh/52db9ba7");
+    }
+
+    fn fast_retries(retries: u32) -> RetryPolicy {
+        RetryPolicy {
+            retries,
+            timeout: Duration::from_secs(1),
+            backoff: Duration::from_millis(1),
+        }
+    }
+
+    #[test]
+    fn transient_failures_are_retried() {
+        // Stands in for a relay that drops the first two requests.
+        let mut calls = 0;
+        let relay = || {
+            calls += 1;
+            if calls <= 2 {
+                Err("connection reset")
+            } else {
+                Ok(calls)
+            }
+        };
+        assert_eq!(with_retries(&fast_retries(3), relay).unwrap(), 3);
+    }
+
+    #[test]
+    fn exhausted_retries_report_the_last_error() {
+        let err = with_retries(&fast_retries(1), || Err::<(), _>("connection reset")).unwrap_err();
+        assert_eq!(err.attempts, 2);
+        assert_eq!(err.last, "connection reset");
+    }
+
+    /// A relay answering with the chain info of the default fastnet chain.
+    struct CannedRelay;
+
+    impl DrandTransport for CannedRelay {
+        fn chain_info(&self) -> Result<ChainInfo, RelayError> {
+            Ok(fastnet_chain_info())
+        }
+
+        fn get(&self, _round: u64) -> Result<RandomnessBeacon, RelayError> {
+            unreachable!("connecting doesn't fetch beacons")
+        }
+    }
+
+    /// A relay answering every request with `status` and `body`, counting the requests.
+    struct ScriptedRelay {
+        status: u16,
+        body: String,
+        requests: std::cell::Cell<u32>,
+    }
+
+    impl ScriptedRelay {
+        fn new(status: u16, body: impl Into<String>) -> Self {
+            Self {
+                status,
+                body: body.into(),
+                requests: Default::default(),
+            }
+        }
+    }
+
+    impl DrandTransport for &ScriptedRelay {
+        fn chain_info(&self) -> Result<ChainInfo, RelayError> {
+            self.requests.set(self.requests.get() + 1);
+            parse_response(self.status, &self.body)
+        }
+
+        fn get(&self, _round: u64) -> Result<RandomnessBeacon, RelayError> {
+            self.requests.set(self.requests.get() + 1);
+            parse_response(self.status, &self.body)
+        }
+    }
+
+    #[test]
+    fn rate_limiting_is_backed_off_from() {
+        let relay = ScriptedRelay::new(429, "rate limit exceeded, slow down");
+        let err = DrandClient::new(&relay, fast_retries(2))
+            .chain_info()
+            .unwrap_err();
+
+        assert_eq!(relay.requests.get(), 3);
+        assert_eq!(err.attempts, 3);
+        assert!(matches!(
+            &err.last,
+            RelayError::RateLimited { body } if body == "rate limit exceeded, slow down"
+        ));
+        assert!(err.to_string().contains("HTTP 429"), "{err}");
+    }
+
+    #[test]
+    fn malformed_response_fails_fast() {
+        let body = format!("<html>{}</html>", "captive portal ".repeat(50));
+        let relay = ScriptedRelay::new(200, body);
+        let err = DrandClient::new(&relay, fast_retries(2))
+            .get(VECTOR_ROUND)
+            .unwrap_err();
+
+        assert_eq!(relay.requests.get(), 1);
+        let RelayError::Malformed { body, .. } = &err.last else {
+            panic!("expected a malformed response, got {}", err.last);
+        };
+        assert!(body.starts_with("<html>captive portal"));
+        assert_eq!(body.len(), BODY_SNIPPET_LEN + "...".len());
+    }
+
+    #[test]
+    fn statuses_are_told_apart() {
+        assert!(matches!(
+            parse_response::<ChainInfo>(503, "upstream unavailable"),
+            Err(RelayError::Server { status: 503, .. })
+        ));
+        let not_found = parse_response::<ChainInfo>(404, "chain not found").unwrap_err();
+        assert!(matches!(not_found, RelayError::Status { status: 404, .. }));
+        assert!(!not_found.is_transient());
+    }
+
+    #[test]
+    fn connect_resolves_the_chain_info() {
+        let chain = DrandChain {
+            chain_hash: Some(
+                "DBD506D6EF76E5F386F41C651DCB808C5BCBD75471CC4EAFA3F4DF7AD4E4C493".to_string(),
+            ),
+            ..Default::default()
+        };
+        let client = DrandClient::new(CannedRelay, fast_retries(0));
+
+        let (_, info) = connect_with(&chain, client).unwrap();
+        assert_eq!(info.period(), 3);
+        assert_eq!(info.genesis_time(), FASTNET_GENESIS);
+    }
+
+    #[test]
+    fn connect_rejects_another_chain() {
+        let chain = DrandChain {
+            chain_hash: Some("52db9ba7".to_string()),
+            ..Default::default()
+        };
+        let client = DrandClient::new(CannedRelay, fast_retries(0));
+
+        assert!(matches!(
+            connect_with(&chain, client),
+            Err(ConnectError::Chain(ChainError::ChainHashMismatch { .. }))
+        ));
+    }
+
+    #[test]
+    fn mock_reports_its_parameters() {
+        let mock = MockDrand {
+            period: 30,
+            genesis: 1_000,
+            ..Default::default()
+        };
+        let info = mock.chain_info().unwrap();
+        assert_eq!(info.period(), 30);
+        assert_eq!(info.genesis_time(), 1_000);
+        assert_eq!(info.public_key(), mock.public_key);
+        assert_eq!(BeaconScheme::of(&info), Ok(BeaconScheme::UnchainedOnG1));
+    }
+
+    #[test]
+    fn unsupported_schemes_are_rejected_by_name() {
+        let chained = MockDrand {
+            scheme_id: "pedersen-bls-chained".to_string(),
+            ..Default::default()
+        };
+        assert!(matches!(
+            chained.chain_info(),
+            Err(ConnectError::Chain(ChainError::UnsupportedScheme { actual, .. }))
+                if actual == "pedersen-bls-chained"
+        ));
+
+        let unknown = MockDrand {
+            scheme_id: "bls-on-g3".to_string(),
+            ..Default::default()
+        };
+        assert!(matches!(
+            unknown.chain_info(),
+            Err(ConnectError::Chain(ChainError::UnsupportedScheme { .. }))
+        ));
+
+        // A G2 key labelled with a scheme whose keys are on G1.
+        let mislabelled = MockDrand {
+            scheme_id: "pedersen-bls-unchained".to_string(),
+            ..Default::default()
+        };
+        assert!(matches!(
+            mislabelled.chain_info(),
+            Err(ConnectError::Chain(ChainError::PublicKeyLength {
+                expected: 48,
+                actual: 96,
+                ..
+            }))
+        ));
+    }
+
+    #[test]
+    fn scheme_bytes_round_trip() {
+        for scheme in BeaconScheme::ALL {
+            assert_eq!(BeaconScheme::from_byte(scheme.to_byte()), Some(scheme));
+        }
+        assert_eq!(BeaconScheme::from_byte(0xff), None);
+    }
+
+    #[test]
+    fn retries_stop_at_the_timeout() {
+        let policy = RetryPolicy {
+            retries: 10,
+            timeout: Duration::from_millis(5),
+            backoff: Duration::from_millis(4),
+        };
+        let err = with_retries(&policy, || Err::<(), _>("timeout")).unwrap_err();
+        assert!(err.attempts < 11);
+    }
+}
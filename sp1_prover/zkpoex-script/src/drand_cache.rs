@@ -0,0 +1,211 @@
+//! Caching the drand chain info, which never changes for a chain, so repeated runs skip the relay
+//! and keep working offline once the cache is warm.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use drand_core::chain::ChainInfo;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    drand::ConnectError,
+    output::{write_atomic, DEFAULT_OUT_DIR},
+};
+
+/// Where the chain info is cached unless told otherwise, relative to where the prover runs.
+pub fn default_drand_cache_dir() -> PathBuf {
+    Path::new(DEFAULT_OUT_DIR).join("drand")
+}
+
+/// How long a cached chain info is used before it is fetched again.
+pub const DEFAULT_DRAND_CACHE_TTL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// An on-disk cache of chain info, one file per relay endpoint. The default endpoint ends in the
+/// chain hash, so every chain gets its own entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DrandCache {
+    pub dir: PathBuf,
+    pub ttl: Duration,
+    /// Fetch the chain info even if a fresh entry is cached, and cache the result.
+    pub refresh: bool,
+}
+
+impl Default for DrandCache {
+    fn default() -> Self {
+        Self {
+            dir: default_drand_cache_dir(),
+            ttl: DEFAULT_DRAND_CACHE_TTL,
+            refresh: false,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Entry {
+    /// Unix time the info was fetched at.
+    fetched_at: u64,
+    info: ChainInfo,
+}
+
+impl DrandCache {
+    pub fn path(&self, endpoint: &str) -> PathBuf {
+        let key = hex::encode(Sha256::digest(endpoint.as_bytes()));
+        self.dir.join(key).with_extension("json")
+    }
+
+    /// The chain info of `endpoint` from the cache if a fresh entry is there, otherwise from
+    /// `fetch`, caching it. Should `fetch` fail, an expired entry is used rather than failing.
+    ///
+    /// Like the setup cache, this is best effort: an unreadable entry is fetched again and a
+    /// failed write only logs.
+    pub fn chain_info(
+        &self,
+        endpoint: &str,
+        fetch: impl FnOnce() -> Result<ChainInfo, ConnectError>,
+    ) -> Result<ChainInfo, ConnectError> {
+        let path = self.path(endpoint);
+        let cached = if self.refresh { None } else { load(&path) };
+        let now = unix_now();
+
+        let stale = match cached {
+            Some(entry) if now.saturating_sub(entry.fetched_at) < self.ttl.as_secs() => {
+                tracing::debug!("loaded drand chain info from {}", path.display());
+                return Ok(entry.info);
+            }
+            stale => stale,
+        };
+
+        match fetch() {
+            Ok(info) => {
+                let entry = Entry {
+                    fetched_at: now,
+                    info,
+                };
+                if let Err(e) = store(&entry, &path) {
+                    tracing::warn!(
+                        "failed to cache drand chain info at {}: {e}",
+                        path.display()
+                    );
+                }
+                Ok(entry.info)
+            }
+            Err(e) => match stale {
+                Some(entry) => {
+                    tracing::warn!(
+                        "{e}, using the expired chain info cached at {}",
+                        path.display()
+                    );
+                    Ok(entry.info)
+                }
+                None => Err(e),
+            },
+        }
+    }
+}
+
+fn load(path: &Path) -> Option<Entry> {
+    let json = fs::read_to_string(path).ok()?;
+    match serde_json::from_str(&json) {
+        Ok(entry) => Some(entry),
+        Err(e) => {
+            tracing::warn!("ignoring corrupt drand cache {}: {e}", path.display());
+            None
+        }
+    }
+}
+
+fn store(entry: &Entry, path: &Path) -> std::io::Result<()> {
+    fs::create_dir_all(path.parent().expect("cache path has a parent"))?;
+    write_atomic(
+        path,
+        serde_json::to_string(entry).expect("chain info serializes to json"),
+    )
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+    use crate::drand::{DrandSource, MockDrand};
+
+    fn cache(name: &str) -> DrandCache {
+        DrandCache {
+            dir: std::env::temp_dir().join(format!("zkpoex-drand-{name}-{}", std::process::id())),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn second_lookup_is_served_from_the_cache() {
+        let cache = cache("warm");
+        let drand = MockDrand::default();
+        let requests = Cell::new(0);
+        let fetch = || {
+            requests.set(requests.get() + 1);
+            drand.chain_info()
+        };
+
+        let first = cache.chain_info("https://relay/chain", fetch).unwrap();
+        let second = cache.chain_info("https://relay/chain", fetch).unwrap();
+        assert_eq!(requests.get(), 1);
+        assert_eq!(second.hash(), first.hash());
+        assert_eq!(second.public_key(), first.public_key());
+
+        // Another endpoint is another chain.
+        cache.chain_info("https://relay/other", fetch).unwrap();
+        assert_eq!(requests.get(), 2);
+
+        let refreshing = DrandCache {
+            refresh: true,
+            ..cache.clone()
+        };
+        refreshing.chain_info("https://relay/chain", fetch).unwrap();
+        assert_eq!(requests.get(), 3);
+        fs::remove_dir_all(cache.dir).unwrap();
+    }
+
+    #[test]
+    fn expired_entry_is_refetched_and_used_when_offline() {
+        let cache = DrandCache {
+            ttl: Duration::ZERO,
+            ..cache("expired")
+        };
+        let drand = MockDrand::default();
+        let requests = Cell::new(0);
+        let fetch = || {
+            requests.set(requests.get() + 1);
+            drand.chain_info()
+        };
+
+        let info = cache.chain_info("https://relay/chain", fetch).unwrap();
+        cache.chain_info("https://relay/chain", fetch).unwrap();
+        assert_eq!(requests.get(), 2);
+
+        // Stands in for an unreachable relay.
+        let offline = MockDrand {
+            scheme_id: "bls-unchained-on-g9".to_string(),
+            ..Default::default()
+        };
+        let cached = cache
+            .chain_info("https://relay/chain", || offline.chain_info())
+            .unwrap();
+        assert_eq!(cached.genesis_time(), info.genesis_time());
+        assert!(cache
+            .chain_info("https://relay/other", || offline.chain_info())
+            .is_err());
+        fs::remove_dir_all(cache.dir).unwrap();
+    }
+}
@@ -0,0 +1,420 @@
+//! Mapping wall-clock time to drand rounds.
+//!
+//! drand publishes round 1 at `genesis` and round `r` at `genesis + (r - 1) * period`. All the
+//! arithmetic here is on whole seconds with integers: drand periods are whole seconds, and the
+//! float division this replaced could round wrong for large offsets.
+
+use std::{
+    ops::Add,
+    time::{Duration, SystemTime, SystemTimeError, UNIX_EPOCH},
+};
+
+use drand_core::chain::ChainInfo;
+
+use crate::settings::BlockchainSettings;
+
+/// Errors that can occur while mapping a point in time to a drand round.
+#[derive(Debug, thiserror::Error)]
+pub enum RoundError {
+    /// The requested time lies before the unix epoch.
+    #[error("time is before the unix epoch: {0}")]
+    BeforeEpoch(#[from] SystemTimeError),
+    /// The chain reports a period of zero seconds, so rounds can't be told apart.
+    #[error("drand period must be at least one second")]
+    InvalidPeriod,
+    /// The requested round is already published, so timelocking to it wouldn't delay anything.
+    #[error("round {requested_round} is not in the future, the current round is {current_round}")]
+    DiscloseTooSoon {
+        requested_round: u64,
+        current_round: u64,
+    },
+    /// Only blocks after the one the settings describe can be projected forward.
+    #[error("block {block} is not after the settings block {settings_block}")]
+    BlockNotAhead { block: u64, settings_block: u64 },
+    #[error("block {0} is too far ahead to project")]
+    BlockOutOfRange(u64),
+    /// An immediate disclosure hands the key over directly, so no round is involved.
+    #[error("an immediate disclosure has no drand round")]
+    NotTimelocked,
+}
+
+/// The round [`current_round`] gives for the instant `t`.
+pub fn round_at(chain_info: &ChainInfo, t: SystemTime) -> Result<u64, RoundError> {
+    let since_epoch = t.duration_since(UNIX_EPOCH)?;
+    let t_unix = since_epoch.as_secs();
+    current_round(
+        t_unix,
+        Duration::from_secs(chain_info.period()),
+        chain_info.genesis_time(),
+    )
+}
+
+/// The instant drand publishes `round`, `genesis + (round - 1) * period`, from which a key
+/// timelocked to it can be disclosed. Rounds 0 and 1 map to genesis.
+///
+/// [`round_at`] stays a round behind, so it only gives `round` one period later.
+pub fn round_to_time(chain_info: &ChainInfo, round: u64) -> SystemTime {
+    UNIX_EPOCH
+        + Duration::from_secs(
+            chain_info.genesis_time() + round.saturating_sub(1) * chain_info.period(),
+        )
+}
+
+/// The rounds from `radius` before to `radius` after the one [`round_at`] gives for `now`, each
+/// with the instant [`round_to_time`] gives for it. Rounds before round 1 don't exist, so a window
+/// reaching back past genesis starts at round 1.
+pub fn rounds_around(
+    chain_info: &ChainInfo,
+    now: SystemTime,
+    radius: u64,
+) -> Result<Vec<(u64, SystemTime)>, RoundError> {
+    let current = round_at(chain_info, now)?;
+    let first = current.saturating_sub(radius).max(1);
+    let last = current.saturating_add(radius);
+    Ok((first..=last)
+        .map(|round| (round, round_to_time(chain_info, round)))
+        .collect())
+}
+
+/// The instant `block` is expected at, counting `seconds_per_block` from the block `settings`
+/// describes.
+///
+/// This is only an estimate. Block times drift, slots get missed and chains change their target
+/// block time, so the actual block can arrive noticeably before or after the projected instant,
+/// and the key with it. Leave a margin when the policy depends on the exact block.
+pub fn block_instant(
+    settings: &BlockchainSettings,
+    block: u64,
+    seconds_per_block: u64,
+) -> Result<SystemTime, RoundError> {
+    let blocks_ahead = block
+        .checked_sub(settings.block_number)
+        .filter(|&blocks| blocks > 0)
+        .ok_or(RoundError::BlockNotAhead {
+            block,
+            settings_block: settings.block_number,
+        })?;
+    blocks_ahead
+        .checked_mul(seconds_per_block)
+        .and_then(|secs| settings.block_timestamp.checked_add(secs))
+        .and_then(|secs| UNIX_EPOCH.checked_add(Duration::from_secs(secs)))
+        .ok_or(RoundError::BlockOutOfRange(block))
+}
+
+/// The round [`current_round`] gives at the instant [`block_instant`] projects for `block`.
+pub fn round_at_block(
+    chain_info: &ChainInfo,
+    settings: &BlockchainSettings,
+    block: u64,
+    seconds_per_block: u64,
+) -> Result<u64, RoundError> {
+    round_at(
+        chain_info,
+        block_instant(settings, block, seconds_per_block)?,
+    )
+}
+
+/// The round [`current_round`] gives for `d` from now.
+pub fn round_after(chain_info: &ChainInfo, d: Duration) -> Result<u64, RoundError> {
+    let t = SystemTime::now().add(d);
+    round_at(chain_info, t)
+}
+
+/// The number of whole periods elapsed between `genesis` and `now`, and at least 1.
+///
+/// The round returned is always published by `now`, so a key timelocked to it is disclosable at
+/// the requested time at the latest.
+pub fn current_round(now: u64, period: Duration, genesis: u64) -> Result<u64, RoundError> {
+    let (next_round, _) = next_round(now, period, genesis)?;
+
+    if next_round <= 1 {
+        Ok(next_round)
+    } else {
+        Ok(next_round - 1)
+    }
+}
+
+/// The latest round drand has published by `now`, or 0 before genesis.
+///
+/// Unlike [`current_round`], which stays a round behind so a key is disclosable by the requested
+/// time at the latest, this is the round whose signature already exists.
+pub fn published_round(now: u64, period: Duration, genesis: u64) -> Result<u64, RoundError> {
+    let (next_round, _) = next_round(now, period, genesis)?;
+    if now < genesis {
+        return Ok(0);
+    }
+    Ok(next_round)
+}
+
+/// Checks that `round` is still unpublished at `now`, i.e. later than [`published_round`]. A key
+/// timelocked to a published round could be decrypted straight away.
+pub fn ensure_unpublished(
+    round: u64,
+    now: u64,
+    period: Duration,
+    genesis: u64,
+) -> Result<(), RoundError> {
+    let current_round = published_round(now, period, genesis)?;
+    if round <= current_round {
+        return Err(RoundError::DiscloseTooSoon {
+            requested_round: round,
+            current_round,
+        });
+    }
+    Ok(())
+}
+
+/// [`ensure_unpublished`] against `chain_info` at the instant `now`.
+pub fn ensure_unpublished_at(
+    chain_info: &ChainInfo,
+    round: u64,
+    now: SystemTime,
+) -> Result<(), RoundError> {
+    ensure_unpublished(
+        round,
+        now.duration_since(UNIX_EPOCH)?.as_secs(),
+        Duration::from_secs(chain_info.period()),
+        chain_info.genesis_time(),
+    )
+}
+
+/// `from_genesis / period + 1` for the seconds `from_genesis` elapsed since `genesis`, together
+/// with the instant `genesis + that * period` at which the following period ends. Before genesis
+/// this is round 1 at `genesis`.
+pub fn next_round(now: u64, period: Duration, genesis: u64) -> Result<(u64, u64), RoundError> {
+    if period.as_secs() == 0 {
+        return Err(RoundError::InvalidPeriod);
+    }
+
+    if now < genesis {
+        return Ok((1, genesis));
+    }
+
+    let from_genesis = now - genesis;
+    let next_round = from_genesis / period.as_secs() + 1;
+    let next_time = genesis + next_round * period.as_secs();
+
+    Ok((next_round, next_time))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::drand::{fastnet_chain_info, FASTNET_GENESIS, FASTNET_PERIOD};
+
+    const GENESIS: u64 = FASTNET_GENESIS;
+    const PERIOD: Duration = Duration::from_secs(FASTNET_PERIOD);
+
+    fn chain_info() -> ChainInfo {
+        fastnet_chain_info()
+    }
+
+    #[test]
+    fn round_at_rejects_pre_epoch_time() {
+        let t = UNIX_EPOCH - Duration::from_secs(1);
+        assert!(matches!(
+            round_at(&chain_info(), t),
+            Err(RoundError::BeforeEpoch(_))
+        ));
+    }
+
+    #[test]
+    fn before_genesis() {
+        assert_eq!(
+            next_round(GENESIS - 1, PERIOD, GENESIS).unwrap(),
+            (1, GENESIS)
+        );
+        assert_eq!(current_round(GENESIS - 1, PERIOD, GENESIS).unwrap(), 1);
+    }
+
+    #[test]
+    fn at_genesis() {
+        assert_eq!(
+            next_round(GENESIS, PERIOD, GENESIS).unwrap(),
+            (1, GENESIS + 3)
+        );
+        assert_eq!(current_round(GENESIS, PERIOD, GENESIS).unwrap(), 1);
+    }
+
+    #[test]
+    fn just_before_first_period() {
+        assert_eq!(
+            next_round(GENESIS + 2, PERIOD, GENESIS).unwrap(),
+            (1, GENESIS + 3)
+        );
+        assert_eq!(current_round(GENESIS + 2, PERIOD, GENESIS).unwrap(), 1);
+    }
+
+    #[test]
+    fn at_first_period() {
+        assert_eq!(
+            next_round(GENESIS + 3, PERIOD, GENESIS).unwrap(),
+            (2, GENESIS + 6)
+        );
+        assert_eq!(current_round(GENESIS + 3, PERIOD, GENESIS).unwrap(), 1);
+        assert_eq!(current_round(GENESIS + 6, PERIOD, GENESIS).unwrap(), 2);
+    }
+
+    #[test]
+    fn far_future_is_exact() {
+        let periods = u64::MAX / 8;
+        let now = GENESIS + periods * 3;
+        assert_eq!(next_round(now, PERIOD, GENESIS).unwrap().0, periods + 1);
+        assert_eq!(current_round(now, PERIOD, GENESIS).unwrap(), periods);
+    }
+
+    #[test]
+    fn zero_period_is_rejected() {
+        assert!(matches!(
+            next_round(GENESIS, Duration::ZERO, GENESIS),
+            Err(RoundError::InvalidPeriod)
+        ));
+        assert!(matches!(
+            current_round(GENESIS - 1, Duration::ZERO, GENESIS),
+            Err(RoundError::InvalidPeriod)
+        ));
+    }
+
+    #[test]
+    fn round_at_uses_chain_info() {
+        let t = UNIX_EPOCH + Duration::from_secs(GENESIS + 30);
+        assert_eq!(round_at(&chain_info(), t).unwrap(), 10);
+    }
+
+    #[test]
+    fn round_to_time_is_when_published_round_reaches_the_round() {
+        let info = chain_info();
+        assert_eq!(
+            round_to_time(&info, 0),
+            UNIX_EPOCH + Duration::from_secs(GENESIS)
+        );
+        for round in [1, 2, 11, 1_000, 12_345_678] {
+            let at = round_to_time(&info, round);
+            let secs = at.duration_since(UNIX_EPOCH).unwrap().as_secs();
+            assert_eq!(published_round(secs, PERIOD, GENESIS).unwrap(), round);
+            assert_eq!(
+                published_round(secs - 1, PERIOD, GENESIS).unwrap(),
+                round - 1
+            );
+        }
+    }
+
+    #[test]
+    fn round_to_time_round_trips_through_round_at() {
+        let info = chain_info();
+        for round in [2, 3, 10, 1_000, 12_345_678] {
+            // round_at stays a round behind what is published, so it reaches the round a period
+            // later and no earlier.
+            let t = round_to_time(&info, round) + PERIOD;
+            assert_eq!(round_at(&info, t).unwrap(), round, "round {round}");
+            let before = t - Duration::from_secs(1);
+            assert_eq!(round_at(&info, before).unwrap(), round - 1, "round {round}");
+        }
+    }
+
+    #[test]
+    fn rounds_around_are_one_period_apart() {
+        let info = chain_info();
+        let now = UNIX_EPOCH + Duration::from_secs(GENESIS + 3_000);
+        let rounds = rounds_around(&info, now, 5).unwrap();
+
+        assert_eq!(rounds.len(), 11);
+        assert_eq!(rounds[5].0, round_at(&info, now).unwrap());
+        for pair in rounds.windows(2) {
+            let [(round, at), (next, next_at)] = pair else {
+                unreachable!()
+            };
+            assert_eq!(*next, round + 1);
+            assert_eq!(next_at.duration_since(*at).unwrap(), PERIOD);
+        }
+    }
+
+    #[test]
+    fn rounds_around_genesis_start_at_round_one() {
+        let info = chain_info();
+        let now = UNIX_EPOCH + Duration::from_secs(GENESIS + 6);
+        let rounds: Vec<_> = rounds_around(&info, now, 5)
+            .unwrap()
+            .into_iter()
+            .map(|(round, _)| round)
+            .collect();
+        assert_eq!(rounds, (1..=7).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn block_delta_maps_to_round() {
+        let mut settings = BlockchainSettings::default();
+        settings.block_number = 100;
+        settings.block_timestamp = GENESIS + 30;
+
+        // 10 blocks of 12s land 150s after genesis, at the end of the 50th period.
+        assert_eq!(
+            round_at_block(&chain_info(), &settings, 110, 12).unwrap(),
+            50
+        );
+        assert_eq!(
+            round_at_block(&chain_info(), &settings, 125, 12).unwrap(),
+            110
+        );
+    }
+
+    #[test]
+    fn only_later_blocks_are_projected() {
+        let mut settings = BlockchainSettings::default();
+        settings.block_number = 100;
+        for block in [99, 100] {
+            assert!(matches!(
+                block_instant(&settings, block, 12),
+                Err(RoundError::BlockNotAhead {
+                    settings_block: 100,
+                    ..
+                })
+            ));
+        }
+        assert!(matches!(
+            block_instant(&settings, u64::MAX, 12),
+            Err(RoundError::BlockOutOfRange(u64::MAX))
+        ));
+    }
+
+    #[test]
+    fn sub_period_delay_is_too_soon() {
+        let now = GENESIS + 31;
+        let round = current_round(now + 1, PERIOD, GENESIS).unwrap();
+        let published = published_round(now, PERIOD, GENESIS).unwrap();
+        assert!(matches!(
+            ensure_unpublished(round, now, PERIOD, GENESIS),
+            Err(RoundError::DiscloseTooSoon {
+                requested_round,
+                current_round,
+            }) if requested_round == round && current_round == published
+        ));
+    }
+
+    #[test]
+    fn next_period_is_far_enough() {
+        let now = GENESIS + 31;
+        let round = published_round(now + PERIOD.as_secs(), PERIOD, GENESIS).unwrap();
+        assert!(ensure_unpublished(round, now, PERIOD, GENESIS).is_ok());
+    }
+
+    #[test]
+    fn round_published_at_now_is_too_soon() {
+        // Round 11 is published at genesis + 30, round 12 only at genesis + 33.
+        let now = GENESIS + 31;
+        assert_eq!(published_round(now, PERIOD, GENESIS).unwrap(), 11);
+        assert_eq!(current_round(now, PERIOD, GENESIS).unwrap(), 10);
+        assert!(matches!(
+            ensure_unpublished(11, now, PERIOD, GENESIS),
+            Err(RoundError::DiscloseTooSoon {
+                requested_round: 11,
+                current_round: 11,
+            })
+        ));
+        assert!(ensure_unpublished(12, now, PERIOD, GENESIS).is_ok());
+
+        // Nothing is published before genesis, round 1 included.
+        assert_eq!(published_round(GENESIS - 1, PERIOD, GENESIS).unwrap(), 0);
+        assert!(ensure_unpublished(1, GENESIS - 1, PERIOD, GENESIS).is_ok());
+    }
+}
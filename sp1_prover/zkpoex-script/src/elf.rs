@@ -0,0 +1,112 @@
+//! Loading the zkpoex program from disk instead of the ELF embedded at build time, so the program
+//! can be iterated on without rebuilding the host.
+
+#[cfg(feature = "prover")]
+use std::borrow::Cow;
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+#[cfg(feature = "prover")]
+use crate::ZKPOEX_ELF;
+
+const MAGIC: &[u8; 4] = b"\x7fELF";
+/// `EI_CLASS` of a 32-bit ELF.
+const CLASS_32: u8 = 1;
+/// `e_machine` of RISC-V.
+const MACHINE_RISCV: u16 = 0xf3;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ElfError {
+    #[error("failed to read ELF {}: {source}", .path.display())]
+    Read { path: PathBuf, source: io::Error },
+    #[error("{} is not an ELF file", .0.display())]
+    NotElf(PathBuf),
+    /// The SP1 zkVM only runs 32-bit RISC-V programs.
+    #[error("{} is not a 32-bit RISC-V ELF (class {class}, machine {machine:#x})", .path.display())]
+    Target {
+        path: PathBuf,
+        class: u8,
+        machine: u16,
+    },
+}
+
+/// Reads the ELF at `path`, checking it is a program the zkVM can run.
+pub fn load_elf(path: &Path) -> Result<Vec<u8>, ElfError> {
+    let elf = fs::read(path).map_err(|source| ElfError::Read {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    if elf.len() < 20 || &elf[..4] != MAGIC {
+        return Err(ElfError::NotElf(path.to_path_buf()));
+    }
+    let class = elf[4];
+    let machine = u16::from_le_bytes([elf[18], elf[19]]);
+    if class != CLASS_32 || machine != MACHINE_RISCV {
+        return Err(ElfError::Target {
+            path: path.to_path_buf(),
+            class,
+            machine,
+        });
+    }
+    Ok(elf)
+}
+
+/// The ELF at `path` if given, otherwise the embedded [`ZKPOEX_ELF`].
+#[cfg(feature = "prover")]
+pub fn program_elf(path: Option<&Path>) -> Result<Cow<'static, [u8]>, ElfError> {
+    match path {
+        Some(path) => load_elf(path).map(Cow::Owned),
+        None => Ok(Cow::Borrowed(ZKPOEX_ELF)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The start of an ELF header for `class` and `machine`, enough for [`load_elf`].
+    fn header(class: u8, machine: u16) -> Vec<u8> {
+        let mut elf = MAGIC.to_vec();
+        elf.extend([class, 1, 1]);
+        elf.resize(18, 0);
+        elf.extend(machine.to_le_bytes());
+        elf.resize(52, 0);
+        elf
+    }
+
+    #[test]
+    #[cfg(feature = "prover")]
+    fn custom_elf_is_used_over_the_embedded_one() {
+        let path = std::env::temp_dir().join(format!("zkpoex-elf-{}", std::process::id()));
+        let custom = header(CLASS_32, MACHINE_RISCV);
+        fs::write(&path, &custom).unwrap();
+
+        assert_eq!(program_elf(Some(&path)).unwrap(), custom);
+        assert_eq!(program_elf(None).unwrap(), ZKPOEX_ELF);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn only_32_bit_risc_v_elves_are_accepted() {
+        let path = std::env::temp_dir().join(format!("zkpoex-elf-bad-{}", std::process::id()));
+
+        fs::write(&path, b"#!/bin/sh\n").unwrap();
+        assert!(matches!(load_elf(&path), Err(ElfError::NotElf(_))));
+
+        // x86-64
+        fs::write(&path, header(2, 0x3e)).unwrap();
+        assert!(matches!(
+            load_elf(&path),
+            Err(ElfError::Target {
+                class: 2,
+                machine: 0x3e,
+                ..
+            })
+        ));
+
+        fs::remove_file(&path).unwrap();
+        assert!(matches!(load_elf(&path), Err(ElfError::Read { .. })));
+    }
+}
@@ -0,0 +1,139 @@
+//! Projecting how long a proof will take and how large it will be from the cycles the program
+//! runs, so a run can be scheduled before committing to it.
+
+use std::{fmt, time::Duration};
+
+use sp1_sdk::{ProverClient, SP1Stdin};
+
+use crate::{
+    execute_zkpoex, proof::ProofMode, settings::BlockchainSettings, ProveError, ProveOptions,
+};
+
+/// Cycles a local CPU prover gets through per second, measured on an 8 core machine. Calibrate
+/// with [`CostModel::cycles_per_second`] against a real run's `provingDurationMs` and `cycles`.
+pub const DEFAULT_CYCLES_PER_SECOND: u64 = 50_000;
+
+/// What proving a given number of cycles costs on the machine at hand.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CostModel {
+    pub cycles_per_second: u64,
+    /// The time wrapping the compressed STARK into a SNARK takes on top, whatever the cycles.
+    pub wrap_duration: Duration,
+}
+
+impl Default for CostModel {
+    fn default() -> Self {
+        Self {
+            cycles_per_second: DEFAULT_CYCLES_PER_SECOND,
+            wrap_duration: Duration::from_secs(5 * 60),
+        }
+    }
+}
+
+/// The projected cost of proving one execution.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProveEstimate {
+    /// The cycles the program actually ran.
+    pub cycles: u64,
+    pub est_duration: Duration,
+    /// Bytes of the proof itself, without the public values and stdin saved along with it.
+    pub est_proof_size: u64,
+}
+
+impl CostModel {
+    /// The estimate for proving `cycles` in `mode`.
+    pub fn project(&self, cycles: u64, mode: ProofMode) -> ProveEstimate {
+        let proving = Duration::from_secs_f64(cycles as f64 / self.cycles_per_second.max(1) as f64);
+        let (wrap, est_proof_size) = match mode {
+            // Roughly the size of a recursion proof, independent of the cycles.
+            ProofMode::Compressed => (Duration::ZERO, 1_500_000),
+            // Eight field elements.
+            ProofMode::Groth16 => (self.wrap_duration, 256),
+            ProofMode::Plonk => (self.wrap_duration, 868),
+        };
+        ProveEstimate {
+            cycles,
+            est_duration: proving + wrap,
+            est_proof_size,
+        }
+    }
+}
+
+impl fmt::Display for ProveEstimate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let duration = Duration::from_secs(self.est_duration.as_secs());
+        write!(
+            f,
+            "{} cycles, about {} to prove, a proof of about {} bytes",
+            self.cycles,
+            humantime::format_duration(duration),
+            self.est_proof_size
+        )
+    }
+}
+
+/// Executes `elf` on `stdin` for its cycle count and projects the cost of proving it in `mode`.
+pub fn estimate(
+    client: &ProverClient,
+    elf: &[u8],
+    stdin: SP1Stdin,
+    mode: ProofMode,
+    model: &CostModel,
+) -> Result<ProveEstimate, ProveError> {
+    let (_, report) = client.execute(elf, stdin).map_err(ProveError::Execute)?;
+    Ok(model.project(report.total_instruction_count(), mode))
+}
+
+/// Like [`estimate`], for the program input `options` give for `calldata`, as
+/// [`execute_zkpoex`] runs it.
+pub fn estimate_zkpoex(
+    calldata: &str,
+    blockchain_settings: &BlockchainSettings,
+    options: &ProveOptions,
+    model: &CostModel,
+) -> Result<ProveEstimate, ProveError> {
+    let execution = execute_zkpoex(calldata, blockchain_settings, options)?;
+    Ok(model.project(execution.cycles, options.proof_mode))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Disclosure;
+
+    #[test]
+    fn projection_scales_with_cycles_and_wraps() {
+        let model = CostModel {
+            cycles_per_second: 1_000,
+            wrap_duration: Duration::from_secs(60),
+        };
+        let compressed = model.project(30_000, ProofMode::Compressed);
+        assert_eq!(compressed.est_duration, Duration::from_secs(30));
+
+        let groth16 = model.project(30_000, ProofMode::Groth16);
+        assert_eq!(groth16.est_duration, Duration::from_secs(90));
+        assert!(groth16.est_proof_size < compressed.est_proof_size);
+        assert_eq!(
+            groth16.to_string(),
+            "30000 cycles, about 1m 30s to prove, a proof of about 256 bytes"
+        );
+    }
+
+    #[test]
+    fn estimate_counts_the_cycles_of_a_real_execution() {
+        let options = ProveOptions {
+            disclosure: Disclosure::Immediate,
+            setup_cache: None,
+            ..Default::default()
+        };
+        let estimate = estimate_zkpoex(
+            "63d9b770",
+            &BlockchainSettings::default(),
+            &options,
+            &CostModel::default(),
+        )
+        .unwrap();
+        assert!(estimate.cycles > 0);
+        assert!(estimate.est_duration > Duration::ZERO);
+    }
+}
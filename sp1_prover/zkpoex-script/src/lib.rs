@@ -0,0 +1,658 @@
+//! The zkpoex proving pipeline as a library, so it can be embedded in other Rust code (tests, a
+//! long-running service) without shelling out to the `prove` binary.
+//!
+//! Proving needs the `prover` feature, on by default. Without it only the fixture types, round
+//! math, tlock and payload ciphers are built, which is all a consumer disclosing or checking
+//! exploits needs.
+
+pub mod archive;
+pub mod audit;
+#[cfg(feature = "prover")]
+pub mod backend;
+pub mod bundle;
+pub mod calldata;
+pub mod cipher;
+#[cfg(feature = "prover")]
+pub mod client;
+pub mod config;
+pub mod delay;
+pub mod diff;
+pub mod digest;
+pub mod disclose;
+pub mod drand;
+pub mod drand_cache;
+pub mod drand_round;
+pub mod elf;
+#[cfg(feature = "prover")]
+pub mod estimate;
+pub mod logging;
+pub mod manifest;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+pub mod note;
+pub mod output;
+pub mod presets;
+pub mod progress;
+pub mod proof;
+#[cfg(feature = "prover")]
+mod prover;
+#[cfg(feature = "prover")]
+pub mod regenerate;
+pub mod report;
+pub mod reseal;
+#[cfg(feature = "prover")]
+pub mod selftest;
+pub mod settings;
+#[cfg(feature = "prover")]
+pub mod setup;
+pub mod split;
+pub mod state;
+pub mod tlock_header;
+
+#[cfg(feature = "prover")]
+pub use prover::*;
+
+use std::time::{Duration, SystemTime};
+
+use drand_core::chain::ChainInfo;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{de, Deserialize, Deserializer, Serialize};
+use serde_json::{Map, Value};
+use sha3::{Digest, Keccak256};
+
+use crate::{
+    cipher::PayloadCipher,
+    digest::PrivateInputsDigest,
+    drand_round::{ensure_unpublished_at, round_after, round_at, RoundError},
+    proof::ProofMode,
+    report::ReportSummary,
+    split::TlockShare,
+};
+
+/// The ELF (executable and linkable format) file for the Succinct RISC-V zkVM.
+///
+/// The build script compiles it from `zk-poex` with the `prover` feature, which is also the only
+/// one it is embedded with. It isn't checked in: a copy left over from an older program would
+/// prove with outdated inputs and public values.
+#[cfg(feature = "prover")]
+pub const ZKPOEX_ELF: &[u8] = include_bytes!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/../zk-poex/elf/riscv32im-succinct-zkvm-elf"
+));
+
+/// A fixture that can be used to test the verification of SP1 zkVM proofs inside Solidity.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SP1ZkPoExProofFixture {
+    /// The [`FIXTURE_SCHEMA_VERSION`] the fixture was written with. Fixtures from before it was
+    /// recorded are version 1.
+    #[serde(
+        default = "initial_schema_version",
+        deserialize_with = "supported_schema_version"
+    )]
+    pub schema_version: u32,
+    /// The plaintext key, kept in memory only: it is never serialized, so a fixture read back has
+    /// it zeroed. Prove writes it to a file of its own with `--persist-key`.
+    #[serde(skip)]
+    pub key: [u8; 32],
+    /// Fixtures from before the cipher was selectable are all ChaCha20.
+    #[serde(default)]
+    pub cipher: PayloadCipher,
+    pub nonce: Vec<u8>,
+    /// The drand round the key is timelocked to, `None` if it isn't timelocked at all.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub round: Option<u64>,
+    pub before: String,
+    pub after: String,
+    pub hash_private_inputs: PrivateInputsDigest,
+    pub chacha_cipher: Vec<u8>,
+    /// Empty if the key was split, then its shares are in `tlock_shares` instead, or if it isn't
+    /// timelocked.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tlock_cipher: Vec<u8>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tlock_shares: Vec<TlockShare>,
+    pub calldata: String,
+    pub blockchain_settings: String,
+    pub vkey: String,
+    #[serde(default)]
+    pub proof_mode: ProofMode,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proof: Option<String>,
+    /// Wall-clock time spent generating the proof.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proving_duration_ms: Option<u64>,
+    /// RISC-V cycles the program took, as reported by executing it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cycles: Option<u64>,
+    /// The syscalls the program made most, as reported by executing it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub report: Option<ReportSummary>,
+    /// Who to contact and when the exploit will be disclosed, for whoever handles the fixture.
+    /// Checked by [`note::policy_note`] before it is stored.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub policy_note: Option<String>,
+}
+
+/// The layout version of [`SP1ZkPoExProofFixture`] this host writes and the newest it reads. Bump
+/// it whenever a field is renamed, changes meaning, or can't be defaulted when missing.
+pub const FIXTURE_SCHEMA_VERSION: u32 = 2;
+
+#[derive(Debug, thiserror::Error)]
+pub enum FixtureError {
+    /// The fixture was written by a newer host, whose fields this one could misread.
+    #[error("fixture schema version {found} is newer than the supported version {supported}")]
+    UnsupportedSchema { found: u64, supported: u32 },
+    #[error("failed to parse fixture: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+impl SP1ZkPoExProofFixture {
+    /// Parses a fixture, rejecting one of a schema version newer than [`FIXTURE_SCHEMA_VERSION`].
+    pub fn from_json(json: &str) -> Result<Self, FixtureError> {
+        let value: Value = serde_json::from_str(json)?;
+        if let Some(found) = value.get("schemaVersion").and_then(Value::as_u64) {
+            check_schema_version(found)?;
+        }
+        Ok(serde_json::from_value(value)?)
+    }
+
+    /// The fixture as pretty JSON with its keys sorted, so rewriting it gives reproducible diffs.
+    pub fn to_json(&self) -> String {
+        let value = serde_json::to_value(self).expect("fixture serializes to json");
+        serde_json::to_string_pretty(&sort_keys(value)).expect("fixture serializes to json")
+    }
+
+    /// Checks the calldata and blockchain settings the fixture advertises are the ones the proof
+    /// behind `public_values` was run on.
+    pub fn check_inputs(&self, public_values: &PublicValues) -> Result<(), InputsMismatch> {
+        public_values.check_inputs(&self.calldata, &self.blockchain_settings)
+    }
+}
+
+fn initial_schema_version() -> u32 {
+    1
+}
+
+fn check_schema_version(found: u64) -> Result<(), FixtureError> {
+    if found > FIXTURE_SCHEMA_VERSION as u64 {
+        return Err(FixtureError::UnsupportedSchema {
+            found,
+            supported: FIXTURE_SCHEMA_VERSION,
+        });
+    }
+    Ok(())
+}
+
+fn supported_schema_version<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u32, D::Error> {
+    let version = u32::deserialize(deserializer)?;
+    check_schema_version(version.into()).map_err(de::Error::custom)?;
+    Ok(version)
+}
+
+/// `value` with the keys of every object in sorted order, whether or not serde_json preserves
+/// insertion order.
+fn sort_keys(value: Value) -> Value {
+    match value {
+        Value::Object(fields) => {
+            let mut sorted: Vec<_> = fields.into_iter().collect();
+            sorted.sort_by(|(a, _), (b, _)| a.cmp(b));
+            Value::Object(
+                sorted
+                    .into_iter()
+                    .map(|(key, value)| (key, sort_keys(value)))
+                    .collect::<Map<_, _>>(),
+            )
+        }
+        Value::Array(values) => Value::Array(values.into_iter().map(sort_keys).collect()),
+        value => value,
+    }
+}
+
+/// The layout version of [`PublicValues`] this host understands, committed by the program as the
+/// first byte of its public values.
+pub const PUBLIC_VALUES_VERSION: u8 = 2;
+
+/// The values the zkpoex program commits to, in commit order after the version byte.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PublicValues {
+    pub before: String,
+    pub after: String,
+    pub hash_private_inputs: PrivateInputsDigest,
+    pub chacha_cipher: Vec<u8>,
+    pub key_hash: String,
+    /// [`inputs_hash`] of the calldata and blockchain settings the program ran on.
+    pub inputs_hash: [u8; 32],
+}
+
+/// Keccak256 of the calldata and blockchain settings JSON as the program commits it: the length of
+/// the calldata as a big endian u64, the calldata, then the settings, so no two pairs of inputs
+/// hash alike.
+pub fn inputs_hash(calldata: &str, blockchain_settings: &str) -> [u8; 32] {
+    let mut hasher = Keccak256::new();
+    hasher.update((calldata.len() as u64).to_be_bytes());
+    hasher.update(calldata.as_bytes());
+    hasher.update(blockchain_settings.as_bytes());
+    hasher.finalize().into()
+}
+
+/// The inputs a fixture advertises are not the ones its proof was run on.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error(
+    "calldata and blockchain settings hash to 0x{actual}, but the proof commits to 0x{committed}"
+)]
+pub struct InputsMismatch {
+    pub committed: String,
+    pub actual: String,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum PublicValuesError {
+    #[error("public values are empty")]
+    Empty,
+    /// The program commits a different layout than this host decodes.
+    #[error("public values are version {found}, expected version {expected}")]
+    VersionMismatch { expected: u8, found: u8 },
+    #[error("failed to deserialize public values: {0}")]
+    Decode(bincode::Error),
+    #[error("failed to parse public values JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("public values field {0} is not valid hex")]
+    InvalidHex(&'static str),
+}
+
+impl PublicValues {
+    pub fn decode(bytes: &[u8]) -> Result<Self, PublicValuesError> {
+        let (&found, rest) = bytes.split_first().ok_or(PublicValuesError::Empty)?;
+        if found != PUBLIC_VALUES_VERSION {
+            return Err(PublicValuesError::VersionMismatch {
+                expected: PUBLIC_VALUES_VERSION,
+                found,
+            });
+        }
+        bincode::deserialize(rest).map_err(PublicValuesError::Decode)
+    }
+
+    /// Checks `calldata` and `blockchain_settings` hash to the inputs the program committed to.
+    pub fn check_inputs(
+        &self,
+        calldata: &str,
+        blockchain_settings: &str,
+    ) -> Result<(), InputsMismatch> {
+        let actual = inputs_hash(calldata, blockchain_settings);
+        if actual != self.inputs_hash {
+            return Err(InputsMismatch {
+                committed: hex::encode(self.inputs_hash),
+                actual: hex::encode(actual),
+            });
+        }
+        Ok(())
+    }
+
+    /// The public values as the program commits them.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = vec![PUBLIC_VALUES_VERSION];
+        bytes.extend(bincode::serialize(self).expect("public values serialize"));
+        bytes
+    }
+
+    /// The public values as pretty JSON with named fields in sorted order, for consumers that
+    /// can't decode bincode:
+    ///
+    /// | field               | value                                                     |
+    /// |---------------------|-----------------------------------------------------------|
+    /// | `version`           | [`PUBLIC_VALUES_VERSION`], the leading byte               |
+    /// | `before`, `after`   | the contract states, JSON strings as the program commits  |
+    /// | `hashPrivateInputs` | keccak256 of the private inputs, 0x prefixed hex          |
+    /// | `chachaCipher`      | the encrypted exploit payload, 0x prefixed hex            |
+    /// | `keyHash`           | keccak256 of the payload key, 0x prefixed hex             |
+    /// | `inputsHash`        | [`inputs_hash`] of calldata and settings, 0x prefixed hex |
+    pub fn to_json(&self) -> String {
+        let value = serde_json::json!({
+            "version": PUBLIC_VALUES_VERSION,
+            "before": self.before,
+            "after": self.after,
+            "hashPrivateInputs": format!("0x{}", self.hash_private_inputs),
+            "chachaCipher": format!("0x{}", hex::encode(&self.chacha_cipher)),
+            "keyHash": format!("0x{}", self.key_hash),
+            "inputsHash": format!("0x{}", hex::encode(self.inputs_hash)),
+        });
+        serde_json::to_string_pretty(&sort_keys(value)).expect("public values serialize to json")
+    }
+
+    /// Parses the JSON [`PublicValues::to_json`] writes, e.g. when it is all a third party was
+    /// handed besides the fixture.
+    pub fn from_json(json: &str) -> Result<Self, PublicValuesError> {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Named {
+            version: u8,
+            before: String,
+            after: String,
+            hash_private_inputs: PrivateInputsDigest,
+            chacha_cipher: String,
+            key_hash: String,
+            inputs_hash: String,
+        }
+
+        fn unhex(field: &'static str, s: &str) -> Result<Vec<u8>, PublicValuesError> {
+            hex::decode(s.strip_prefix("0x").unwrap_or(s))
+                .map_err(|_| PublicValuesError::InvalidHex(field))
+        }
+
+        let named: Named = serde_json::from_str(json)?;
+        if named.version != PUBLIC_VALUES_VERSION {
+            return Err(PublicValuesError::VersionMismatch {
+                expected: PUBLIC_VALUES_VERSION,
+                found: named.version,
+            });
+        }
+        let key_hash = unhex("keyHash", &named.key_hash)?;
+        Ok(Self {
+            before: named.before,
+            after: named.after,
+            hash_private_inputs: named.hash_private_inputs,
+            chacha_cipher: unhex("chachaCipher", &named.chacha_cipher)?,
+            key_hash: hex::encode(key_hash),
+            inputs_hash: unhex("inputsHash", &named.inputs_hash)?
+                .try_into()
+                .map_err(|_| PublicValuesError::InvalidHex("inputsHash"))?,
+        })
+    }
+}
+
+/// Decodes the committed `bytes` and re-emits them as [`PublicValues::to_json`] does.
+pub fn public_values_json(bytes: &[u8]) -> Result<String, PublicValuesError> {
+    Ok(PublicValues::decode(bytes)?.to_json())
+}
+
+/// When the encryption key becomes disclosable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Disclosure {
+    /// At the drand round reached after the given duration from now.
+    After(Duration),
+    /// At the drand round reached at the given instant.
+    At(SystemTime),
+    /// At a specific drand round.
+    AtRound(u64),
+    /// Whenever the prover hands the key over, e.g. through the ecdh script. The key isn't
+    /// timelocked, so no drand chain is needed.
+    Immediate,
+}
+
+impl Disclosure {
+    /// Whether the key is timelocked to a drand round.
+    pub fn is_timelocked(self) -> bool {
+        self != Self::Immediate
+    }
+}
+
+/// `key` and `nonce`, or fresh ones for `cipher` in their place, drawn from `thread_rng` unless a
+/// test `seed` is given.
+pub fn key_and_nonce(
+    cipher: PayloadCipher,
+    key: Option<[u8; 32]>,
+    nonce: Option<&[u8]>,
+    seed: Option<u64>,
+) -> ([u8; 32], Vec<u8>) {
+    fn draw(
+        cipher: PayloadCipher,
+        key: Option<[u8; 32]>,
+        nonce: Option<&[u8]>,
+        rng: &mut impl Rng,
+    ) -> ([u8; 32], Vec<u8>) {
+        let key = key.unwrap_or_else(|| rng.gen());
+        let nonce = nonce.map_or_else(|| cipher.random_nonce(rng), <[u8]>::to_vec);
+        (key, nonce)
+    }
+
+    match seed {
+        Some(seed) => draw(cipher, key, nonce, &mut StdRng::seed_from_u64(seed)),
+        None => draw(cipher, key, nonce, &mut rand::thread_rng()),
+    }
+}
+
+/// The drand round of `chain_info` that `disclosure` asks for.
+///
+/// Fails if that round is already published, since the timelock would then hide nothing, or if
+/// the disclosure is immediate and so has no round.
+pub fn disclosure_round(chain_info: &ChainInfo, disclosure: Disclosure) -> Result<u64, RoundError> {
+    let round = match disclosure {
+        Disclosure::After(d) => round_after(chain_info, d)?,
+        Disclosure::At(t) => round_at(chain_info, t)?,
+        Disclosure::AtRound(round) => round,
+        Disclosure::Immediate => return Err(RoundError::NotTimelocked),
+    };
+    ensure_unpublished_at(chain_info, round, SystemTime::now())?;
+    Ok(round)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::drand::{DrandSource, MockDrand};
+
+    fn test_public_values() -> PublicValues {
+        PublicValues {
+            before: "{}".to_string(),
+            after: "{}".to_string(),
+            hash_private_inputs: PrivateInputsDigest([1; 32]),
+            chacha_cipher: vec![2, 3],
+            key_hash: "00".repeat(32),
+            inputs_hash: inputs_hash("63d9b770", "{}"),
+        }
+    }
+
+    #[test]
+    fn public_values_json_names_the_decoded_fields() {
+        let public_values = test_public_values();
+        let json: Value =
+            serde_json::from_str(&public_values_json(&public_values.encode()).unwrap()).unwrap();
+        assert_eq!(json["version"], PUBLIC_VALUES_VERSION);
+        assert_eq!(json["before"], public_values.before);
+        assert_eq!(json["after"], public_values.after);
+        assert_eq!(json["hashPrivateInputs"], format!("0x{}", "01".repeat(32)));
+        assert_eq!(json["chachaCipher"], "0x0203");
+        assert_eq!(json["keyHash"], format!("0x{}", public_values.key_hash));
+        assert_eq!(
+            json["inputsHash"],
+            format!("0x{}", hex::encode(inputs_hash("63d9b770", "{}")))
+        );
+        assert_eq!(json.as_object().unwrap().len(), 7);
+        assert_eq!(
+            PublicValues::from_json(&public_values.to_json()).unwrap(),
+            public_values
+        );
+
+        assert!(matches!(
+            public_values_json(&[PUBLIC_VALUES_VERSION + 1]),
+            Err(PublicValuesError::VersionMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn fixture_with_other_inputs_than_proven_is_rejected() {
+        let public_values = test_public_values();
+        let mut fixture = SP1ZkPoExProofFixture::from_json(
+            r#"{
+                "nonce": [], "round": 42, "before": "{}", "after": "{}",
+                "hashPrivateInputs": "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470",
+                "chachaCipher": [2,3], "tlockCipher": [],
+                "calldata": "63d9b770", "blockchainSettings": "{}", "vkey": "0x00"
+            }"#,
+        )
+        .unwrap();
+        fixture.check_inputs(&public_values).unwrap();
+
+        // The calldata is edited after proving.
+        fixture.calldata = "63d9b771".to_string();
+        let err = fixture.check_inputs(&public_values).unwrap_err();
+        assert_eq!(err.committed, hex::encode(public_values.inputs_hash));
+        assert_eq!(
+            err.actual,
+            hex::encode(inputs_hash("63d9b771", &fixture.blockchain_settings))
+        );
+
+        fixture.calldata = "63d9b770".to_string();
+        fixture.blockchain_settings = "{\"blockNumber\":1}".to_string();
+        assert!(fixture.check_inputs(&public_values).is_err());
+    }
+
+    #[test]
+    fn inputs_hash_separates_calldata_from_settings() {
+        assert_ne!(inputs_hash("63d9", "b770{}"), inputs_hash("63d9b770", "{}"));
+    }
+
+    #[test]
+    fn public_values_round_trip() {
+        let public_values = test_public_values();
+        let decoded = PublicValues::decode(&public_values.encode()).unwrap();
+        assert_eq!(decoded, public_values);
+    }
+
+    #[test]
+    fn public_values_of_another_version_are_rejected() {
+        let mut bytes = test_public_values().encode();
+        bytes[0] = PUBLIC_VALUES_VERSION + 1;
+        assert!(matches!(
+            PublicValues::decode(&bytes),
+            Err(PublicValuesError::VersionMismatch {
+                expected: PUBLIC_VALUES_VERSION,
+                found,
+            }) if found == PUBLIC_VALUES_VERSION + 1
+        ));
+        assert!(matches!(
+            PublicValues::decode(&[]),
+            Err(PublicValuesError::Empty)
+        ));
+    }
+
+    #[test]
+    fn fixture_without_stats_still_deserializes() {
+        let fixture = r#"{
+            "key": [1,1,1,1,1,1,1,1,1,1,1,1,1,1,1,1,1,1,1,1,1,1,1,1,1,1,1,1,1,1,1,1],
+            "nonce": [0,0,0,0,0,0,0,0,0,0,0,0],
+            "round": 42,
+            "before": "", "after": "",
+            "hashPrivateInputs": "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470",
+            "chachaCipher": [], "tlockCipher": [],
+            "calldata": "63d9b770", "blockchainSettings": "{}", "vkey": "0x00"
+        }"#;
+        let fixture = SP1ZkPoExProofFixture::from_json(fixture).unwrap();
+        assert_eq!(fixture.proving_duration_ms, None);
+        assert_eq!(fixture.cycles, None);
+        assert_eq!(fixture.schema_version, 1);
+        // Older fixtures carried the plaintext key, it is neither read nor written back.
+        assert_eq!(fixture.key, [0; 32]);
+
+        // Written back with the version and the keys sorted.
+        let json = fixture.to_json();
+        let keys: Vec<_> = json
+            .lines()
+            .filter_map(|line| line.trim().strip_prefix('"')?.split_once('"'))
+            .map(|(key, _)| key)
+            .collect();
+        let mut sorted = keys.clone();
+        sorted.sort();
+        assert_eq!(keys, sorted);
+        assert!(keys.contains(&"schemaVersion"));
+        assert!(!keys.contains(&"key"));
+    }
+
+    #[test]
+    fn fixture_of_a_newer_schema_is_rejected() {
+        let fixture = format!(
+            r#"{{
+                "schemaVersion": {},
+                "nonce": [], "round": 42, "before": "", "after": "",
+                "hashPrivateInputs": "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470",
+                "chachaCipher": [], "tlockCipher": [],
+                "calldata": "63d9b770", "blockchainSettings": "{{}}", "vkey": "0x00"
+            }}"#,
+            FIXTURE_SCHEMA_VERSION + 1
+        );
+        assert!(matches!(
+            SP1ZkPoExProofFixture::from_json(&fixture),
+            Err(FixtureError::UnsupportedSchema { found, supported: FIXTURE_SCHEMA_VERSION })
+                if found == FIXTURE_SCHEMA_VERSION as u64 + 1
+        ));
+        // Plain serde refuses it too.
+        let err = serde_json::from_str::<SP1ZkPoExProofFixture>(&fixture).unwrap_err();
+        assert!(err.to_string().contains("newer than the supported version"));
+
+        let current = fixture.replacen(
+            &format!("\"schemaVersion\": {}", FIXTURE_SCHEMA_VERSION + 1),
+            &format!("\"schemaVersion\": {FIXTURE_SCHEMA_VERSION}"),
+            1,
+        );
+        assert!(SP1ZkPoExProofFixture::from_json(&current).is_ok());
+    }
+
+    #[test]
+    fn same_seed_gives_same_key_and_nonce() {
+        let chacha = PayloadCipher::ChaCha20;
+        assert_eq!(
+            key_and_nonce(chacha, None, None, Some(7)),
+            key_and_nonce(chacha, None, None, Some(7))
+        );
+        assert_ne!(
+            key_and_nonce(chacha, None, None, Some(7)),
+            key_and_nonce(chacha, None, None, Some(8))
+        );
+
+        let (_, nonce) = key_and_nonce(PayloadCipher::XChaCha20, None, None, Some(7));
+        assert_eq!(nonce.len(), 24);
+    }
+
+    #[test]
+    fn round_after_follows_the_mock_chain() {
+        let info = MockDrand {
+            period: 30,
+            genesis: 1_600_000_000,
+            ..Default::default()
+        }
+        .chain_info()
+        .unwrap();
+        let delay = Duration::from_secs(600);
+
+        let before = round_at(&info, SystemTime::now() + delay).unwrap();
+        let round = round_after(&info, delay).unwrap();
+        let after = round_at(&info, SystemTime::now() + delay).unwrap();
+        assert!((before..=after).contains(&round));
+
+        // 20 more periods are 20 more rounds.
+        let later = round_after(&info, delay + Duration::from_secs(20 * 30)).unwrap();
+        assert!((round + 20..=round + 21).contains(&later));
+    }
+
+    #[test]
+    fn round_after_is_round_one_before_genesis() {
+        let info = MockDrand {
+            genesis: u32::MAX as u64 * 2,
+            ..Default::default()
+        }
+        .chain_info()
+        .unwrap();
+        assert_eq!(round_after(&info, Duration::from_secs(3600)).unwrap(), 1);
+    }
+
+    /// Uses nothing behind the `prover` feature, so `cargo test --no-default-features` checks the
+    /// consumer side builds and resolves rounds on its own.
+    #[test]
+    fn disclosure_round_resolves_without_the_prover() {
+        let info = MockDrand::default().chain_info().unwrap();
+        let round = disclosure_round(&info, Disclosure::After(Duration::from_secs(3600))).unwrap();
+        assert_eq!(
+            disclosure_round(&info, Disclosure::AtRound(round)).unwrap(),
+            round
+        );
+        assert!(matches!(
+            disclosure_round(&info, Disclosure::AtRound(1)),
+            Err(RoundError::DiscloseTooSoon { .. })
+        ));
+        assert!(matches!(
+            disclosure_round(&info, Disclosure::Immediate),
+            Err(RoundError::NotTimelocked)
+        ));
+    }
+}
@@ -0,0 +1,97 @@
+//! Choosing how the tracing output is formatted.
+//!
+//! The default is the human-readable format of the SP1 SDK's `setup_logger`, or tracing's own
+//! without the `prover` feature. JSON writes one object per event instead, so a prover run as a
+//! service can be fed to a log pipeline.
+
+use std::io;
+
+use clap::ValueEnum;
+use tracing::Subscriber;
+use tracing_subscriber::{fmt::MakeWriter, EnvFilter};
+
+/// How each tracing event is written out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum LogFormat {
+    /// Human-readable lines, as the SP1 SDK formats them.
+    #[default]
+    Pretty,
+    /// One JSON object per line.
+    Json,
+}
+
+/// Installs the global tracing subscriber for `format`, filtered by `RUST_LOG` (info if unset).
+pub fn setup_logging(format: LogFormat) {
+    match format {
+        #[cfg(feature = "prover")]
+        LogFormat::Pretty => sp1_sdk::utils::setup_logger(),
+        #[cfg(not(feature = "prover"))]
+        LogFormat::Pretty => {
+            let filter =
+                EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+            let _ = tracing_subscriber::fmt().with_env_filter(filter).try_init();
+        }
+        LogFormat::Json => {
+            let filter =
+                EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+            // Only fails if a subscriber is already installed, which then keeps logging.
+            let _ = tracing::subscriber::set_global_default(json_subscriber(filter, io::stdout));
+        }
+    }
+}
+
+/// A subscriber writing every event `filter` lets through as a line of JSON to `make_writer`.
+pub fn json_subscriber<W>(filter: EnvFilter, make_writer: W) -> impl Subscriber + Send + Sync
+where
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    tracing_subscriber::fmt()
+        .json()
+        .with_env_filter(filter)
+        .with_writer(make_writer)
+        .finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+
+    /// Collects what the subscriber writes, for the test to read back.
+    #[derive(Clone, Default)]
+    struct Captured(Arc<Mutex<Vec<u8>>>);
+
+    impl io::Write for Captured {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn json_lines_are_parseable() {
+        let captured = Captured::default();
+        let writer = captured.clone();
+        let subscriber = json_subscriber(EnvFilter::new("info"), move || writer.clone());
+
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!(round = 42, "timelocked the key");
+            tracing::debug!("filtered out");
+        });
+
+        let output = String::from_utf8(captured.0.lock().unwrap().clone()).unwrap();
+        let lines: Vec<serde_json::Value> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0]["level"], "INFO");
+        assert_eq!(lines[0]["fields"]["message"], "timelocked the key");
+        assert_eq!(lines[0]["fields"]["round"], 42);
+    }
+}
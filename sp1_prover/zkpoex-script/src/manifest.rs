@@ -0,0 +1,125 @@
+//! An index of the files one prove run leaves in the output directory, so tooling can find and
+//! integrity-check all of them from a single file.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Manifest {
+    /// The disclosure round, `None` if the key isn't timelocked.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub round: Option<u64>,
+    pub vkey: String,
+    pub artifacts: Vec<Artifact>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Artifact {
+    /// Relative to the directory the manifest is in, so the outputs can be moved together.
+    pub path: PathBuf,
+    pub size: u64,
+    /// Hex encoded SHA-256 of the file contents.
+    pub sha256: String,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ManifestError {
+    #[error("failed to read artifact {}: {source}", .path.display())]
+    Read { path: PathBuf, source: io::Error },
+    #[error("artifact {} doesn't match the manifest", .path.display())]
+    Mismatch { path: PathBuf },
+}
+
+impl Artifact {
+    /// Describes the file at `dir.join(path)`.
+    pub fn read(dir: &Path, path: impl Into<PathBuf>) -> Result<Self, ManifestError> {
+        let path = path.into();
+        let contents = fs::read(dir.join(&path)).map_err(|source| ManifestError::Read {
+            path: path.clone(),
+            source,
+        })?;
+        Ok(Self {
+            path,
+            size: contents.len() as u64,
+            sha256: hex::encode(Sha256::digest(&contents)),
+        })
+    }
+}
+
+impl Manifest {
+    /// Hashes the artifacts at `paths` under `dir`.
+    pub fn build(
+        dir: &Path,
+        round: Option<u64>,
+        vkey: String,
+        paths: impl IntoIterator<Item = impl Into<PathBuf>>,
+    ) -> Result<Self, ManifestError> {
+        Ok(Self {
+            round,
+            vkey,
+            artifacts: paths
+                .into_iter()
+                .map(|path| Artifact::read(dir, path))
+                .collect::<Result<_, _>>()?,
+        })
+    }
+
+    /// Checks that every artifact is still present under `dir` with the recorded size and hash.
+    pub fn verify(&self, dir: &Path) -> Result<(), ManifestError> {
+        for artifact in &self.artifacts {
+            if Artifact::read(dir, &artifact.path)? != *artifact {
+                return Err(ManifestError::Mismatch {
+                    path: artifact.path.clone(),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn listed_artifacts_exist_and_match() {
+        let dir = std::env::temp_dir().join(format!("zkpoex-manifest-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let artifacts = [
+            ("zkpoex.bincode", &b"proof"[..]),
+            ("zkpoex_chacha", b"chacha"),
+            ("zkpoex_tlock", b"tlock"),
+            ("zkpoex_fixture.json", b"{}"),
+        ];
+        for (name, contents) in artifacts {
+            fs::write(dir.join(name), contents).unwrap();
+        }
+        let names = artifacts.map(|(name, _)| name);
+
+        let manifest = Manifest::build(&dir, Some(42), "0x00ab".to_string(), names).unwrap();
+        assert_eq!(manifest.artifacts.len(), names.len());
+        for artifact in &manifest.artifacts {
+            assert!(dir.join(&artifact.path).is_file());
+        }
+        assert_eq!(
+            manifest.artifacts[3].sha256,
+            // SHA-256 of "{}".
+            "44136fa355b3678a1146ad16f7e8649e94fb4fc21fe77e8310c060f61caaff8a"
+        );
+        manifest.verify(&dir).unwrap();
+
+        fs::write(dir.join("zkpoex_tlock"), b"tampered").unwrap();
+        assert!(matches!(
+            manifest.verify(&dir),
+            Err(ManifestError::Mismatch { path }) if path == Path::new("zkpoex_tlock")
+        ));
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
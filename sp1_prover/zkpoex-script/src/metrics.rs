@@ -0,0 +1,332 @@
+//! Counters for running the prover as a long-lived service, in the Prometheus text format.
+//!
+//! Attach a [`Metrics`] to a [`ZkPoExProver`] with [`ZkPoExProver::with_metrics`] and wrap the
+//! drand source it is built from in [`Metrics::observe`], then either scrape [`Metrics::render`]
+//! from your own server or let [`serve`] answer `GET /metrics`.
+
+use std::{
+    fmt::Write as _,
+    io::{self, BufRead, BufReader, Write as _},
+    net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use drand_core::chain::ChainInfo;
+
+use crate::{
+    backend::ProofBackend,
+    drand::{ConnectError, DrandSource},
+    ProveError, SP1ZkPoExProofFixture, ZkPoExProver,
+};
+
+/// Upper bounds in seconds of the proving duration buckets, from a quick execution-sized proof to
+/// a Groth16 wrap on a slow machine.
+const DURATION_BUCKETS: [f64; 10] = [1., 5., 10., 30., 60., 120., 300., 600., 1800., 3600.];
+
+/// What the prover has done since the service started.
+#[derive(Debug, Default)]
+pub struct Metrics {
+    proofs_generated: AtomicU64,
+    proving_duration: Histogram,
+    drand_failures: AtomicU64,
+    verification_failures: AtomicU64,
+}
+
+#[derive(Debug, Default)]
+struct Histogram {
+    /// Observations per bucket of [`DURATION_BUCKETS`], not cumulative, plus one for the rest.
+    buckets: [AtomicU64; DURATION_BUCKETS.len() + 1],
+    sum_ms: AtomicU64,
+}
+
+impl Histogram {
+    fn observe(&self, d: Duration) {
+        let secs = d.as_secs_f64();
+        let bucket = DURATION_BUCKETS
+            .iter()
+            .position(|&le| secs <= le)
+            .unwrap_or(DURATION_BUCKETS.len());
+        self.buckets[bucket].fetch_add(1, Ordering::Relaxed);
+        self.sum_ms
+            .fetch_add(d.as_millis() as u64, Ordering::Relaxed);
+    }
+
+    fn render(&self, out: &mut String, name: &str) {
+        let mut cumulative = 0;
+        let les = DURATION_BUCKETS.iter().map(|le| le.to_string());
+        for (le, count) in les.chain(["+Inf".to_string()]).zip(&self.buckets) {
+            cumulative += count.load(Ordering::Relaxed);
+            let _ = writeln!(out, "{name}_bucket{{le=\"{le}\"}} {cumulative}");
+        }
+        let sum = self.sum_ms.load(Ordering::Relaxed) as f64 / 1000.;
+        let _ = writeln!(out, "{name}_sum {sum}");
+        let _ = writeln!(out, "{name}_count {cumulative}");
+    }
+}
+
+impl Metrics {
+    pub fn proofs_generated(&self) -> u64 {
+        self.proofs_generated.load(Ordering::Relaxed)
+    }
+
+    pub fn drand_failures(&self) -> u64 {
+        self.drand_failures.load(Ordering::Relaxed)
+    }
+
+    pub fn verification_failures(&self) -> u64 {
+        self.verification_failures.load(Ordering::Relaxed)
+    }
+
+    /// Counts the outcome of one [`ZkPoExProver::prove`].
+    pub fn record_prove<T>(&self, result: &Result<(SP1ZkPoExProofFixture, T), ProveError>) {
+        match result {
+            Ok((fixture, _)) => {
+                self.proofs_generated.fetch_add(1, Ordering::Relaxed);
+                if let Some(ms) = fixture.proving_duration_ms {
+                    self.proving_duration.observe(Duration::from_millis(ms));
+                }
+            }
+            Err(e) => self.record_verify(e),
+        }
+    }
+
+    /// Counts `e` if it is a proof that didn't verify.
+    pub fn record_verify(&self, e: &ProveError) {
+        if matches!(e, ProveError::Verify { .. }) {
+            self.verification_failures.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// `drand`, counting every failure to fetch its chain info.
+    pub fn observe<'a, D: DrandSource>(&'a self, drand: &'a D) -> ObservedDrand<'a, D> {
+        ObservedDrand {
+            drand,
+            metrics: self,
+        }
+    }
+
+    /// All metrics in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        let counter = |out: &mut String, name: &str, help: &str, value: u64| {
+            let _ = writeln!(out, "# HELP {name} {help}");
+            let _ = writeln!(out, "# TYPE {name} counter");
+            let _ = writeln!(out, "{name} {value}");
+        };
+
+        counter(
+            &mut out,
+            "zkpoex_proofs_generated_total",
+            "Proofs generated.",
+            self.proofs_generated(),
+        );
+        let _ = writeln!(
+            out,
+            "# HELP zkpoex_proving_duration_seconds Wall-clock time spent generating a proof."
+        );
+        let _ = writeln!(out, "# TYPE zkpoex_proving_duration_seconds histogram");
+        self.proving_duration
+            .render(&mut out, "zkpoex_proving_duration_seconds");
+        counter(
+            &mut out,
+            "zkpoex_drand_fetch_failures_total",
+            "Failed attempts to fetch the drand chain info.",
+            self.drand_failures(),
+        );
+        counter(
+            &mut out,
+            "zkpoex_verification_failures_total",
+            "Generated proofs that did not verify.",
+            self.verification_failures(),
+        );
+        out
+    }
+}
+
+/// A drand source whose failures are counted, see [`Metrics::observe`].
+pub struct ObservedDrand<'a, D> {
+    drand: &'a D,
+    metrics: &'a Metrics,
+}
+
+impl<D: DrandSource> DrandSource for ObservedDrand<'_, D> {
+    fn chain_info(&self) -> Result<ChainInfo, ConnectError> {
+        let info = self.drand.chain_info();
+        if info.is_err() {
+            self.metrics.drand_failures.fetch_add(1, Ordering::Relaxed);
+        }
+        info
+    }
+}
+
+/// Answers `GET /metrics` on `addr` from a background thread, returning the address it listens
+/// on. Every other request gets a 404.
+pub fn serve(metrics: Arc<Metrics>, addr: impl ToSocketAddrs) -> io::Result<SocketAddr> {
+    let listener = TcpListener::bind(addr)?;
+    let local_addr = listener.local_addr()?;
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let result = stream.and_then(|stream| respond(&metrics, stream));
+            if let Err(e) = result {
+                tracing::warn!("failed to answer a metrics request: {e}");
+            }
+        }
+    });
+    Ok(local_addr)
+}
+
+fn respond(metrics: &Metrics, mut stream: TcpStream) -> io::Result<()> {
+    let mut request_line = String::new();
+    BufReader::new(&stream).read_line(&mut request_line)?;
+
+    let (status, body) = match request_line.split_whitespace().take(2).collect::<Vec<_>>()[..] {
+        ["GET", "/metrics"] => ("200 OK", metrics.render()),
+        _ => ("404 Not Found", String::new()),
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )
+}
+
+impl<B: ProofBackend> ZkPoExProver<B> {
+    /// Counts every proof this prover generates, and every one that fails to verify, in `metrics`.
+    pub fn with_metrics(mut self, metrics: Arc<Metrics>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+
+    use super::*;
+    use crate::{
+        backend::MockBackend, cipher::PayloadCipher, digest::PrivateInputsDigest, drand::MockDrand,
+        inputs_hash, proof::ProofMode, report::ProgramReport, settings::BlockchainSettings,
+        Disclosure, ProveOptions, PublicValues, FIXTURE_SCHEMA_VERSION,
+    };
+
+    fn fixture(proving_duration_ms: u64) -> SP1ZkPoExProofFixture {
+        SP1ZkPoExProofFixture {
+            schema_version: FIXTURE_SCHEMA_VERSION,
+            key: [1; 32],
+            cipher: PayloadCipher::ChaCha20,
+            nonce: vec![2; 12],
+            round: Some(42),
+            before: String::new(),
+            after: String::new(),
+            hash_private_inputs: PrivateInputsDigest([0; 32]),
+            chacha_cipher: vec![3; 8],
+            tlock_cipher: vec![4; 8],
+            tlock_shares: vec![],
+            calldata: "63d9b770".to_string(),
+            blockchain_settings: BlockchainSettings::default().to_json(),
+            vkey: "0x00".to_string(),
+            proof_mode: ProofMode::Compressed,
+            proof: None,
+            proving_duration_ms: Some(proving_duration_ms),
+            cycles: None,
+            report: None,
+            policy_note: None,
+        }
+    }
+
+    #[test]
+    fn proving_increments_the_proof_counter() {
+        let metrics = Metrics::default();
+        metrics.record_prove(&Ok((fixture(7_000), ())));
+        metrics.record_prove(&Ok((fixture(90_000), ())));
+        metrics.record_prove::<()>(&Err(ProveError::TooManyCycles {
+            cycles: 2,
+            limit: 1,
+        }));
+        assert_eq!(metrics.proofs_generated(), 2);
+        assert_eq!(metrics.verification_failures(), 0);
+
+        let rendered = metrics.render();
+        assert!(rendered.contains("zkpoex_proofs_generated_total 2\n"));
+        assert!(rendered.contains("zkpoex_proving_duration_seconds_bucket{le=\"5\"} 0\n"));
+        assert!(rendered.contains("zkpoex_proving_duration_seconds_bucket{le=\"10\"} 1\n"));
+        assert!(rendered.contains("zkpoex_proving_duration_seconds_bucket{le=\"+Inf\"} 2\n"));
+        assert!(rendered.contains("zkpoex_proving_duration_seconds_sum 97\n"));
+    }
+
+    #[test]
+    fn proving_through_the_prover_is_counted() {
+        let settings = BlockchainSettings::default();
+        let public_values = PublicValues {
+            before: "{}".to_string(),
+            after: "{}".to_string(),
+            hash_private_inputs: PrivateInputsDigest([1; 32]),
+            chacha_cipher: vec![2, 3],
+            key_hash: "00".repeat(32),
+            inputs_hash: inputs_hash("63d9b770", &settings.to_json()),
+        };
+        let backend = MockBackend {
+            public_values: public_values.encode(),
+            report: ProgramReport::default(),
+            vkey: "0x00ab".to_string(),
+        };
+        let options = ProveOptions {
+            disclosure: Disclosure::After(Duration::from_secs(3600)),
+            setup_cache: None,
+            ..Default::default()
+        };
+        let metrics = Arc::new(Metrics::default());
+        let prover = ZkPoExProver::with_backend(backend, options, None, &MockDrand::default())
+            .unwrap()
+            .with_metrics(metrics.clone());
+
+        prover.prove("63d9b770", &settings).unwrap();
+        // The canned public values commit to other calldata, so this one fails before counting.
+        assert!(prover.prove("deadbeef", &settings).is_err());
+
+        assert_eq!(metrics.proofs_generated(), 1);
+        assert_eq!(metrics.verification_failures(), 0);
+        let rendered = metrics.render();
+        assert!(rendered.contains("zkpoex_proofs_generated_total 1\n"));
+        assert!(rendered.contains("zkpoex_proving_duration_seconds_count 1\n"));
+    }
+
+    #[test]
+    fn drand_failures_are_counted() {
+        let metrics = Metrics::default();
+        let working = MockDrand::default();
+        let broken = MockDrand {
+            scheme_id: "bls-unchained-on-g9".to_string(),
+            ..Default::default()
+        };
+
+        metrics.observe(&working).chain_info().unwrap();
+        assert!(metrics.observe(&broken).chain_info().is_err());
+        assert_eq!(metrics.drand_failures(), 1);
+    }
+
+    #[test]
+    fn metrics_endpoint_serves_the_text_format() {
+        let metrics = Arc::new(Metrics::default());
+        metrics.record_prove(&Ok((fixture(1_000), ())));
+        let addr = serve(metrics, "127.0.0.1:0").unwrap();
+
+        let get = |path: &str| {
+            let mut stream = TcpStream::connect(addr).unwrap();
+            write!(stream, "GET {path} HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).unwrap();
+            response
+        };
+
+        let response = get("/metrics");
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.contains("zkpoex_proofs_generated_total 1\n"));
+        assert!(get("/").starts_with("HTTP/1.1 404"));
+    }
+}
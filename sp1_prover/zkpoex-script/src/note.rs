@@ -0,0 +1,62 @@
+//! The free-form disclosure policy note a fixture can carry for the humans handling it downstream,
+//! e.g. who to contact and when the exploit will be disclosed.
+
+/// The most characters a note may have, enough for a contact and a line of policy.
+pub const MAX_POLICY_NOTE_LEN: usize = 280;
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum NoteError {
+    #[error("policy note is {len} characters, at most {MAX_POLICY_NOTE_LEN} are allowed")]
+    TooLong { len: usize },
+    /// Control characters could rewrite the terminal or the log of whoever displays the note.
+    #[error("policy note has a control character {character:?} at position {position}")]
+    ControlCharacter { character: char, position: usize },
+}
+
+/// Checks a policy note can be stored as is, trimming surrounding whitespace.
+pub fn policy_note(s: &str) -> Result<String, NoteError> {
+    let note = s.trim();
+    let len = note.chars().count();
+    if len > MAX_POLICY_NOTE_LEN {
+        return Err(NoteError::TooLong { len });
+    }
+    if let Some((position, character)) = note.chars().enumerate().find(|(_, c)| c.is_control()) {
+        return Err(NoteError::ControlCharacter {
+            character,
+            position,
+        });
+    }
+    Ok(note.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_note_is_kept_trimmed() {
+        assert_eq!(
+            policy_note("  Contact security@protocol.xyz; disclose after round 42 \n"),
+            Ok("Contact security@protocol.xyz; disclose after round 42".to_string())
+        );
+    }
+
+    #[test]
+    fn control_characters_and_long_notes_are_rejected() {
+        assert_eq!(
+            policy_note("Contact us\u{1b}[2J\x07now"),
+            Err(NoteError::ControlCharacter {
+                character: '\u{1b}',
+                position: 10,
+            })
+        );
+        assert!(policy_note("line one\nline two").is_err());
+        assert_eq!(
+            policy_note(&"a".repeat(MAX_POLICY_NOTE_LEN + 1)),
+            Err(NoteError::TooLong {
+                len: MAX_POLICY_NOTE_LEN + 1
+            })
+        );
+        assert!(policy_note(&"é".repeat(MAX_POLICY_NOTE_LEN)).is_ok());
+    }
+}
@@ -0,0 +1,227 @@
+//! Writing prover outputs so that readers only ever see complete files.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{Mutex, MutexGuard, PoisonError},
+};
+
+/// The directory the prover writes its outputs to when none is given, relative to where it runs.
+pub const DEFAULT_OUT_DIR: &str = "data";
+
+/// The file `--persist-key` writes the raw 32 byte payload key to in the out dir, which is where
+/// the ecdh script reads it from.
+pub const ENC_KEY_FILE: &str = "zkpoex_enc_key";
+
+/// `components` joined onto this crate's directory one at a time, so paths into the repository
+/// don't depend on the current directory or hardcode a separator.
+pub fn crate_path<'a>(components: impl IntoIterator<Item = &'a str>) -> PathBuf {
+    components.into_iter().fold(
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")),
+        |path, component| path.join(component),
+    )
+}
+
+/// The contracts' fixture directory, which the Solidity tests load the fixture from.
+pub fn default_fixtures_dir() -> PathBuf {
+    crate_path(["..", "contracts", "src", "fixtures"])
+}
+
+/// The name of the fixture the Solidity tests load: `zkpoex_fixture.json`, or
+/// `zkpoex_<name>_fixture.json` so the fixtures of several verifier contracts can share a
+/// directory. `suffix` tells the entries of a batch apart.
+pub fn fixture_file_name(name: Option<&str>, suffix: &str) -> String {
+    match name {
+        Some(name) => format!("zkpoex_{name}_fixture{suffix}.json"),
+        None => format!("zkpoex_fixture{suffix}.json"),
+    }
+}
+
+/// Clap value parser for fixture names, which must keep the fixture inside the fixtures dir.
+pub fn fixture_name_arg(s: &str) -> Result<String, String> {
+    if s.is_empty()
+        || !s
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        return Err(format!(
+            "{s:?} is not a fixture name, use letters, digits, '_' and '-'"
+        ));
+    }
+    Ok(s.to_string())
+}
+
+/// Clap value parser for output directories: relative paths are anchored to the current directory
+/// when the arguments are parsed, so later directory changes can't move the outputs.
+pub fn resolve_dir(s: &str) -> io::Result<PathBuf> {
+    Ok(std::env::current_dir()?.join(s))
+}
+
+/// Held while a staged file is renamed into place, so a run that is interrupted is cleaned up
+/// either before or after the rename, never during it.
+static COMMIT: Mutex<()> = Mutex::new(());
+
+fn commit_lock() -> MutexGuard<'static, ()> {
+    COMMIT.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// The sibling path a file is staged at before being renamed into place. Keeping it in the same
+/// directory keeps the rename on one filesystem, where it is atomic.
+fn staging_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".tmp");
+    path.with_file_name(name)
+}
+
+/// Lets `write` produce the file at a staging path and renames it to `path` only once `write`
+/// succeeded. On failure the staged file is removed and `path` is left untouched.
+pub fn write_atomic_with(
+    path: &Path,
+    write: impl FnOnce(&Path) -> io::Result<()>,
+) -> io::Result<()> {
+    let staging = staging_path(path);
+    let result = write(&staging)
+        .and_then(|()| fs::File::open(&staging)?.sync_all())
+        .and_then(|()| {
+            let _commit = commit_lock();
+            fs::rename(&staging, path)
+        });
+    if result.is_err() {
+        let _ = fs::remove_file(&staging);
+    }
+    result
+}
+
+/// Atomically replaces the file at `path` with `contents`.
+pub fn write_atomic(path: &Path, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    write_atomic_with(path, |staging| fs::write(staging, contents))
+}
+
+/// Removes the files a write that never finished left staged in `dir`, e.g. when a run is given up
+/// on. A missing `dir` has nothing staged.
+pub fn remove_staged(dir: &Path) -> io::Result<()> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e),
+    };
+    for entry in entries {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "tmp") && path.is_file() {
+            fs::remove_file(path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Removes what is staged in every one of `dirs` when the run is interrupted, e.g. by Ctrl-C. Waits
+/// for a rename under way to finish and holds off any other until the returned guard is dropped, so
+/// the process can exit leaving only complete outputs behind.
+pub fn abandon_staged<'a>(dirs: impl IntoIterator<Item = &'a Path>) -> MutexGuard<'static, ()> {
+    let commit = commit_lock();
+    for dir in dirs {
+        if let Err(e) = remove_staged(dir) {
+            tracing::warn!("failed to clean up {}: {e}", dir.display());
+        }
+    }
+    commit
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("zkpoex-output-{name}-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn complete_write_replaces_the_file() {
+        let dir = temp_dir("complete");
+        let path = dir.join("zkpoex_fixture.json");
+        fs::write(&path, "old").unwrap();
+
+        write_atomic(&path, "new").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+        assert!(!staging_path(&path).exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn interrupted_write_leaves_no_partial_file() {
+        let dir = temp_dir("interrupted");
+        let fresh = dir.join("zkpoex.bincode");
+        let existing = dir.join("zkpoex_fixture.json");
+        fs::write(&existing, "old").unwrap();
+
+        // Write half of the output, then fail as if the process had been killed.
+        let interrupted = |staging: &Path| {
+            fs::write(staging, b"{\"key\":")?;
+            Err(io::Error::new(io::ErrorKind::Interrupted, "killed"))
+        };
+        assert!(write_atomic_with(&fresh, interrupted).is_err());
+        assert!(write_atomic_with(&existing, interrupted).is_err());
+
+        assert!(!fresh.exists());
+        assert_eq!(fs::read_to_string(&existing).unwrap(), "old");
+        assert!(!staging_path(&fresh).exists());
+        assert!(!staging_path(&existing).exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn abandoned_staged_files_are_removed() {
+        let dir = temp_dir("abandoned");
+        let proof = dir.join("zkpoex.bincode");
+        fs::write(&proof, "proof").unwrap();
+        fs::write(staging_path(&dir.join("zkpoex_fixture.json")), "{").unwrap();
+
+        remove_staged(&dir).unwrap();
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        assert!(proof.is_file());
+        remove_staged(&dir.join("missing")).unwrap();
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn interrupt_leaves_no_partial_files() {
+        let dir = temp_dir("ctrl-c");
+        let fixtures = temp_dir("ctrl-c-fixtures");
+        fs::write(staging_path(&dir.join("zkpoex.bincode")), "half a proof").unwrap();
+        fs::write(staging_path(&fixtures.join("zkpoex_fixture.json")), "{").unwrap();
+
+        let commit = abandon_staged([dir.as_path(), fixtures.as_path()]);
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+        assert_eq!(fs::read_dir(&fixtures).unwrap().count(), 0);
+
+        // A write finishing while the process exits doesn't get to rename its file into place.
+        let path = dir.join("zkpoex_fixture.json");
+        let writer = std::thread::spawn({
+            let path = path.clone();
+            move || write_atomic(&path, "{}")
+        });
+        std::thread::sleep(std::time::Duration::from_millis(100));
+        assert!(!path.exists());
+
+        drop(commit);
+        writer.join().unwrap().unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "{}");
+        fs::remove_dir_all(dir).unwrap();
+        fs::remove_dir_all(fixtures).unwrap();
+    }
+
+    #[test]
+    fn fixture_name_goes_between_prefix_and_suffix() {
+        assert_eq!(fixture_file_name(None, ""), "zkpoex_fixture.json");
+        assert_eq!(
+            fixture_file_name(Some("vault"), "_3"),
+            "zkpoex_vault_fixture_3.json"
+        );
+        assert!(fixture_name_arg("vault-v2").is_ok());
+        for name in ["", "../vault", "a/b", "vault.json"] {
+            assert!(fixture_name_arg(name).is_err(), "{name}");
+        }
+    }
+}
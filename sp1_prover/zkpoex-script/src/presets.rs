@@ -0,0 +1,67 @@
+//! Blockchain settings for common chains, so `chain_id` and friends don't have to be written out.
+//!
+//! The block fields describe an empty, representative block of each chain rather than a live one:
+//! a real fork block should still be given with `--blockchain-settings`.
+
+use clap::ValueEnum;
+use primitive_types::{H160, U256};
+
+use crate::settings::BlockchainSettings;
+
+const GWEI: u64 = 1_000_000_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ChainPreset {
+    /// Ethereum mainnet.
+    Mainnet,
+    /// The Sepolia testnet.
+    Sepolia,
+    /// OP mainnet.
+    Optimism,
+}
+
+impl ChainPreset {
+    pub fn settings(self) -> BlockchainSettings {
+        let (chain_id, block_base_fee_per_gas) = match self {
+            ChainPreset::Mainnet => (1, U256::from(10 * GWEI)),
+            ChainPreset::Sepolia => (11_155_111, U256::from(GWEI)),
+            ChainPreset::Optimism => (10, U256::from(1_000_000)),
+        };
+
+        BlockchainSettings {
+            gas_price: block_base_fee_per_gas,
+            origin: H160::zero(),
+            chain_id,
+            block_hashes: Vec::new(),
+            block_number: 0,
+            block_coinbase: H160::zero(),
+            block_timestamp: 0,
+            block_difficulty: U256::zero(),
+            block_gas_limit: 30_000_000,
+            block_base_fee_per_gas,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn presets_have_their_chain_ids() {
+        assert_eq!(ChainPreset::Mainnet.settings().chain_id, 1);
+        assert_eq!(ChainPreset::Sepolia.settings().chain_id, 11_155_111);
+        assert_eq!(ChainPreset::Optimism.settings().chain_id, 10);
+    }
+
+    #[test]
+    fn presets_round_trip_through_the_wire_format() {
+        for preset in ChainPreset::value_variants() {
+            let settings = preset.settings();
+            assert_eq!(
+                BlockchainSettings::from_json(&settings.to_json()).unwrap(),
+                settings
+            );
+        }
+    }
+}
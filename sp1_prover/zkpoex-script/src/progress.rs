@@ -0,0 +1,82 @@
+//! Reporting which phase of a long proving run is under way.
+//!
+//! The SP1 SDK doesn't report progress from inside `prove_*`, so the phases are the steps the
+//! prover itself takes: setting the program up, executing it, proving it (which includes the
+//! compression into the requested proof mode) and, if asked to, verifying the proof.
+
+use std::{
+    fmt,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    Setup,
+    Execute,
+    Prove,
+    Verify,
+}
+
+impl fmt::Display for Phase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Phase::Setup => "setup",
+            Phase::Execute => "execute",
+            Phase::Prove => "prove",
+            Phase::Verify => "verify",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PhaseEvent {
+    Started,
+    Finished(Duration),
+}
+
+/// Called at the start and the end of every phase.
+pub type ProgressFn = Arc<dyn Fn(Phase, PhaseEvent) + Send + Sync>;
+
+/// Runs `f` as `phase`, logging its start and end and reporting both to `progress`.
+pub fn run_phase<T>(progress: Option<&ProgressFn>, phase: Phase, f: impl FnOnce() -> T) -> T {
+    tracing::info!("{phase} started");
+    if let Some(progress) = progress {
+        progress(phase, PhaseEvent::Started);
+    }
+
+    let started = Instant::now();
+    let output = f();
+    let elapsed = started.elapsed();
+
+    tracing::info!("{phase} finished in {elapsed:?}");
+    if let Some(progress) = progress {
+        progress(phase, PhaseEvent::Finished(elapsed));
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    #[test]
+    fn callback_sees_start_and_end_of_a_phase() {
+        let events = Arc::new(Mutex::new(vec![]));
+        let recorded = events.clone();
+        let progress: ProgressFn = Arc::new(move |phase, event| {
+            recorded.lock().unwrap().push((phase, event));
+        });
+
+        // Stands in for the prover, which runs every zkVM call through `run_phase`.
+        let proof = run_phase(Some(&progress), Phase::Prove, || "proof");
+        assert_eq!(proof, "proof");
+
+        let events = events.lock().unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0], (Phase::Prove, PhaseEvent::Started));
+        assert!(matches!(events[1], (Phase::Prove, PhaseEvent::Finished(_))));
+    }
+}
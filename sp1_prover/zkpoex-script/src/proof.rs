@@ -0,0 +1,138 @@
+//! The proof systems a zkpoex proof can be produced with.
+
+#[cfg(feature = "prover")]
+use std::path::Path;
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "prover")]
+use sp1_sdk::{
+    ProverClient, SP1CompressedProof, SP1Groth16Proof, SP1PlonkProof, SP1PublicValues, SP1Stdin,
+    SP1VerifyingKey,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProofMode {
+    /// A compressed STARK, cheap to produce but not verifiable on-chain.
+    #[default]
+    Compressed,
+    /// A Groth16 SNARK wrapping the STARK, verifiable on-chain.
+    Groth16,
+    /// A PLONK SNARK wrapping the STARK, verifiable on-chain.
+    Plonk,
+}
+
+/// A proof of the zkpoex program in any of the supported [`ProofMode`]s.
+#[cfg(feature = "prover")]
+pub enum ZkPoExProof {
+    Compressed(SP1CompressedProof),
+    Groth16(SP1Groth16Proof),
+    Plonk(SP1PlonkProof),
+}
+
+#[cfg(feature = "prover")]
+impl ZkPoExProof {
+    pub fn mode(&self) -> ProofMode {
+        match self {
+            ZkPoExProof::Compressed(_) => ProofMode::Compressed,
+            ZkPoExProof::Groth16(_) => ProofMode::Groth16,
+            ZkPoExProof::Plonk(_) => ProofMode::Plonk,
+        }
+    }
+
+    pub fn public_values(&self) -> &SP1PublicValues {
+        match self {
+            ZkPoExProof::Compressed(proof) => &proof.public_values,
+            ZkPoExProof::Groth16(proof) => &proof.public_values,
+            ZkPoExProof::Plonk(proof) => &proof.public_values,
+        }
+    }
+
+    /// The input the program was proven on.
+    pub fn stdin(&self) -> &SP1Stdin {
+        match self {
+            ZkPoExProof::Compressed(proof) => &proof.stdin,
+            ZkPoExProof::Groth16(proof) => &proof.stdin,
+            ZkPoExProof::Plonk(proof) => &proof.stdin,
+        }
+    }
+
+    /// The encoded proof for the Solidity verifier. Compressed proofs can't be verified on-chain,
+    /// so they have none.
+    pub fn bytes(&self) -> Option<String> {
+        match self {
+            ZkPoExProof::Compressed(_) => None,
+            ZkPoExProof::Groth16(proof) => Some(proof.bytes()),
+            ZkPoExProof::Plonk(proof) => Some(proof.bytes()),
+        }
+    }
+
+    pub fn load(mode: ProofMode, path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        Ok(match mode {
+            ProofMode::Compressed => ZkPoExProof::Compressed(SP1CompressedProof::load(path)?),
+            ProofMode::Groth16 => ZkPoExProof::Groth16(SP1Groth16Proof::load(path)?),
+            ProofMode::Plonk => ZkPoExProof::Plonk(SP1PlonkProof::load(path)?),
+        })
+    }
+
+    pub fn verify(&self, client: &ProverClient, vk: &SP1VerifyingKey) -> anyhow::Result<()> {
+        match self {
+            ZkPoExProof::Compressed(proof) => client.verify_compressed(proof, vk)?,
+            ZkPoExProof::Groth16(proof) => client.verify_groth16(proof, vk)?,
+            ZkPoExProof::Plonk(proof) => client.verify_plonk(proof, vk)?,
+        }
+        Ok(())
+    }
+
+    /// Wraps a compressed proof into a Groth16 one for the Solidity verifier, without executing the
+    /// program or proving the STARK again. A Groth16 proof is returned as is, a PLONK one can't be
+    /// converted. Only the local prover exposes the wrapping steps.
+    pub fn into_groth16(self, client: &ProverClient) -> anyhow::Result<Self> {
+        let compressed = match self {
+            ZkPoExProof::Compressed(proof) => proof,
+            ZkPoExProof::Groth16(_) => return Ok(self),
+            ZkPoExProof::Plonk(_) => anyhow::bail!("a plonk proof can't be converted to groth16"),
+        };
+
+        let prover = client.prover.sp1_prover();
+        let outer = prover.wrap_bn254(prover.shrink(compressed.proof)?)?;
+        let artifacts = if sp1_prover::build::sp1_dev_mode() {
+            sp1_prover::build::try_build_groth16_artifacts_dev(&prover.wrap_vk, &outer.proof)
+        } else {
+            sp1_prover::build::try_install_groth16_artifacts()
+        };
+        Ok(ZkPoExProof::Groth16(SP1Groth16Proof {
+            proof: prover.wrap_groth16(outer, &artifacts),
+            stdin: compressed.stdin,
+            public_values: compressed.public_values,
+        }))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        match self {
+            ZkPoExProof::Compressed(proof) => proof.save(path),
+            ZkPoExProof::Groth16(proof) => proof.save(path),
+            ZkPoExProof::Plonk(proof) => proof.save(path),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn proof_mode_parses_all_variants() {
+        assert_eq!(
+            ProofMode::from_str("compressed", false),
+            Ok(ProofMode::Compressed)
+        );
+        assert_eq!(
+            ProofMode::from_str("groth16", false),
+            Ok(ProofMode::Groth16)
+        );
+        assert_eq!(ProofMode::from_str("plonk", false), Ok(ProofMode::Plonk));
+        assert!(ProofMode::from_str("stark", false).is_err());
+    }
+}
@@ -0,0 +1,1090 @@
+//! Proving exploits with the SP1 prover. Only built with the `prover` feature, so tooling that
+//! just reads fixtures or discloses exploits doesn't have to compile it.
+
+use std::{
+    borrow::Cow,
+    path::{Path, PathBuf},
+    sync::mpsc::{self, RecvTimeoutError},
+    thread,
+    time::{Duration, Instant, SystemTime},
+};
+
+use drand_core::chain::ChainInfo;
+use sp1_sdk::{HashableKey, ProverClient, SP1Stdin};
+use zeroize::Zeroizing;
+
+use crate::{
+    archive::ArchiveError,
+    backend::{BackendProof, ProofBackend},
+    calldata::{canonical_calldata, CalldataError},
+    cipher::{associated_data, CipherError, PayloadCipher, PayloadKey},
+    client::{
+        catch_prover_panic, classify_proof_error, prover_client, prover_client_with, ProverConfig,
+        ProverError,
+    },
+    config::ConfigError,
+    disclosure_round,
+    drand::{ChainError, ConnectError, DrandChain, DrandSource, DrandUnavailable},
+    drand_round::{ensure_unpublished_at, RoundError},
+    elf::{program_elf, ElfError},
+    key_and_nonce,
+    manifest::ManifestError,
+    progress::{run_phase, Phase, ProgressFn},
+    proof::{ProofMode, ZkPoExProof},
+    report::ProgramReport,
+    settings::{BlockchainSettings, SettingsError},
+    setup::{default_setup_cache_dir, setup_cached},
+    split::{SplitError, SplitTimelock, TlockShare},
+    Disclosure, InputsMismatch, PublicValues, PublicValuesError, SP1ZkPoExProofFixture,
+    FIXTURE_SCHEMA_VERSION,
+};
+
+/// Everything besides the exploit itself that shapes a zkpoex proof.
+#[derive(Debug, Clone)]
+pub struct ProveOptions {
+    pub disclosure: Disclosure,
+    pub drand: DrandChain,
+    pub proof_mode: ProofMode,
+    /// Load the program from this ELF instead of the embedded [`crate::ZKPOEX_ELF`].
+    pub elf: Option<PathBuf>,
+    /// The cipher the exploit payload is encrypted with.
+    pub cipher: PayloadCipher,
+    /// Where to cache the proving key, or `None` to always run the setup.
+    pub setup_cache: Option<PathBuf>,
+    /// Encrypts the payload with this key instead of a fresh random one, e.g. to share it with the
+    /// ecdh script. Whoever else knows it can decrypt the exploit before the disclosure round.
+    pub enc_key: Option<[u8; 32]>,
+    /// Encrypts the payload with this nonce instead of a fresh random one, so the ciphertext can
+    /// be reproduced. Never reuse a nonce with the same key: the two payloads XOR to the XOR of
+    /// their plaintexts.
+    pub nonce: Option<Vec<u8>>,
+    /// Seeds the key and nonce so a proof can be reproduced. For debugging only: anyone who knows
+    /// the seed can decrypt the exploit without waiting for the disclosure round.
+    pub seed: Option<u64>,
+    /// Verify every proof right after producing it, failing instead of handing back a proof the
+    /// verifier would reject.
+    pub verify_after_prove: bool,
+    /// Timelock Shamir shares of the key to several rounds rather than the whole key to the
+    /// disclosure round. Ignored with [`Disclosure::Immediate`].
+    pub split: Option<SplitTimelock>,
+    /// Abort before proving if executing the program takes more cycles than this.
+    pub max_cycles: Option<u64>,
+    /// Recorded in the fixture as is, so check it with [`crate::note::policy_note`] first.
+    pub policy_note: Option<String>,
+    /// The prover to run on, the one `SP1_PROVER` selects by default.
+    pub prover: ProverConfig,
+}
+
+impl Default for ProveOptions {
+    fn default() -> Self {
+        Self {
+            disclosure: Disclosure::After(Duration::from_secs(90 * 24 * 60 * 60)),
+            drand: DrandChain::default(),
+            proof_mode: ProofMode::default(),
+            elf: None,
+            cipher: PayloadCipher::default(),
+            setup_cache: Some(default_setup_cache_dir()),
+            enc_key: None,
+            nonce: None,
+            seed: None,
+            verify_after_prove: false,
+            split: None,
+            max_cycles: None,
+            policy_note: None,
+            prover: ProverConfig::default(),
+        }
+    }
+}
+
+/// Errors that can occur while producing a zkpoex proof.
+#[derive(Debug, thiserror::Error)]
+pub enum ProveError {
+    #[error("failed to connect to drand: {0}")]
+    Drand(drand_core::DrandError),
+    #[error(transparent)]
+    DrandUnavailable(DrandUnavailable),
+    #[error("unusable drand chain: {0}")]
+    Chain(ChainError),
+    #[error("failed to compute disclosure round: {0}")]
+    Round(RoundError),
+    #[error("failed to tlock encrypt the key: {0}")]
+    Tlock(anyhow::Error),
+    #[error(transparent)]
+    Split(#[from] SplitError),
+    #[error("failed to execute the program: {0}")]
+    Execute(anyhow::Error),
+    /// Executing the program took more cycles than `--max-cycles` allows, so it wasn't proven.
+    #[error("program ran {cycles} cycles, more than the limit of {limit}")]
+    TooManyCycles { cycles: u64, limit: u64 },
+    #[error("failed to generate proof: {0}")]
+    Proof(anyhow::Error),
+    /// The run didn't finish within `--timeout`, whichever phase it was stuck in.
+    #[error("gave up after {}", humantime::format_duration(*.timeout))]
+    ProveTimeout { timeout: Duration },
+    /// Some calldatas of a batch failed to prove or write, the outputs of the others are kept.
+    #[error("{rejected} of {total} calldatas were rejected")]
+    BatchRejected { rejected: usize, total: usize },
+    /// The freshly generated proof was rejected by the verifier, along with the public values it
+    /// commits to.
+    #[error("generated proof does not verify: {source} (public values: 0x{public_values})")]
+    Verify {
+        source: anyhow::Error,
+        public_values: String,
+    },
+    #[error(transparent)]
+    PublicValues(PublicValuesError),
+    /// The inputs about to be written to the fixture aren't the ones that were proven.
+    #[error(transparent)]
+    Inputs(#[from] InputsMismatch),
+    #[error(transparent)]
+    Calldata(#[from] CalldataError),
+    #[error(transparent)]
+    Settings(#[from] SettingsError),
+    #[error("failed to write {}: {source}", .path.display())]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error(transparent)]
+    Manifest(#[from] ManifestError),
+    #[error(transparent)]
+    Archive(#[from] ArchiveError),
+    #[error(transparent)]
+    Config(#[from] ConfigError),
+    #[error(transparent)]
+    Prover(#[from] ProverError),
+    #[error(transparent)]
+    Elf(#[from] ElfError),
+    #[error(transparent)]
+    Cipher(#[from] CipherError),
+}
+
+impl From<ConnectError> for ProveError {
+    fn from(e: ConnectError) -> Self {
+        match e {
+            ConnectError::Client(e) => ProveError::Drand(e),
+            ConnectError::Unavailable(e) => ProveError::DrandUnavailable(e),
+            ConnectError::Chain(e) => ProveError::Chain(e),
+        }
+    }
+}
+
+/// Proves the exploit described by `calldata` and timelocks the encryption key to the drand round
+/// selected by `options`.
+///
+/// Nothing is written to disk; callers decide where the fixture bytes go.
+pub fn prove_zkpoex(
+    calldata: &str,
+    blockchain_settings: &BlockchainSettings,
+    options: &ProveOptions,
+) -> Result<SP1ZkPoExProofFixture, ProveError> {
+    prove_zkpoex_with_proof(calldata, blockchain_settings, options).map(|(fixture, _)| fixture)
+}
+
+/// Same as [`prove_zkpoex`], but also hands back the proof so it can be saved.
+pub fn prove_zkpoex_with_proof(
+    calldata: &str,
+    blockchain_settings: &BlockchainSettings,
+    options: &ProveOptions,
+) -> Result<(SP1ZkPoExProofFixture, ZkPoExProof), ProveError> {
+    ZkPoExProver::new(options.clone())?.prove(calldata, blockchain_settings)
+}
+
+/// A prover with the drand chain resolved and the program set up, so many exploits can be proven
+/// without paying for the setup each time. Proves with the SP1 [`ProverClient`] unless given
+/// another [`ProofBackend`].
+pub struct ZkPoExProver<B: ProofBackend = ProverClient> {
+    options: ProveOptions,
+    info: Option<ChainInfo>,
+    elf: Cow<'static, [u8]>,
+    backend: B,
+    pk: B::ProvingKey,
+    vk: B::VerifyingKey,
+    progress: Option<ProgressFn>,
+    #[cfg(feature = "metrics")]
+    pub(crate) metrics: Option<std::sync::Arc<crate::metrics::Metrics>>,
+}
+
+impl ZkPoExProver {
+    pub fn new(options: ProveOptions) -> Result<Self, ProveError> {
+        Self::with_progress(options, None)
+    }
+
+    /// Like [`ZkPoExProver::new`], reporting the start and end of every phase to `progress`.
+    pub fn with_progress(
+        options: ProveOptions,
+        progress: Option<ProgressFn>,
+    ) -> Result<Self, ProveError> {
+        let drand = options.drand.clone();
+        Self::with_drand(options, progress, &drand)
+    }
+
+    /// Like [`ZkPoExProver::with_progress`], timelocking to the chain `drand` reports rather than
+    /// the one `options.drand` names.
+    pub fn with_drand(
+        options: ProveOptions,
+        progress: Option<ProgressFn>,
+        drand: &impl DrandSource,
+    ) -> Result<Self, ProveError> {
+        // Setup the prover client.
+        let client = prover_client_with(&options.prover)?;
+        Self::with_backend(client, options, progress, drand)
+    }
+}
+
+impl<B: ProofBackend> ZkPoExProver<B> {
+    /// Like [`ZkPoExProver::with_drand`], running the program on `backend`.
+    pub fn with_backend(
+        backend: B,
+        options: ProveOptions,
+        progress: Option<ProgressFn>,
+        drand: &impl DrandSource,
+    ) -> Result<Self, ProveError> {
+        let info = timelock_chain(&options, drand)?;
+        let elf = program_elf(options.elf.as_deref())?;
+
+        // Setup the program.
+        let (pk, vk) = run_phase(progress.as_ref(), Phase::Setup, || {
+            catch_prover_panic(|| backend.setup(&elf, options.setup_cache.as_deref()))
+        })?;
+
+        Ok(Self {
+            options,
+            info,
+            elf,
+            backend,
+            pk,
+            vk,
+            progress,
+            #[cfg(feature = "metrics")]
+            metrics: None,
+        })
+    }
+
+    pub fn vk(&self) -> &B::VerifyingKey {
+        &self.vk
+    }
+
+    /// Checks `proof` against the program's verifying key, as [`verify_proof`] does.
+    pub fn verify(&self, proof: &B::Proof) -> Result<(), ProveError> {
+        let result = verify_proof(&self.backend, &self.vk, proof);
+        #[cfg(feature = "metrics")]
+        if let (Some(metrics), Err(e)) = (&self.metrics, &result) {
+            metrics.record_verify(e);
+        }
+        result
+    }
+
+    /// The drand chain the keys are timelocked on, `None` if they aren't timelocked.
+    pub fn chain_info(&self) -> Option<&ChainInfo> {
+        self.info.as_ref()
+    }
+
+    pub fn prove(
+        &self,
+        calldata: &str,
+        blockchain_settings: &BlockchainSettings,
+    ) -> Result<(SP1ZkPoExProofFixture, B::Proof), ProveError> {
+        let result = self.prove_once(calldata, blockchain_settings);
+        #[cfg(feature = "metrics")]
+        if let Some(metrics) = &self.metrics {
+            metrics.record_prove(&result);
+        }
+        result
+    }
+
+    fn prove_once(
+        &self,
+        calldata: &str,
+        blockchain_settings: &BlockchainSettings,
+    ) -> Result<(SP1ZkPoExProofFixture, B::Proof), ProveError> {
+        // The fixture records the calldata in the form the program committed to.
+        let calldata = &canonical_calldata(calldata)?;
+        let vkey = self.backend.vkey(&self.vk);
+        let ProgramInput {
+            key,
+            nonce,
+            round,
+            tlock_cipher,
+            tlock_shares,
+            stdin,
+        } = program_input(
+            self.info.as_ref(),
+            &self.options,
+            &vkey,
+            calldata,
+            blockchain_settings,
+        )?;
+
+        // Proving doesn't report cycles, so execute first; it is cheap next to the proof.
+        let (_, report) = run_phase(self.progress.as_ref(), Phase::Execute, || {
+            self.backend.execute(&self.elf, stdin.clone())
+        })
+        .map_err(ProveError::Execute)?;
+        check_cycles(report.cycles, self.options.max_cycles)?;
+
+        // Generate the proof.
+        let started = Instant::now();
+        let proof = run_phase(self.progress.as_ref(), Phase::Prove, || {
+            self.backend.prove(&self.pk, stdin, self.options.proof_mode)
+        })
+        .map_err(|e| match classify_proof_error(&e) {
+            Some(e) => ProveError::Prover(e),
+            None => ProveError::Proof(e),
+        })?;
+        let proving_duration = started.elapsed();
+
+        if self.options.verify_after_prove {
+            run_phase(self.progress.as_ref(), Phase::Verify, || {
+                verify_proof(&self.backend, &self.vk, &proof)
+            })?;
+        }
+
+        let public_values =
+            PublicValues::decode(proof.public_value_bytes()).map_err(ProveError::PublicValues)?;
+
+        let fixture = SP1ZkPoExProofFixture {
+            schema_version: FIXTURE_SCHEMA_VERSION,
+            before: public_values.before.clone(),
+            after: public_values.after.clone(),
+            hash_private_inputs: public_values.hash_private_inputs,
+            key: *key,
+            cipher: self.options.cipher,
+            nonce,
+            round,
+            chacha_cipher: public_values.chacha_cipher.clone(),
+            tlock_cipher,
+            tlock_shares,
+            calldata: calldata.to_string(),
+            blockchain_settings: blockchain_settings.to_json(),
+            vkey,
+            proof_mode: proof.mode(),
+            proof: proof.bytes(),
+            proving_duration_ms: Some(proving_duration.as_millis() as u64),
+            cycles: Some(report.cycles),
+            report: Some(report.summary()),
+            policy_note: self.options.policy_note.clone(),
+        };
+        fixture.check_inputs(&public_values)?;
+
+        Ok((fixture, proof))
+    }
+}
+
+/// Turns the compressed `proof` behind `fixture` into a Groth16 one the Solidity verifier accepts,
+/// see [`ZkPoExProof::into_groth16`], and returns the fixture updated to match. The public values
+/// carry over unchanged, so the rest of the fixture still holds.
+pub fn convert_to_groth16(
+    client: &ProverClient,
+    proof: ZkPoExProof,
+    fixture: &SP1ZkPoExProofFixture,
+) -> Result<(SP1ZkPoExProofFixture, ZkPoExProof), ProveError> {
+    let public_values =
+        PublicValues::decode(proof.public_values().as_slice()).map_err(ProveError::PublicValues)?;
+    // Otherwise the fixture of some other proof would end up pointing at this one.
+    fixture.check_inputs(&public_values)?;
+
+    let proof = proof.into_groth16(client).map_err(ProveError::Proof)?;
+    let fixture = SP1ZkPoExProofFixture {
+        proof_mode: proof.mode(),
+        proof: proof.bytes(),
+        ..fixture.clone()
+    };
+    Ok((fixture, proof))
+}
+
+/// Checks `proof` against `vk`, reporting the public values it commits to if it doesn't verify so
+/// the failure can be debugged.
+pub fn verify_proof<B: ProofBackend>(
+    backend: &B,
+    vk: &B::VerifyingKey,
+    proof: &B::Proof,
+) -> Result<(), ProveError> {
+    backend.verify(proof, vk).map_err(|source| {
+        let public_values = hex::encode(proof.public_value_bytes());
+        tracing::error!("proof does not verify, public values: 0x{public_values}");
+        ProveError::Verify {
+            source,
+            public_values,
+        }
+    })
+}
+
+/// The vkey of the program at `elf`, or of the embedded one, without proving anything. Loads the
+/// keys from `setup_cache` when they were already generated for that ELF.
+pub fn program_vkey(elf: Option<&Path>, setup_cache: Option<&Path>) -> Result<String, ProveError> {
+    let elf = program_elf(elf)?;
+    let client = prover_client()?;
+    let (_, vk) = catch_prover_panic(|| match setup_cache {
+        Some(cache_dir) => setup_cached(&client, &elf, cache_dir),
+        None => client.setup(&elf),
+    })?;
+    Ok(vk.bytes32())
+}
+
+/// The outcome of running the program without proving it.
+#[derive(Debug, Clone)]
+pub struct Execution {
+    pub public_values: PublicValues,
+    pub cycles: u64,
+    /// What the cycles were spent on.
+    pub report: ProgramReport,
+}
+
+/// Runs the zkpoex program on `calldata` without generating a proof, to check that it accepts the
+/// exploit. Skips the setup too, so this is quick to iterate with, unless the payload is
+/// authenticated: that binds it to the vkey, which only the setup computes.
+pub fn execute_zkpoex(
+    calldata: &str,
+    blockchain_settings: &BlockchainSettings,
+    options: &ProveOptions,
+) -> Result<Execution, ProveError> {
+    let calldata = &canonical_calldata(calldata)?;
+    let info = timelock_chain(options, &options.drand)?;
+    let elf = program_elf(options.elf.as_deref())?;
+    let client = prover_client_with(&options.prover)?;
+    let vkey = if options.cipher.is_authenticated() {
+        catch_prover_panic(|| client.setup(&elf))?.1.bytes32()
+    } else {
+        String::new()
+    };
+    let input = program_input(info.as_ref(), options, &vkey, calldata, blockchain_settings)?;
+
+    let (public_values, report) = client
+        .execute(&elf, input.stdin)
+        .map_err(ProveError::Execute)?;
+    check_cycles(report.total_instruction_count(), options.max_cycles)?;
+
+    Ok(Execution {
+        public_values: PublicValues::decode(public_values.as_slice())
+            .map_err(ProveError::PublicValues)?,
+        cycles: report.total_instruction_count(),
+        report: ProgramReport::of(&report),
+    })
+}
+
+/// Runs `f` on its own thread, failing with [`ProveError::ProveTimeout`] if it hasn't returned
+/// after `timeout`. A call stuck in the SDK or on the network can't be interrupted, so the thread
+/// is left behind to end with the process.
+pub fn with_timeout<T: Send + 'static>(
+    timeout: Duration,
+    f: impl FnOnce() -> Result<T, ProveError> + Send + 'static,
+) -> Result<T, ProveError> {
+    let (done, result) = mpsc::channel();
+    let handle = thread::spawn(move || {
+        // The receiver is gone if we already gave up.
+        let _ = done.send(f());
+    });
+    match result.recv_timeout(timeout) {
+        Ok(result) => result,
+        Err(RecvTimeoutError::Timeout) => Err(ProveError::ProveTimeout { timeout }),
+        // `f` panicked before sending anything, so pass the panic on.
+        Err(RecvTimeoutError::Disconnected) => match handle.join() {
+            Err(panic) => std::panic::resume_unwind(panic),
+            Ok(()) => unreachable!("the thread sends before returning"),
+        },
+    }
+}
+
+/// Fails if the program ran more than `max_cycles`, when a limit is given.
+pub fn check_cycles(cycles: u64, max_cycles: Option<u64>) -> Result<(), ProveError> {
+    match max_cycles {
+        Some(limit) if cycles > limit => Err(ProveError::TooManyCycles { cycles, limit }),
+        _ => Ok(()),
+    }
+}
+
+/// Fetches the chain info of `drand` and checks tlock can encrypt towards it.
+fn resolve_chain(drand: &impl DrandSource) -> Result<ChainInfo, ProveError> {
+    Ok(drand.chain_info()?)
+}
+
+/// The chain of `drand` if `options` timelock the key, without contacting it otherwise.
+fn timelock_chain(
+    options: &ProveOptions,
+    drand: &impl DrandSource,
+) -> Result<Option<ChainInfo>, ProveError> {
+    options
+        .disclosure
+        .is_timelocked()
+        .then(|| resolve_chain(drand))
+        .transpose()
+}
+
+/// Everything the program is run on for one exploit, along with the parts of it that end up in
+/// the fixture.
+struct ProgramInput {
+    /// Wiped when the input is dropped, once the key has been copied into the fixture.
+    key: Zeroizing<[u8; 32]>,
+    nonce: Vec<u8>,
+    round: Option<u64>,
+    tlock_cipher: Vec<u8>,
+    tlock_shares: Vec<TlockShare>,
+    stdin: SP1Stdin,
+}
+
+/// Draws a fresh key unless one was supplied, timelocks it to the disclosure round on `info`, if
+/// given, and assembles the program input.
+fn program_input(
+    info: Option<&ChainInfo>,
+    options: &ProveOptions,
+    vkey: &str,
+    calldata: &str,
+    blockchain_settings: &BlockchainSettings,
+) -> Result<ProgramInput, ProveError> {
+    if let Some(nonce) = &options.nonce {
+        options.cipher.check_nonce(nonce)?;
+    }
+    let (key, nonce) = key_and_nonce(
+        options.cipher,
+        options.enc_key,
+        options.nonce.as_deref(),
+        options.seed,
+    );
+    let key = Zeroizing::new(key);
+
+    let (round, drand_master_key, tlock_cipher, tlock_shares) = match info {
+        // Only the ChaCha ciphertext is written, the key is handed over directly.
+        None => (None, vec![], vec![], vec![]),
+        Some(info) => {
+            let drand_master_key = info.public_key();
+            let round = disclosure_round(info, options.disclosure).map_err(ProveError::Round)?;
+            let (tlock_cipher, tlock_shares) = match &options.split {
+                None => (tlock_encrypt(&key[..], &drand_master_key, round)?, vec![]),
+                Some(split) => (vec![], tlock_shares(info, split, &key)?),
+            };
+            (Some(round), drand_master_key, tlock_cipher, tlock_shares)
+        }
+    };
+
+    // Setup the inputs.
+    let payload_key = PayloadKey {
+        cipher: options.cipher,
+        key: *key,
+        nonce,
+    };
+    // The program reads a round either way, an untimelocked key is bound to round 0.
+    let stdin = zkpoex_stdin(
+        &payload_key,
+        &associated_data(round.unwrap_or_default(), vkey),
+        calldata,
+        blockchain_settings,
+        drand_master_key,
+        round.unwrap_or_default(),
+    );
+
+    Ok(ProgramInput {
+        key,
+        nonce: payload_key.nonce,
+        round,
+        tlock_cipher,
+        tlock_shares,
+        stdin,
+    })
+}
+
+fn tlock_encrypt(
+    plaintext: &[u8],
+    drand_master_key: &[u8],
+    round: u64,
+) -> Result<Vec<u8>, ProveError> {
+    let mut cipher = vec![];
+    tlock::encrypt(&mut cipher, plaintext, drand_master_key, round).map_err(ProveError::Tlock)?;
+    Ok(cipher)
+}
+
+/// Splits `key` as `split` asks and timelocks every share to its round.
+fn tlock_shares(
+    info: &ChainInfo,
+    split: &SplitTimelock,
+    key: &[u8; 32],
+) -> Result<Vec<TlockShare>, ProveError> {
+    if split.rounds.len() != split.split.shares as usize {
+        return Err(SplitError::ShareRounds {
+            split: split.split,
+            actual: split.rounds.len(),
+        }
+        .into());
+    }
+
+    let drand_master_key = info.public_key();
+    let shares = split.split.split(key, &mut rand::thread_rng());
+    shares
+        .iter()
+        .zip(&split.rounds)
+        .map(|(share, &round)| {
+            ensure_unpublished_at(info, round, SystemTime::now()).map_err(ProveError::Round)?;
+            Ok(TlockShare {
+                round,
+                tlock_cipher: tlock_encrypt(&share.to_bytes(), &drand_master_key, round)?,
+            })
+        })
+        .collect()
+}
+
+/// The input of the zkpoex program, in the order it reads it. `associated_data` is authenticated
+/// along with the payload if `payload_key` asks for ChaCha20-Poly1305.
+pub fn zkpoex_stdin(
+    payload_key: &PayloadKey,
+    associated_data: &[u8],
+    calldata: &str,
+    blockchain_settings: &BlockchainSettings,
+    drand_master_key: Vec<u8>,
+    round: u64,
+) -> SP1Stdin {
+    let mut stdin = SP1Stdin::new();
+    stdin.write(&(
+        payload_key.key,
+        payload_key.cipher.id(),
+        payload_key.nonce.clone(),
+        associated_data.to_vec(),
+        calldata.to_string(),
+        blockchain_settings.to_json(),
+        drand_master_key,
+        round,
+    ));
+    stdin
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        backend::MockBackend,
+        calldata,
+        digest::PrivateInputsDigest,
+        drand::{MockDrand, VECTOR_PUBLIC_KEY, VECTOR_ROUND, VECTOR_SIGNATURE},
+        drand_round::{round_after, round_at},
+        inputs_hash,
+        selftest::VECTOR_KEY,
+        tlock_header::tlock_inspect,
+    };
+
+    #[test]
+    fn calldata_file_gives_same_stdin_as_inline_calldata() {
+        let raw = [0x63, 0xd9, 0xb7, 0x70, 0x00, 0x00, 0x00, 0x2a];
+        let from_file = calldata::parse_calldata_file(&raw).unwrap();
+        let inline = calldata::calldata_arg("0x63d9b7700000002a").unwrap();
+        assert_eq!(from_file, [inline.clone()]);
+
+        let settings = BlockchainSettings::default();
+        let stdin = |calldata: &str| {
+            let payload_key = PayloadKey {
+                cipher: PayloadCipher::ChaCha20,
+                key: [1; 32],
+                nonce: vec![2; 12],
+            };
+            zkpoex_stdin(&payload_key, &[], calldata, &settings, vec![3; 96], 42).buffer
+        };
+        assert_eq!(stdin(&from_file[0]), stdin(&inline));
+    }
+
+    /// Runs a full proof against the default drand chain, so it needs the network and a while.
+    #[test]
+    #[ignore]
+    fn prove_records_duration_and_cycles() {
+        let fixture = prove_zkpoex(
+            "63d9b770",
+            &BlockchainSettings::default(),
+            &ProveOptions {
+                setup_cache: None,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert!(fixture.proving_duration_ms.unwrap() > 0);
+        assert!(fixture.cycles.unwrap() > 0);
+    }
+
+    #[test]
+    fn full_flow_runs_against_the_mock_backend() {
+        let settings = BlockchainSettings::default();
+        let public_values = PublicValues {
+            before: "{}".to_string(),
+            after: "{}".to_string(),
+            hash_private_inputs: PrivateInputsDigest([1; 32]),
+            chacha_cipher: vec![2, 3],
+            key_hash: "00".repeat(32),
+            inputs_hash: inputs_hash("63d9b770", &settings.to_json()),
+        };
+        let backend = MockBackend {
+            public_values: public_values.encode(),
+            report: ProgramReport {
+                cycles: 1_000,
+                ..Default::default()
+            },
+            vkey: "0x00ab".to_string(),
+        };
+        let options = ProveOptions {
+            disclosure: Disclosure::After(Duration::from_secs(3600)),
+            proof_mode: ProofMode::Groth16,
+            setup_cache: None,
+            verify_after_prove: true,
+            ..Default::default()
+        };
+        let prover =
+            ZkPoExProver::with_backend(backend, options, None, &MockDrand::default()).unwrap();
+
+        // Any spelling of the calldata ends up as the canonical one.
+        let (fixture, proof) = prover.prove("0x63D9B770", &settings).unwrap();
+        assert_eq!(fixture.calldata, "63d9b770");
+        assert_eq!(fixture.vkey, "0x00ab");
+        assert_eq!(fixture.cycles, Some(1_000));
+        assert_eq!(fixture.proof_mode, ProofMode::Groth16);
+        assert_eq!(fixture.chacha_cipher, public_values.chacha_cipher);
+        assert_eq!(proof.public_values, public_values.encode());
+
+        let round = fixture.round.unwrap();
+        assert_eq!(tlock_inspect(&fixture.tlock_cipher).unwrap().round, round);
+
+        let err = prover
+            .prove("63d9b77000", &settings)
+            .expect_err("the canned public values commit to other calldata");
+        assert!(matches!(err, ProveError::Inputs(_)), "{err}");
+
+        let limited = ZkPoExProver::with_backend(
+            prover.backend.clone(),
+            ProveOptions {
+                disclosure: Disclosure::Immediate,
+                max_cycles: Some(999),
+                setup_cache: None,
+                ..Default::default()
+            },
+            None,
+            &MockDrand::default(),
+        )
+        .unwrap();
+        assert!(matches!(
+            limited.prove("63d9b770", &settings),
+            Err(ProveError::TooManyCycles {
+                cycles: 1_000,
+                limit: 999
+            })
+        ));
+    }
+
+    /// Proves the embedded program in full, so it needs the network and a while.
+    #[test]
+    #[ignore]
+    fn program_vkey_matches_the_proven_one() {
+        let fixture = prove_zkpoex(
+            "63d9b770",
+            &BlockchainSettings::default(),
+            &ProveOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(program_vkey(None, None).unwrap(), fixture.vkey);
+    }
+
+    #[test]
+    fn stuck_prover_times_out() {
+        let timeout = Duration::from_millis(50);
+        // Stands in for a prover hung on the network.
+        let err = with_timeout(timeout, || {
+            thread::sleep(Duration::from_secs(2));
+            Ok("proof")
+        })
+        .unwrap_err();
+        assert!(matches!(err, ProveError::ProveTimeout { timeout: t } if t == timeout));
+        assert_eq!(err.to_string(), "gave up after 50ms");
+
+        assert_eq!(with_timeout(timeout, || Ok("proof")).unwrap(), "proof");
+        assert!(matches!(
+            with_timeout(timeout, || Err::<(), _>(
+                check_cycles(2, Some(1)).unwrap_err()
+            )),
+            Err(ProveError::TooManyCycles { .. })
+        ));
+    }
+
+    #[test]
+    fn cycles_over_the_limit_are_reported() {
+        let err = check_cycles(1_000, Some(10)).unwrap_err();
+        assert!(matches!(
+            err,
+            ProveError::TooManyCycles {
+                cycles: 1_000,
+                limit: 10
+            }
+        ));
+        assert_eq!(
+            err.to_string(),
+            "program ran 1000 cycles, more than the limit of 10"
+        );
+        assert!(check_cycles(10, Some(10)).is_ok());
+        assert!(check_cycles(u64::MAX, None).is_ok());
+    }
+
+    /// Executes against the default drand chain, so it needs the network.
+    #[test]
+    #[ignore]
+    fn execution_over_a_tiny_limit_aborts() {
+        let err = execute_zkpoex(
+            "63d9b770",
+            &BlockchainSettings::default(),
+            &ProveOptions {
+                max_cycles: Some(1),
+                ..Default::default()
+            },
+        )
+        .unwrap_err();
+        let ProveError::TooManyCycles { cycles, limit: 1 } = err else {
+            panic!("expected the cycle limit to abort, got {err}");
+        };
+        assert!(cycles > 1);
+    }
+
+    /// Proves against the default drand chain, so it needs the network and a while.
+    #[test]
+    #[ignore]
+    fn corrupted_proof_fails_verification() {
+        let prover = ZkPoExProver::new(ProveOptions {
+            setup_cache: None,
+            verify_after_prove: true,
+            ..Default::default()
+        })
+        .unwrap();
+        let (_, mut proof) = prover
+            .prove("63d9b770", &BlockchainSettings::default())
+            .unwrap();
+        verify_proof(&prover.backend, &prover.vk, &proof).unwrap();
+
+        let ZkPoExProof::Compressed(compressed) = &mut proof else {
+            panic!("default proof mode is compressed");
+        };
+        let mut public_values = compressed.public_values.as_slice().to_vec();
+        *public_values.last_mut().unwrap() ^= 1;
+        compressed.public_values = sp1_sdk::SP1PublicValues::from(&public_values);
+
+        let err = verify_proof(&prover.backend, &prover.vk, &proof).unwrap_err();
+        let ProveError::Verify {
+            public_values: dumped,
+            ..
+        } = err
+        else {
+            panic!("expected a verification failure, got {err}");
+        };
+        assert_eq!(dumped, hex::encode(&public_values));
+    }
+
+    #[test]
+    fn tlock_round_trips_with_a_published_signature() {
+        let public_key = hex::decode(VECTOR_PUBLIC_KEY).unwrap();
+        let cipher = tlock_encrypt(&VECTOR_KEY, &public_key, VECTOR_ROUND).unwrap();
+        assert_eq!(tlock_inspect(&cipher).unwrap().round, VECTOR_ROUND);
+
+        let mut opened = vec![];
+        tlock::decrypt(
+            &mut opened,
+            cipher.as_slice(),
+            &hex::decode(VECTOR_SIGNATURE).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(opened, VECTOR_KEY);
+    }
+
+    /// Proves against the default drand chain and wraps the proof, so it needs the network and a
+    /// long while.
+    #[test]
+    #[ignore]
+    fn conversion_to_groth16_preserves_the_public_values() {
+        let prover = ZkPoExProver::new(ProveOptions {
+            setup_cache: None,
+            ..Default::default()
+        })
+        .unwrap();
+        let (fixture, compressed) = prover
+            .prove("63d9b770", &BlockchainSettings::default())
+            .unwrap();
+        let public_values = compressed.public_values().as_slice().to_vec();
+
+        let (converted, groth16) =
+            convert_to_groth16(&prover.backend, compressed, &fixture).unwrap();
+        assert_eq!(groth16.public_values().as_slice(), public_values);
+        assert_eq!(converted.proof_mode, ProofMode::Groth16);
+        assert_eq!(converted.proof, groth16.bytes());
+        assert_eq!(converted.chacha_cipher, fixture.chacha_cipher);
+        prover.verify(&groth16).unwrap();
+    }
+
+    #[test]
+    fn supplied_key_is_timelocked_as_is() {
+        let info = MockDrand::default().chain_info().unwrap();
+        let round = round_after(&info, Duration::from_secs(3600)).unwrap();
+        let options = ProveOptions {
+            disclosure: Disclosure::AtRound(round),
+            enc_key: Some([9; 32]),
+            ..Default::default()
+        };
+
+        let input = program_input(
+            Some(&info),
+            &options,
+            "",
+            "63d9b770",
+            &BlockchainSettings::default(),
+        )
+        .unwrap();
+        assert_eq!(*input.key, [9; 32]);
+        assert_eq!(input.nonce.len(), 12);
+        assert_eq!(tlock_inspect(&input.tlock_cipher).unwrap().round, round);
+    }
+
+    #[test]
+    fn supplied_nonce_is_used_verbatim() {
+        let input = |cipher, nonce: &[u8]| {
+            let options = ProveOptions {
+                disclosure: Disclosure::Immediate,
+                cipher,
+                nonce: Some(nonce.to_vec()),
+                ..Default::default()
+            };
+            program_input(
+                None,
+                &options,
+                "",
+                "63d9b770",
+                &BlockchainSettings::default(),
+            )
+        };
+
+        let nonce = [5; 24];
+        assert_eq!(
+            input(PayloadCipher::XChaCha20, &nonce).unwrap().nonce,
+            nonce
+        );
+        assert!(matches!(
+            input(PayloadCipher::ChaCha20, &nonce),
+            Err(ProveError::Cipher(CipherError::NonceLength {
+                expected: 12,
+                actual: 24,
+                ..
+            }))
+        ));
+    }
+
+    #[test]
+    fn supplied_key_stays_out_of_the_fixture_json() {
+        let key = [0x5a; 32];
+        let settings = BlockchainSettings::default();
+        let public_values = PublicValues {
+            before: "{}".to_string(),
+            after: "{}".to_string(),
+            hash_private_inputs: PrivateInputsDigest([1; 32]),
+            chacha_cipher: vec![2, 3],
+            key_hash: crate::disclose::key_hash(&key),
+            inputs_hash: inputs_hash("63d9b770", &settings.to_json()),
+        };
+        let backend = MockBackend {
+            public_values: public_values.encode(),
+            report: ProgramReport::default(),
+            vkey: "0x00".to_string(),
+        };
+        let options = ProveOptions {
+            disclosure: Disclosure::After(Duration::from_secs(3600)),
+            enc_key: Some(key),
+            setup_cache: None,
+            ..Default::default()
+        };
+        let prover =
+            ZkPoExProver::with_backend(backend, options, None, &MockDrand::default()).unwrap();
+        let (fixture, _) = prover.prove("63d9b770", &settings).unwrap();
+
+        // Only the in-memory fixture has it, for the caller to persist if asked to.
+        assert_eq!(fixture.key, key);
+        let json = serde_json::to_string(&fixture).unwrap();
+        assert!(!json.contains(&serde_json::to_string(&key).unwrap()));
+        assert!(!json.contains(&hex::encode(key)));
+        assert!(!fixture.to_json().contains("\"key\""));
+        assert_eq!(
+            SP1ZkPoExProofFixture::from_json(&fixture.to_json())
+                .unwrap()
+                .key,
+            [0; 32]
+        );
+    }
+
+    /// Proves against the default drand chain and waits for the disclosure round, so it needs the
+    /// network and a while.
+    #[test]
+    #[ignore]
+    fn supplied_key_ends_up_in_the_tlock_and_fixture() {
+        let (client, info) = crate::drand::connect_drand(&DrandChain::default()).unwrap();
+        let round = round_at(&info, SystemTime::now()).unwrap() + 2;
+        let key = [9; 32];
+
+        let fixture = prove_zkpoex(
+            "63d9b770",
+            &BlockchainSettings::default(),
+            &ProveOptions {
+                disclosure: Disclosure::AtRound(round),
+                enc_key: Some(key),
+                setup_cache: None,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(fixture.key, key);
+
+        // Proving takes far longer than two rounds, so the round is published by now.
+        let beacon = client.get(round).unwrap();
+        let mut opened = vec![];
+        tlock::decrypt(
+            &mut opened,
+            fixture.tlock_cipher.as_slice(),
+            &beacon.signature(),
+        )
+        .unwrap();
+        assert_eq!(opened, key);
+    }
+
+    #[test]
+    fn key_is_timelocked_to_the_disclosure_round_of_the_mock_chain() {
+        let info = MockDrand::default().chain_info().unwrap();
+        let round = round_after(&info, Duration::from_secs(3600)).unwrap();
+        let options = ProveOptions {
+            disclosure: Disclosure::AtRound(round),
+            seed: Some(1),
+            ..Default::default()
+        };
+
+        let input = program_input(
+            Some(&info),
+            &options,
+            "",
+            "63d9b770",
+            &BlockchainSettings::default(),
+        )
+        .unwrap();
+        assert_eq!(input.round, Some(round));
+        assert_eq!(tlock_inspect(&input.tlock_cipher).unwrap().round, round);
+    }
+
+    #[test]
+    fn immediate_disclosure_leaves_the_key_untimelocked() {
+        let options = ProveOptions {
+            disclosure: Disclosure::Immediate,
+            // Unreachable, so resolving the chain would fail.
+            drand: DrandChain {
+                url: "http://127.0.0.1:9".to_string(),
+                ..Default::default()
+            },
+            seed: Some(1),
+            ..Default::default()
+        };
+        let info = timelock_chain(&options, &options.drand).unwrap();
+        assert!(info.is_none());
+
+        let input = program_input(
+            info.as_ref(),
+            &options,
+            "",
+            "63d9b770",
+            &BlockchainSettings::default(),
+        )
+        .unwrap();
+        assert_eq!(input.round, None);
+        assert!(input.tlock_cipher.is_empty());
+        assert!(input.tlock_shares.is_empty());
+    }
+}
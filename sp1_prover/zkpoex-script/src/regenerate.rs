@@ -0,0 +1,267 @@
+//! Rebuilding a fixture from the outputs of an earlier prove run, so the fixture format can change
+//! without paying for another proof.
+
+use crate::{
+    bundle::CipherBundle,
+    cipher::{CipherError, PayloadCipher, PayloadKey},
+    proof::{ProofMode, ZkPoExProof},
+    InputsMismatch, PublicValues, PublicValuesError, SP1ZkPoExProofFixture, FIXTURE_SCHEMA_VERSION,
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum RegenerateError {
+    #[error("proof has no program input")]
+    MissingInput,
+    #[error("failed to decode the program input: {0}")]
+    Input(bincode::Error),
+    #[error(transparent)]
+    Cipher(#[from] CipherError),
+    #[error(transparent)]
+    PublicValues(PublicValuesError),
+    /// A cipher file belongs to a different run than the proof.
+    #[error("{file} cipher file doesn't match the proof")]
+    CipherMismatch { file: &'static str },
+    #[error(transparent)]
+    Inputs(#[from] InputsMismatch),
+}
+
+/// The input the zkpoex program was proven on, as written by [`crate::zkpoex_stdin`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProgramStdin {
+    pub payload_key: PayloadKey,
+    pub associated_data: Vec<u8>,
+    pub calldata: String,
+    pub blockchain_settings: String,
+    pub drand_master_key: Vec<u8>,
+    pub round: u64,
+}
+
+impl ProgramStdin {
+    pub fn decode(buffer: &[Vec<u8>]) -> Result<Self, RegenerateError> {
+        let input = buffer.first().ok_or(RegenerateError::MissingInput)?;
+        let (
+            key,
+            cipher,
+            nonce,
+            associated_data,
+            calldata,
+            blockchain_settings,
+            drand_master_key,
+            round,
+        ) = bincode::deserialize(input).map_err(RegenerateError::Input)?;
+        Ok(Self {
+            payload_key: PayloadKey {
+                cipher: PayloadCipher::from_id(cipher)?,
+                key,
+                nonce,
+            },
+            associated_data,
+            calldata,
+            blockchain_settings,
+            drand_master_key,
+            round,
+        })
+    }
+}
+
+/// Rebuilds the fixture of `proof` from the cipher files written next to it and the program's
+/// `vkey`. The timings of the original run aren't recorded anywhere else, so they are left out.
+pub fn regenerate_fixture(
+    proof: &ZkPoExProof,
+    chacha: &CipherBundle,
+    tlock: &CipherBundle,
+    vkey: String,
+) -> Result<SP1ZkPoExProofFixture, RegenerateError> {
+    let input = ProgramStdin::decode(&proof.stdin().buffer)?;
+    let public_values = PublicValues::decode(proof.public_values().as_slice())
+        .map_err(RegenerateError::PublicValues)?;
+    fixture_from_parts(
+        input,
+        public_values,
+        chacha,
+        tlock,
+        vkey,
+        proof.mode(),
+        proof.bytes(),
+    )
+}
+
+fn fixture_from_parts(
+    input: ProgramStdin,
+    public_values: PublicValues,
+    chacha: &CipherBundle,
+    tlock: &CipherBundle,
+    vkey: String,
+    proof_mode: ProofMode,
+    proof: Option<String>,
+) -> Result<SP1ZkPoExProofFixture, RegenerateError> {
+    let PayloadKey { cipher, key, nonce } = input.payload_key;
+    let matches_input = |bundle: &CipherBundle| {
+        (bundle.cipher, &bundle.nonce, bundle.round) == (cipher, &nonce, input.round)
+    };
+    if !matches_input(chacha) || chacha.ciphertext != public_values.chacha_cipher {
+        return Err(RegenerateError::CipherMismatch { file: "chacha" });
+    }
+    if !matches_input(tlock) {
+        return Err(RegenerateError::CipherMismatch { file: "tlock" });
+    }
+    public_values.check_inputs(&input.calldata, &input.blockchain_settings)?;
+
+    Ok(SP1ZkPoExProofFixture {
+        schema_version: FIXTURE_SCHEMA_VERSION,
+        key,
+        cipher,
+        nonce,
+        round: Some(input.round),
+        before: public_values.before,
+        after: public_values.after,
+        hash_private_inputs: public_values.hash_private_inputs,
+        chacha_cipher: public_values.chacha_cipher,
+        tlock_cipher: tlock.ciphertext.clone(),
+        tlock_shares: vec![],
+        calldata: input.calldata,
+        blockchain_settings: input.blockchain_settings,
+        vkey,
+        proof_mode,
+        proof,
+        proving_duration_ms: None,
+        cycles: None,
+        report: None,
+        policy_note: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        cipher::associated_data,
+        diff::fixture_diff,
+        digest::PrivateInputsDigest,
+        drand::BeaconScheme,
+        inputs_hash,
+        settings::{BlockchainSettings, DEFAULT_BLOCKCHAIN_SETTINGS},
+        zkpoex_stdin,
+    };
+
+    fn original() -> SP1ZkPoExProofFixture {
+        SP1ZkPoExProofFixture {
+            schema_version: FIXTURE_SCHEMA_VERSION,
+            key: [1; 32],
+            cipher: PayloadCipher::ChaCha20Poly1305,
+            nonce: vec![2; 12],
+            round: Some(42),
+            before: "{}".to_string(),
+            after: "{\"0x01\":\"0x02\"}".to_string(),
+            hash_private_inputs: PrivateInputsDigest([3; 32]),
+            chacha_cipher: vec![4, 5, 6],
+            tlock_cipher: b"age-encryption.org/v1\n".to_vec(),
+            tlock_shares: vec![],
+            calldata: "0xdeadbeef".to_string(),
+            blockchain_settings: BlockchainSettings::from_json(DEFAULT_BLOCKCHAIN_SETTINGS)
+                .unwrap()
+                .to_json(),
+            vkey: "0x00ab".to_string(),
+            proof_mode: ProofMode::Groth16,
+            proof: Some("0xc430ff7f".to_string()),
+            proving_duration_ms: Some(1_000),
+            cycles: Some(2_000),
+            report: None,
+            policy_note: None,
+        }
+    }
+
+    /// The parts a prove run of `fixture` leaves behind, as they are read back.
+    fn saved_parts(
+        fixture: &SP1ZkPoExProofFixture,
+    ) -> (ProgramStdin, PublicValues, CipherBundle, CipherBundle) {
+        let settings = BlockchainSettings::from_json(&fixture.blockchain_settings).unwrap();
+        let round = fixture.round.unwrap();
+        let payload_key = PayloadKey {
+            cipher: fixture.cipher,
+            key: fixture.key,
+            nonce: fixture.nonce.clone(),
+        };
+        let stdin = zkpoex_stdin(
+            &payload_key,
+            &associated_data(round, &fixture.vkey),
+            &fixture.calldata,
+            &settings,
+            vec![7; 96],
+            round,
+        );
+        let public_values = PublicValues {
+            before: fixture.before.clone(),
+            after: fixture.after.clone(),
+            hash_private_inputs: fixture.hash_private_inputs,
+            chacha_cipher: fixture.chacha_cipher.clone(),
+            key_hash: "00".repeat(32),
+            inputs_hash: inputs_hash(&fixture.calldata, &fixture.blockchain_settings),
+        };
+        let bundle = |ciphertext: &[u8]| {
+            CipherBundle::new(
+                fixture.cipher,
+                Some(BeaconScheme::UnchainedOnG1),
+                fixture.nonce.clone(),
+                round,
+                ciphertext.to_vec(),
+            )
+        };
+        let chacha = bundle(&fixture.chacha_cipher);
+        let tlock = bundle(&fixture.tlock_cipher);
+        (
+            ProgramStdin::decode(&stdin.buffer).unwrap(),
+            public_values,
+            chacha,
+            tlock,
+        )
+    }
+
+    #[test]
+    fn regenerated_fixture_matches_the_original() {
+        let original = original();
+        let (input, public_values, chacha, tlock) = saved_parts(&original);
+
+        let regenerated = fixture_from_parts(
+            input,
+            public_values,
+            &chacha,
+            &tlock,
+            original.vkey.clone(),
+            original.proof_mode,
+            original.proof.clone(),
+        )
+        .unwrap();
+
+        // Only the timings of the run are lost.
+        assert!(
+            fixture_diff(&original, &regenerated, &["proving_duration_ms", "cycles"]).is_empty()
+        );
+    }
+
+    #[test]
+    fn cipher_files_of_another_run_are_rejected() {
+        let original = original();
+        let (input, public_values, chacha, _) = saved_parts(&original);
+        let other_tlock = CipherBundle::new(
+            original.cipher,
+            Some(BeaconScheme::UnchainedOnG1),
+            original.nonce.clone(),
+            original.round.unwrap() + 1,
+            vec![],
+        );
+
+        assert!(matches!(
+            fixture_from_parts(
+                input,
+                public_values,
+                &chacha,
+                &other_tlock,
+                original.vkey,
+                original.proof_mode,
+                original.proof,
+            ),
+            Err(RegenerateError::CipherMismatch { file: "tlock" })
+        ));
+    }
+}
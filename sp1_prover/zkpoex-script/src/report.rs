@@ -0,0 +1,147 @@
+//! What executing the program spent its cycles on, for finding the hotspots of the zkpoex program.
+//!
+//! The SP1 SDK reports how often every opcode ran and every syscall was made; this keeps those
+//! counts by name, so they can be printed, written as JSON or summarized into the fixture.
+
+use std::{collections::BTreeMap, fmt};
+
+use serde::{Deserialize, Serialize};
+
+/// How many of the most frequent syscalls a [`ReportSummary`] keeps.
+pub const TOP_SYSCALLS: usize = 5;
+
+/// The full execution report: every opcode and syscall with how often it ran.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProgramReport {
+    pub cycles: u64,
+    pub syscalls: u64,
+    pub opcode_counts: BTreeMap<String, u64>,
+    pub syscall_counts: BTreeMap<String, u64>,
+}
+
+/// The part of a [`ProgramReport`] kept in the fixture.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportSummary {
+    pub cycles: u64,
+    /// The [`TOP_SYSCALLS`] most frequent syscalls, most frequent first.
+    pub top_syscalls: Vec<(String, u64)>,
+}
+
+#[cfg(feature = "prover")]
+impl ProgramReport {
+    pub fn of(report: &sp1_sdk::ExecutionReport) -> Self {
+        Self {
+            cycles: report.total_instruction_count(),
+            syscalls: report.total_syscall_count(),
+            opcode_counts: report
+                .opcode_counts
+                .iter()
+                .map(|(opcode, &count)| (format!("{opcode:?}"), count))
+                .collect(),
+            syscall_counts: report
+                .syscall_counts
+                .iter()
+                .map(|(syscall, &count)| (format!("{syscall:?}"), count))
+                .collect(),
+        }
+    }
+}
+
+impl ProgramReport {
+    /// The report as pretty JSON, names in sorted order.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("report serializes to json")
+    }
+
+    pub fn summary(&self) -> ReportSummary {
+        ReportSummary {
+            cycles: self.cycles,
+            top_syscalls: by_count(&self.syscall_counts)
+                .take(TOP_SYSCALLS)
+                .map(|(name, count)| (name.to_string(), count))
+                .collect(),
+        }
+    }
+}
+
+/// The entries of `counts`, most frequent first and by name among equals.
+fn by_count(counts: &BTreeMap<String, u64>) -> impl Iterator<Item = (&str, u64)> {
+    let mut sorted: Vec<_> = counts
+        .iter()
+        .map(|(name, &count)| (name.as_str(), count))
+        .collect();
+    sorted.sort_by(|(a, m), (b, n)| n.cmp(m).then(a.cmp(b)));
+    sorted.into_iter()
+}
+
+impl fmt::Display for ProgramReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Cycles: {}", self.cycles)?;
+        writeln!(f, "Syscalls: {}", self.syscalls)?;
+        for (title, counts) in [
+            ("Opcodes", &self.opcode_counts),
+            ("Syscall Counts", &self.syscall_counts),
+        ] {
+            writeln!(f, "{title}:")?;
+            for (name, count) in by_count(counts) {
+                writeln!(f, "  {name:<24} {count}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report() -> ProgramReport {
+        ProgramReport {
+            cycles: 1_000,
+            syscalls: 60,
+            opcode_counts: [("ADD", 600), ("LW", 400)]
+                .map(|(name, count)| (name.to_string(), count))
+                .into(),
+            syscall_counts: [
+                ("WRITE", 2),
+                ("SHA_EXTEND", 20),
+                ("KECCAK_PERMUTE", 20),
+                ("HALT", 1),
+                ("ENTER_UNCONSTRAINED", 7),
+                ("HINT_LEN", 10),
+            ]
+            .map(|(name, count)| (name.to_string(), count))
+            .into(),
+        }
+    }
+
+    #[test]
+    fn report_json_is_valid_and_has_the_cycles() {
+        let json: serde_json::Value = serde_json::from_str(&report().to_json()).unwrap();
+        assert_eq!(json["cycles"], 1_000);
+        assert_eq!(json["syscallCounts"]["SHA_EXTEND"], 20);
+    }
+
+    #[test]
+    fn summary_keeps_the_most_frequent_syscalls() {
+        let summary = report().summary();
+        assert_eq!(summary.cycles, 1_000);
+        let names: Vec<_> = summary
+            .top_syscalls
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "KECCAK_PERMUTE",
+                "SHA_EXTEND",
+                "HINT_LEN",
+                "ENTER_UNCONSTRAINED",
+                "WRITE"
+            ]
+        );
+    }
+}
@@ -0,0 +1,103 @@
+//! Extending the timelock of a proven exploit when its disclosure deadline slips.
+//!
+//! Only the key's timelock changes: it is timelocked again to a later round, while the proof and
+//! the ChaCha ciphertext stay as they are. The payload remains bound to the round it was encrypted
+//! for, which its ChaCha bundle keeps recording.
+
+use std::time::SystemTime;
+
+use drand_core::chain::ChainInfo;
+
+use crate::{
+    bundle::CipherBundle,
+    drand::{BeaconScheme, ChainError},
+    drand_round::{ensure_unpublished_at, RoundError},
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum ResealError {
+    /// Resealing can only postpone the disclosure: whoever holds the old bundle opens it at its
+    /// round regardless.
+    #[error(
+        "the key is timelocked to round {current}, reseal it to a later round than {requested}"
+    )]
+    NotLater { current: u64, requested: u64 },
+    #[error(transparent)]
+    Round(#[from] RoundError),
+    #[error("unusable drand chain: {0}")]
+    Chain(#[from] ChainError),
+    #[error("failed to tlock encrypt the key: {0}")]
+    Tlock(anyhow::Error),
+}
+
+/// Timelocks `key` to `round` of `info` in place of the round `tlock` is timelocked to, keeping its
+/// cipher and nonce. `key` isn't checked against `tlock`, which can't be opened before its round.
+pub fn reseal(
+    tlock: &CipherBundle,
+    key: &[u8; 32],
+    round: u64,
+    info: &ChainInfo,
+) -> Result<CipherBundle, ResealError> {
+    if round <= tlock.round {
+        return Err(ResealError::NotLater {
+            current: tlock.round,
+            requested: round,
+        });
+    }
+    ensure_unpublished_at(info, round, SystemTime::now())?;
+    let scheme = BeaconScheme::of(info)?;
+
+    let mut ciphertext = vec![];
+    tlock::encrypt(&mut ciphertext, &key[..], &info.public_key(), round)
+        .map_err(ResealError::Tlock)?;
+    Ok(CipherBundle::new(
+        tlock.cipher,
+        Some(scheme),
+        tlock.nonce.clone(),
+        round,
+        ciphertext,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::{
+        cipher::PayloadCipher,
+        drand::{DrandSource, MockDrand},
+        drand_round::round_after,
+        tlock_header::tlock_inspect,
+    };
+
+    #[test]
+    fn resealing_targets_the_later_round() {
+        let info = MockDrand::default().chain_info().unwrap();
+        let round = round_after(&info, Duration::from_secs(3600)).unwrap();
+        let key = [9; 32];
+        let mut ciphertext = vec![];
+        tlock::encrypt(&mut ciphertext, &key[..], &info.public_key(), round).unwrap();
+        let tlock = CipherBundle::new(
+            PayloadCipher::ChaCha20,
+            Some(BeaconScheme::UnchainedOnG1),
+            vec![2; 12],
+            round,
+            ciphertext,
+        );
+
+        let later = round + 1_000;
+        let resealed = reseal(&tlock, &key, later, &info).unwrap();
+        assert_eq!(resealed.round, later);
+        assert_eq!(tlock_inspect(&resealed.ciphertext).unwrap().round, later);
+        assert_eq!(
+            (resealed.cipher, &resealed.nonce),
+            (tlock.cipher, &tlock.nonce)
+        );
+
+        assert!(matches!(
+            reseal(&tlock, &key, round, &info),
+            Err(ResealError::NotLater { current, requested }) if current == round && requested == round
+        ));
+    }
+}
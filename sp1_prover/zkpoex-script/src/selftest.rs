@@ -0,0 +1,138 @@
+//! Quick checks that the prover's environment works, short of generating a proof: the zkVM runs
+//! the program, tlock opens what it seals, and the ecdh hand-off recovers the key.
+
+use std::fmt;
+
+use clap::ValueEnum;
+use ecdh_script::keys::{decrypt_key_cipher, encrypt_key_to, Curve, Kdf, MismatchError};
+use sha3::{Digest, Keccak256};
+
+use crate::{
+    drand::{VECTOR_PUBLIC_KEY, VECTOR_ROUND, VECTOR_SIGNATURE},
+    execute_zkpoex,
+    settings::BlockchainSettings,
+    tlock_encrypt, Disclosure, ProveError, ProveOptions,
+};
+
+/// The calldata of the exploit the program is executed on.
+pub const SELFTEST_CALLDATA: &str = "63d9b770";
+
+/// The key the tlock check seals and opens again.
+pub(crate) const VECTOR_KEY: [u8; 32] = *b"zkpoex tlock round trip vector!!";
+
+#[derive(Debug, thiserror::Error)]
+pub enum SelftestError {
+    #[error(transparent)]
+    Prove(#[from] ProveError),
+    #[error("failed to tlock decrypt: {0}")]
+    TlockDecrypt(anyhow::Error),
+    #[error("{0} round trip gave back another key")]
+    RoundTrip(&'static str),
+    #[error("{curve}: {source}")]
+    Ecdh { curve: Curve, source: MismatchError },
+    #[error("{0}: both sides derived different shared secrets")]
+    SharedSecret(Curve),
+}
+
+/// The subsystems [`run_selftest`] checks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Subsystem {
+    /// The zkVM executing the embedded program.
+    Execute,
+    /// Timelock encryption against a recorded drand beacon.
+    Tlock,
+    /// The ECDH key hand-off on every curve.
+    Ecdh,
+}
+
+impl fmt::Display for Subsystem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // `pad` so the names line up in the report.
+        f.pad(match self {
+            Subsystem::Execute => "execute",
+            Subsystem::Tlock => "tlock",
+            Subsystem::Ecdh => "ecdh",
+        })
+    }
+}
+
+/// Runs every check, carrying on past failures so all of them are reported.
+pub fn run_selftest() -> Vec<(Subsystem, Result<(), SelftestError>)> {
+    vec![
+        (Subsystem::Execute, check_execute()),
+        (Subsystem::Tlock, check_tlock()),
+        (Subsystem::Ecdh, check_ecdh()),
+    ]
+}
+
+/// Executes the program without a timelock, so neither drand nor the setup is needed.
+fn check_execute() -> Result<(), SelftestError> {
+    let options = ProveOptions {
+        disclosure: Disclosure::Immediate,
+        setup_cache: None,
+        ..Default::default()
+    };
+    execute_zkpoex(SELFTEST_CALLDATA, &BlockchainSettings::default(), &options)?;
+    Ok(())
+}
+
+fn check_tlock() -> Result<(), SelftestError> {
+    let public_key = hex::decode(VECTOR_PUBLIC_KEY).expect("valid hex");
+    let cipher = tlock_encrypt(&VECTOR_KEY, &public_key, VECTOR_ROUND)?;
+
+    let mut opened = vec![];
+    let signature = hex::decode(VECTOR_SIGNATURE).expect("valid hex");
+    tlock::decrypt(&mut opened, cipher.as_slice(), &signature)
+        .map_err(SelftestError::TlockDecrypt)?;
+    if opened != VECTOR_KEY {
+        return Err(SelftestError::RoundTrip("tlock"));
+    }
+    Ok(())
+}
+
+/// Hands a key from a fresh local key pair to a fresh vendor one and back, as the ecdh script and
+/// a vendor would.
+fn check_ecdh() -> Result<(), SelftestError> {
+    let nonce = [7; 12];
+    for &curve in Curve::value_variants() {
+        let ecdh = |source| SelftestError::Ecdh { curve, source };
+        let (local_sk, local_pk) = curve.random_key_pair();
+        let (vendor_sk, vendor_pk) = curve.random_key_pair();
+
+        let shared = curve.shared_secret(&local_sk, &vendor_pk).map_err(ecdh)?;
+        if curve.shared_secret(&vendor_sk, &local_pk).map_err(ecdh)? != shared {
+            return Err(SelftestError::SharedSecret(curve));
+        }
+
+        let key_cipher = encrypt_key_to(
+            curve,
+            Kdf::default(),
+            &local_sk,
+            &[vendor_pk],
+            &VECTOR_KEY,
+            &nonce,
+        )
+        .map_err(ecdh)?;
+        let key_hash: [u8; 32] = Keccak256::digest(VECTOR_KEY).into();
+        let opened = decrypt_key_cipher(Kdf::default(), &shared, &nonce, &key_cipher[0], key_hash)
+            .map_err(ecdh)?;
+        if opened != VECTOR_KEY {
+            return Err(SelftestError::RoundTrip("ecdh"));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn selftest_passes() {
+        for (subsystem, result) in run_selftest() {
+            if let Err(e) = result {
+                panic!("{subsystem} failed: {e}");
+            }
+        }
+    }
+}
@@ -0,0 +1,378 @@
+//! The typed `blockchain_settings` the exploit is simulated under.
+//!
+//! The zkpoex program reads the settings as a JSON object of strings, parsing numbers as hex (the
+//! way `U256::from_str` does) and `block_hashes` as a stringified JSON array. Settings are parsed
+//! with the same rules here, so a typo fails before proving rather than inside the zkVM, and are
+//! re-serialized deterministically in that same format.
+//!
+//! `block_hashes` holds at most [`MAX_BLOCK_HASHES`] 0x prefixed 32 byte hashes, the window
+//! `BLOCKHASH` can see. Addresses may be given EIP-55 checksummed, in which case the checksum has
+//! to hold, but are always serialized in lowercase.
+
+use std::str::FromStr;
+
+use primitive_types::{H160, H256, U256};
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Keccak256};
+
+/// The settings used when none are given: an empty mainnet block.
+pub const DEFAULT_BLOCKCHAIN_SETTINGS: &str = r#"
+    {
+        "gas_price": "0",
+        "origin": "0x0000000000000000000000000000000000000000",
+        "block_hashes": "[]",
+        "block_number": "0",
+        "block_coinbase": "0x0000000000000000000000000000000000000000",
+        "block_timestamp": "0",
+        "block_difficulty": "0",
+        "block_gas_limit": "0",
+        "chain_id": "1",
+        "block_base_fee_per_gas": "0"
+    }
+"#;
+
+/// The most `block_hashes` accepted: `BLOCKHASH` only reaches back 256 blocks, so more would just
+/// bloat the program's stdin.
+pub const MAX_BLOCK_HASHES: usize = 256;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockchainSettings {
+    pub gas_price: U256,
+    pub origin: H160,
+    pub chain_id: u64,
+    pub block_hashes: Vec<H256>,
+    pub block_number: u64,
+    pub block_coinbase: H160,
+    pub block_timestamp: u64,
+    pub block_difficulty: U256,
+    pub block_gas_limit: u64,
+    pub block_base_fee_per_gas: U256,
+}
+
+/// The wire format, as read by the zkpoex program.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawBlockchainSettings {
+    gas_price: String,
+    origin: String,
+    chain_id: String,
+    block_hashes: String,
+    block_number: String,
+    block_coinbase: String,
+    block_timestamp: String,
+    block_difficulty: String,
+    block_gas_limit: String,
+    block_base_fee_per_gas: String,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum SettingsError {
+    #[error("malformed blockchain settings: {0}")]
+    Json(serde_json::Error),
+    #[error("failed to read blockchain settings: {0}")]
+    Io(std::io::Error),
+    #[error("invalid {field} {value:?}: {reason}")]
+    InvalidField {
+        field: &'static str,
+        value: String,
+        reason: &'static str,
+    },
+    #[error("invalid block_hashes[{index}] {value:?}: expected a 0x prefixed 32 byte hex hash")]
+    InvalidBlockHash { index: usize, value: String },
+    #[error("{count} block_hashes given, at most {MAX_BLOCK_HASHES} are allowed")]
+    TooManyBlockHashes { count: usize },
+}
+
+impl BlockchainSettings {
+    pub fn from_json(json: &str) -> Result<Self, SettingsError> {
+        let raw: RawBlockchainSettings = serde_json::from_str(json).map_err(SettingsError::Json)?;
+
+        Ok(Self {
+            gas_price: uint("gas_price", &raw.gas_price)?,
+            origin: address("origin", &raw.origin)?,
+            chain_id: uint64("chain_id", &raw.chain_id)?,
+            block_hashes: block_hashes(&raw.block_hashes)?,
+            block_number: uint64("block_number", &raw.block_number)?,
+            block_coinbase: address("block_coinbase", &raw.block_coinbase)?,
+            block_timestamp: uint64("block_timestamp", &raw.block_timestamp)?,
+            block_difficulty: uint("block_difficulty", &raw.block_difficulty)?,
+            block_gas_limit: uint64("block_gas_limit", &raw.block_gas_limit)?,
+            block_base_fee_per_gas: uint("block_base_fee_per_gas", &raw.block_base_fee_per_gas)?,
+        })
+    }
+
+    pub fn from_file(path: &std::path::Path) -> Result<Self, SettingsError> {
+        Self::from_json(&std::fs::read_to_string(path).map_err(SettingsError::Io)?)
+    }
+
+    /// The settings in the format the zkpoex program reads, always serialized the same way.
+    pub fn to_json(&self) -> String {
+        let raw = RawBlockchainSettings {
+            gas_price: format!("{:x}", self.gas_price),
+            origin: format!("0x{}", hex::encode(self.origin)),
+            chain_id: format!("{:x}", self.chain_id),
+            block_hashes: serde_json::to_string(
+                &self
+                    .block_hashes
+                    .iter()
+                    .map(|hash| format!("0x{}", hex::encode(hash)))
+                    .collect::<Vec<_>>(),
+            )
+            .expect("hashes serialize to json"),
+            block_number: format!("{:x}", self.block_number),
+            block_coinbase: format!("0x{}", hex::encode(self.block_coinbase)),
+            block_timestamp: format!("{:x}", self.block_timestamp),
+            block_difficulty: format!("{:x}", self.block_difficulty),
+            block_gas_limit: format!("{:x}", self.block_gas_limit),
+            block_base_fee_per_gas: format!("{:x}", self.block_base_fee_per_gas),
+        };
+        serde_json::to_string(&raw).expect("settings serialize to json")
+    }
+}
+
+impl Default for BlockchainSettings {
+    fn default() -> Self {
+        Self::from_json(DEFAULT_BLOCKCHAIN_SETTINGS).expect("default settings are valid")
+    }
+}
+
+impl FromStr for BlockchainSettings {
+    type Err = SettingsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_json(s)
+    }
+}
+
+fn uint(field: &'static str, value: &str) -> Result<U256, SettingsError> {
+    U256::from_str(value).map_err(|_| SettingsError::InvalidField {
+        field,
+        value: value.to_string(),
+        reason: "expected a hex encoded 256-bit integer",
+    })
+}
+
+fn uint64(field: &'static str, value: &str) -> Result<u64, SettingsError> {
+    let n = uint(field, value)?;
+    if n > U256::from(u64::MAX) {
+        return Err(SettingsError::InvalidField {
+            field,
+            value: value.to_string(),
+            reason: "does not fit in 64 bits",
+        });
+    }
+    Ok(n.as_u64())
+}
+
+fn address(field: &'static str, value: &str) -> Result<H160, SettingsError> {
+    let invalid = |reason| SettingsError::InvalidField {
+        field,
+        value: value.to_string(),
+        reason,
+    };
+    let address = match value.strip_prefix("0x") {
+        Some(digits) if digits.len() == 40 => H160::from_str(digits).ok(),
+        _ => None,
+    }
+    .ok_or_else(|| invalid("expected a 0x prefixed 20 byte hex address"))?;
+
+    // Mixed case claims an EIP-55 checksum, all lower or upper case doesn't.
+    let digits = &value[2..];
+    let mixed_case = digits.bytes().any(|b| b.is_ascii_lowercase())
+        && digits.bytes().any(|b| b.is_ascii_uppercase());
+    if mixed_case && digits != eip55_digits(&address) {
+        return Err(invalid("EIP-55 checksum does not match"));
+    }
+    Ok(address)
+}
+
+/// The hex digits of `address` with the EIP-55 checksum casing: a letter is uppercase where the
+/// matching nibble of the keccak256 of the lowercase digits is 8 or more.
+fn eip55_digits(address: &H160) -> String {
+    let lower = hex::encode(address);
+    let hash = Keccak256::digest(lower.as_bytes());
+    lower
+        .chars()
+        .enumerate()
+        .map(|(i, c)| {
+            let nibble = (hash[i / 2] >> (4 * (1 - i % 2))) & 0xf;
+            if nibble >= 8 {
+                c.to_ascii_uppercase()
+            } else {
+                c
+            }
+        })
+        .collect()
+}
+
+/// Parses the stringified JSON array of block hashes.
+fn block_hashes(value: &str) -> Result<Vec<H256>, SettingsError> {
+    let hashes: Vec<String> =
+        serde_json::from_str(value).map_err(|_| SettingsError::InvalidField {
+            field: "block_hashes",
+            value: value.to_string(),
+            reason: "expected a stringified JSON array of hashes",
+        })?;
+    if hashes.len() > MAX_BLOCK_HASHES {
+        return Err(SettingsError::TooManyBlockHashes {
+            count: hashes.len(),
+        });
+    }
+    hashes
+        .into_iter()
+        .enumerate()
+        .map(|(index, hash)| {
+            match hash.strip_prefix("0x") {
+                Some(digits) if digits.len() == 64 => H256::from_str(digits).ok(),
+                _ => None,
+            }
+            .ok_or(SettingsError::InvalidBlockHash { index, value: hash })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_settings_parse() {
+        let settings = BlockchainSettings::default();
+        assert_eq!(settings.chain_id, 1);
+        assert_eq!(settings.origin, H160::zero());
+        assert!(settings.block_hashes.is_empty());
+    }
+
+    #[test]
+    fn settings_round_trip_through_json() {
+        let settings = BlockchainSettings::default();
+        assert_eq!(
+            BlockchainSettings::from_json(&settings.to_json()).unwrap(),
+            settings
+        );
+        assert_eq!(settings.to_json(), BlockchainSettings::default().to_json());
+    }
+
+    #[test]
+    fn bad_address_is_rejected() {
+        let json = DEFAULT_BLOCKCHAIN_SETTINGS.replace(
+            r#""block_coinbase": "0x0000000000000000000000000000000000000000""#,
+            r#""block_coinbase": "0x00000000000000000000000000000000000000zz""#,
+        );
+        assert!(matches!(
+            BlockchainSettings::from_json(&json),
+            Err(SettingsError::InvalidField {
+                field: "block_coinbase",
+                ..
+            })
+        ));
+    }
+
+    fn with_origin(origin: &str) -> Result<BlockchainSettings, SettingsError> {
+        BlockchainSettings::from_json(&DEFAULT_BLOCKCHAIN_SETTINGS.replace(
+            r#""origin": "0x0000000000000000000000000000000000000000""#,
+            &format!(r#""origin": "{origin}""#),
+        ))
+    }
+
+    #[test]
+    fn checksummed_and_lowercase_addresses_normalize_alike() {
+        let checksummed = with_origin("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed").unwrap();
+        let lowercase = with_origin("0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed").unwrap();
+        assert_eq!(checksummed, lowercase);
+        assert_eq!(checksummed.to_json(), lowercase.to_json());
+        assert!(checksummed
+            .to_json()
+            .contains("0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed"));
+
+        // All uppercase carries no checksum.
+        assert_eq!(
+            with_origin("0x5AAEB6053F3E94C9B9A09F33669435E7EF1BEAED").unwrap(),
+            lowercase
+        );
+    }
+
+    #[test]
+    fn wrong_checksum_and_length_are_rejected() {
+        let err = with_origin("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAeD").unwrap_err();
+        assert!(err.to_string().contains("EIP-55"), "{err}");
+        assert!(matches!(
+            with_origin("0x5aaeb6053f3e94c9b9a09f33669435e7ef1bea"),
+            Err(SettingsError::InvalidField {
+                field: "origin",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn out_of_range_block_number_is_rejected() {
+        let json = DEFAULT_BLOCKCHAIN_SETTINGS.replace(
+            r#""block_number": "0""#,
+            r#""block_number": "10000000000000000""#,
+        );
+        assert!(matches!(
+            BlockchainSettings::from_json(&json),
+            Err(SettingsError::InvalidField {
+                field: "block_number",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn block_hashes_are_parsed() {
+        let hash = "0x88e96d4537bea4d9c05d12549907b32561d3bf31f45aae734cdc119f13406cb6";
+        let json = DEFAULT_BLOCKCHAIN_SETTINGS.replace(
+            r#""block_hashes": "[]""#,
+            &format!(r#""block_hashes": "[\"{hash}\"]""#),
+        );
+        let settings = BlockchainSettings::from_json(&json).unwrap();
+        assert_eq!(
+            settings.block_hashes,
+            vec![H256::from_str(&hash[2..]).unwrap()]
+        );
+        assert_eq!(
+            BlockchainSettings::from_json(&settings.to_json()).unwrap(),
+            settings
+        );
+    }
+
+    #[test]
+    fn malformed_block_hash_is_rejected_with_its_index() {
+        let hash = "0x88e96d4537bea4d9c05d12549907b32561d3bf31f45aae734cdc119f13406cb6";
+        let json = DEFAULT_BLOCKCHAIN_SETTINGS.replace(
+            r#""block_hashes": "[]""#,
+            &format!(r#""block_hashes": "[\"{hash}\", \"0x1234\"]""#),
+        );
+        assert!(matches!(
+            BlockchainSettings::from_json(&json),
+            Err(SettingsError::InvalidBlockHash { index: 1, value }) if value == "0x1234"
+        ));
+
+        let json = DEFAULT_BLOCKCHAIN_SETTINGS
+            .replace(r#""block_hashes": "[]""#, r#""block_hashes": "[1, 2]""#);
+        assert!(matches!(
+            BlockchainSettings::from_json(&json),
+            Err(SettingsError::InvalidField {
+                field: "block_hashes",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn too_many_block_hashes_are_rejected() {
+        let hashes = vec![format!("0x{}", "00".repeat(32)); MAX_BLOCK_HASHES + 1];
+        assert!(matches!(
+            block_hashes(&serde_json::to_string(&hashes).unwrap()),
+            Err(SettingsError::TooManyBlockHashes { count }) if count == MAX_BLOCK_HASHES + 1
+        ));
+        assert_eq!(
+            block_hashes(&serde_json::to_string(&hashes[1..]).unwrap())
+                .unwrap()
+                .len(),
+            MAX_BLOCK_HASHES
+        );
+    }
+}
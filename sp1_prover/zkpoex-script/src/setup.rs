@@ -0,0 +1,90 @@
+//! Caching the SP1 proving and verifying keys, which only change when the ELF does.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use sha3::{Digest, Keccak256};
+use sp1_sdk::{ProverClient, SP1ProvingKey, SP1VerifyingKey};
+
+use crate::output::DEFAULT_OUT_DIR;
+
+/// Where the keys are cached unless told otherwise, relative to where the prover runs.
+pub fn default_setup_cache_dir() -> PathBuf {
+    Path::new(DEFAULT_OUT_DIR).join("setup")
+}
+
+const CACHE_EXTENSION: &str = "keys";
+
+/// Hex-encoded keccak256 of the ELF, which the cache is keyed on.
+pub fn elf_digest(elf: &[u8]) -> String {
+    hex::encode(Keccak256::digest(elf))
+}
+
+pub fn cache_path(elf: &[u8], cache_dir: &Path) -> PathBuf {
+    cache_dir
+        .join(elf_digest(elf))
+        .with_extension(CACHE_EXTENSION)
+}
+
+/// Like `client.setup(elf)`, but loads the keys from `cache_dir` when they were already generated
+/// for this exact ELF. Keys cached for any other ELF are removed when new ones are written.
+///
+/// The cache is best effort: an unreadable entry is regenerated and a failed write only logs.
+pub fn setup_cached(
+    client: &ProverClient,
+    elf: &[u8],
+    cache_dir: &Path,
+) -> (SP1ProvingKey, SP1VerifyingKey) {
+    let path = cache_path(elf, cache_dir);
+
+    if let Ok(bytes) = fs::read(&path) {
+        match bincode::deserialize(&bytes) {
+            Ok(keys) => {
+                tracing::info!("loaded proving key from {}", path.display());
+                return keys;
+            }
+            Err(e) => tracing::warn!("ignoring corrupt key cache {}: {e}", path.display()),
+        }
+    }
+
+    let keys = client.setup(elf);
+
+    if let Err(e) = store(&keys, &path) {
+        tracing::warn!("failed to cache proving key at {}: {e}", path.display());
+    }
+
+    keys
+}
+
+fn store(keys: &(SP1ProvingKey, SP1VerifyingKey), path: &Path) -> anyhow::Result<()> {
+    let cache_dir = path.parent().expect("cache path has a parent");
+    fs::create_dir_all(cache_dir)?;
+
+    for entry in fs::read_dir(cache_dir)? {
+        let stale = entry?.path();
+        if stale != path && stale.extension().is_some_and(|ext| ext == CACHE_EXTENSION) {
+            fs::remove_file(stale)?;
+        }
+    }
+
+    fs::write(path, bincode::serialize(keys)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cache_is_keyed_on_elf() {
+        let dir = Path::new("cache");
+        assert_eq!(cache_path(b"elf", dir), cache_path(b"elf", dir));
+        assert_ne!(cache_path(b"elf", dir), cache_path(b"other elf", dir));
+        assert_eq!(
+            cache_path(b"elf", dir).extension().unwrap(),
+            CACHE_EXTENSION
+        );
+    }
+}
@@ -0,0 +1,274 @@
+//! Splitting the encryption key into Shamir shares, so it can be timelocked to several drand
+//! rounds and only disclosed once enough of them are published.
+//!
+//! The shares are taken over GF(2^8) byte by byte. A share is serialized as
+//! `threshold: u8 | index: u8 | value`, so whoever decrypts it knows how many more are needed.
+
+use std::{fmt, str::FromStr};
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+/// How many shares the key is split into, and how many of them recover it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeySplit {
+    pub threshold: u8,
+    pub shares: u8,
+}
+
+/// Timelocks the shares of a split key instead of the key itself, share `i` to `rounds[i]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SplitTimelock {
+    pub split: KeySplit,
+    pub rounds: Vec<u64>,
+}
+
+/// A tlock encrypted [`KeyShare`] and the round it opens at.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TlockShare {
+    pub round: u64,
+    pub tlock_cipher: Vec<u8>,
+}
+
+/// One share of a split key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyShare {
+    pub threshold: u8,
+    /// The point the share was evaluated at, never zero.
+    pub index: u8,
+    pub value: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum SplitError {
+    #[error("invalid key split {0:?}, expected k-of-n with 1 <= k <= n <= 255")]
+    Invalid(String),
+    #[error("key share is {0} bytes, shorter than its header")]
+    Truncated(usize),
+    #[error("{have} key shares recovered, {need} are needed")]
+    NotEnoughShares { have: usize, need: usize },
+    #[error("key shares disagree on the threshold or length, or repeat an index")]
+    Inconsistent,
+    #[error("a {split} split needs {} share rounds, got {actual}", .split.shares)]
+    ShareRounds { split: KeySplit, actual: usize },
+}
+
+impl FromStr for KeySplit {
+    type Err = SplitError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || SplitError::Invalid(s.to_string());
+        let (k, n) = s.trim().split_once("-of-").ok_or_else(invalid)?;
+        let split = KeySplit {
+            threshold: k.parse().map_err(|_| invalid())?,
+            shares: n.parse().map_err(|_| invalid())?,
+        };
+        if split.threshold == 0 || split.threshold > split.shares {
+            return Err(invalid());
+        }
+        Ok(split)
+    }
+}
+
+impl fmt::Display for KeySplit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-of-{}", self.threshold, self.shares)
+    }
+}
+
+impl KeySplit {
+    /// Splits `secret` into [`KeySplit::shares`] shares, any [`KeySplit::threshold`] of which
+    /// recover it.
+    pub fn split(self, secret: &[u8], rng: &mut impl Rng) -> Vec<KeyShare> {
+        let mut shares: Vec<_> = (1..=self.shares)
+            .map(|index| KeyShare {
+                threshold: self.threshold,
+                index,
+                value: Vec::with_capacity(secret.len()),
+            })
+            .collect();
+
+        for &byte in secret {
+            // The secret byte is the constant term, the others are random.
+            let mut coefficients = vec![byte];
+            coefficients.extend((1..self.threshold).map(|_| rng.gen::<u8>()));
+            for share in &mut shares {
+                share.value.push(evaluate(&coefficients, share.index));
+            }
+        }
+        shares
+    }
+}
+
+impl KeyShare {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![self.threshold, self.index];
+        bytes.extend_from_slice(&self.value);
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SplitError> {
+        match bytes {
+            [threshold, index, value @ ..] if *threshold != 0 && *index != 0 => Ok(Self {
+                threshold: *threshold,
+                index: *index,
+                value: value.to_vec(),
+            }),
+            // No split has a threshold of zero, and zero is where the secret itself lies.
+            [0, _, ..] | [_, 0, ..] => Err(SplitError::Inconsistent),
+            _ => Err(SplitError::Truncated(bytes.len())),
+        }
+    }
+}
+
+/// Recovers the secret from at least a threshold of distinct `shares`.
+pub fn combine(shares: &[KeyShare]) -> Result<Vec<u8>, SplitError> {
+    let first = shares
+        .first()
+        .ok_or(SplitError::NotEnoughShares { have: 0, need: 1 })?;
+    let need = first.threshold as usize;
+    if need == 0 {
+        return Err(SplitError::Inconsistent);
+    }
+    if shares.len() < need {
+        return Err(SplitError::NotEnoughShares {
+            have: shares.len(),
+            need,
+        });
+    }
+
+    let shares = &shares[..need];
+    for (i, share) in shares.iter().enumerate() {
+        if share.threshold != first.threshold
+            || share.value.len() != first.value.len()
+            || shares[..i].iter().any(|other| other.index == share.index)
+        {
+            return Err(SplitError::Inconsistent);
+        }
+    }
+
+    // Lagrange interpolation at zero; subtraction is addition (xor) in GF(2^8).
+    let weights: Vec<u8> = shares
+        .iter()
+        .map(|share| {
+            shares
+                .iter()
+                .filter(|other| other.index != share.index)
+                .fold(1, |weight, other| {
+                    mul(weight, div(other.index, other.index ^ share.index))
+                })
+        })
+        .collect();
+
+    Ok((0..first.value.len())
+        .map(|i| {
+            shares
+                .iter()
+                .zip(&weights)
+                .fold(0, |secret, (share, &weight)| {
+                    secret ^ mul(share.value[i], weight)
+                })
+        })
+        .collect())
+}
+
+/// Evaluates the polynomial with `coefficients`, lowest degree first, at `x`.
+fn evaluate(coefficients: &[u8], x: u8) -> u8 {
+    coefficients
+        .iter()
+        .rev()
+        .fold(0, |acc, &coefficient| mul(acc, x) ^ coefficient)
+}
+
+/// Multiplication in GF(2^8) modulo the AES polynomial.
+fn mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0;
+    while b != 0 {
+        if b & 1 != 0 {
+            product ^= a;
+        }
+        let carry = a & 0x80 != 0;
+        a <<= 1;
+        if carry {
+            a ^= 0x1b;
+        }
+        b >>= 1;
+    }
+    product
+}
+
+/// Division in GF(2^8), `b` must not be zero.
+fn div(a: u8, b: u8) -> u8 {
+    // b^254 is the inverse of b, as the multiplicative group has order 255.
+    let mut inverse = 1;
+    for _ in 0..254 {
+        inverse = mul(inverse, b);
+    }
+    mul(a, inverse)
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+
+    #[test]
+    fn two_of_three_reconstructs_the_key() {
+        let key: [u8; 32] = std::array::from_fn(|i| i as u8 * 7);
+        let split: KeySplit = "2-of-3".parse().unwrap();
+        let shares = split.split(&key, &mut StdRng::seed_from_u64(1));
+        assert_eq!(shares.len(), 3);
+
+        for pair in [[0, 1], [0, 2], [2, 1]] {
+            let pair = pair.map(|i| shares[i].clone());
+            assert_eq!(combine(&pair).unwrap(), key);
+        }
+
+        assert_eq!(
+            combine(&shares[..1]),
+            Err(SplitError::NotEnoughShares { have: 1, need: 2 })
+        );
+    }
+
+    #[test]
+    fn share_round_trips_through_bytes() {
+        let share = KeyShare {
+            threshold: 2,
+            index: 3,
+            value: vec![1, 2, 3],
+        };
+        assert_eq!(KeyShare::from_bytes(&share.to_bytes()), Ok(share));
+        assert_eq!(KeyShare::from_bytes(&[2]), Err(SplitError::Truncated(1)));
+    }
+
+    #[test]
+    fn zero_threshold_share_is_rejected() {
+        assert_eq!(
+            KeyShare::from_bytes(&[0, 1, 7, 7]),
+            Err(SplitError::Inconsistent)
+        );
+        // Built directly rather than parsed, it must not combine to an all-zero key either.
+        let share = KeyShare {
+            threshold: 0,
+            index: 1,
+            value: vec![7; 32],
+        };
+        assert_eq!(combine(&[share]), Err(SplitError::Inconsistent));
+    }
+
+    #[test]
+    fn invalid_splits_are_rejected() {
+        assert_eq!(
+            "3-of-5".parse(),
+            Ok(KeySplit {
+                threshold: 3,
+                shares: 5
+            })
+        );
+        for s in ["0-of-3", "4-of-3", "2of3", "2-of-256"] {
+            assert!(s.parse::<KeySplit>().is_err(), "{s}");
+        }
+    }
+}
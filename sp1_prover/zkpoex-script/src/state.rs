@@ -0,0 +1,251 @@
+//! Reading the `before` and `after` EVM states the program commits to, and what changed between
+//! them, so an auditor can see what an exploit did without decoding the raw strings.
+//!
+//! The program currently commits the target contract's balance as a decimal string. A state can
+//! also be a JSON object of accounts, each with an optional `balance` and `storage`:
+//!
+//! ```json
+//! { "0x1000000000000000000000000000000000000000": { "balance": "0x0", "storage": { "0x0": "0x1" } } }
+//! ```
+//!
+//! Balances and storage words are decimal or `0x` prefixed hex.
+
+use std::{collections::BTreeMap, fmt, str::FromStr};
+
+use primitive_types::{H160, U256};
+use serde_json::Value;
+
+/// The account whose balance the program reports, `TARGET_ADDRESS` of the evm-runner.
+pub const TARGET_ADDRESS: &str = "0x1000000000000000000000000000000000000000";
+
+/// The balance and storage of every account a state describes.
+pub type EvmState = BTreeMap<H160, AccountState>;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AccountState {
+    pub balance: U256,
+    pub storage: BTreeMap<U256, U256>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ParseError {
+    #[error("state is neither a balance nor a JSON object of accounts: {0}")]
+    Malformed(String),
+    #[error("invalid account address {0:?}")]
+    Address(String),
+    #[error("invalid {field} {value:?}, expected decimal or 0x prefixed hex")]
+    Word { field: &'static str, value: String },
+}
+
+/// Parses a state as the program commits it.
+pub fn parse_state(s: &str) -> Result<EvmState, ParseError> {
+    let s = s.trim();
+    if !s.starts_with('{') {
+        let balance = parse_word("balance", s)?;
+        let target = H160::from_str(TARGET_ADDRESS).expect("valid address");
+        return Ok(EvmState::from([(
+            target,
+            AccountState {
+                balance,
+                ..Default::default()
+            },
+        )]));
+    }
+
+    let Value::Object(accounts) =
+        serde_json::from_str::<Value>(s).map_err(|e| ParseError::Malformed(e.to_string()))?
+    else {
+        unreachable!("JSON starting with {{ is an object")
+    };
+    accounts
+        .into_iter()
+        .map(|(address, account)| {
+            let address = H160::from_str(&address).map_err(|_| ParseError::Address(address))?;
+            Ok((address, parse_account(&account)?))
+        })
+        .collect()
+}
+
+fn parse_account(account: &Value) -> Result<AccountState, ParseError> {
+    let malformed = || ParseError::Malformed(account.to_string());
+    let word = |field, value: &Value| parse_word(field, value.as_str().ok_or_else(malformed)?);
+
+    let balance = match account.get("balance") {
+        Some(balance) => word("balance", balance)?,
+        None => U256::zero(),
+    };
+    let storage = match account.get("storage") {
+        Some(Value::Object(slots)) => slots
+            .iter()
+            .map(|(slot, value)| Ok((parse_word("storage slot", slot)?, word("storage", value)?)))
+            .collect::<Result<_, ParseError>>()?,
+        Some(_) => return Err(malformed()),
+        None => BTreeMap::new(),
+    };
+    Ok(AccountState { balance, storage })
+}
+
+fn parse_word(field: &'static str, value: &str) -> Result<U256, ParseError> {
+    let invalid = || ParseError::Word {
+        field,
+        value: value.to_string(),
+    };
+    match value.strip_prefix("0x") {
+        Some(hex) => U256::from_str_radix(hex, 16).map_err(|_| invalid()),
+        None => U256::from_dec_str(value).map_err(|_| invalid()),
+    }
+}
+
+/// What changed for every account whose balance or storage differs between two states.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StateDiff {
+    pub accounts: BTreeMap<H160, AccountDiff>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AccountDiff {
+    /// The balance before and after, if it changed.
+    pub balance: Option<(U256, U256)>,
+    /// The value before and after of every storage slot that changed.
+    pub storage: BTreeMap<U256, (U256, U256)>,
+}
+
+/// The changes from `before` to `after`. An account or slot missing from one side is zero there.
+pub fn diff(before: &EvmState, after: &EvmState) -> StateDiff {
+    let empty = AccountState::default();
+    let accounts = before
+        .keys()
+        .chain(after.keys())
+        .filter_map(|address| {
+            let old = before.get(address).unwrap_or(&empty);
+            let new = after.get(address).unwrap_or(&empty);
+
+            let slots = old.storage.keys().chain(new.storage.keys());
+            let storage: BTreeMap<_, _> = slots
+                .filter_map(|slot| {
+                    let old = old.storage.get(slot).copied().unwrap_or_default();
+                    let new = new.storage.get(slot).copied().unwrap_or_default();
+                    (old != new).then_some((*slot, (old, new)))
+                })
+                .collect();
+            let account = AccountDiff {
+                balance: (old.balance != new.balance).then_some((old.balance, new.balance)),
+                storage,
+            };
+
+            (account != AccountDiff::default()).then_some((*address, account))
+        })
+        .collect();
+    StateDiff { accounts }
+}
+
+/// [`diff`] of the states `before` and `after` as the program commits them.
+pub fn diff_states(before: &str, after: &str) -> Result<StateDiff, ParseError> {
+    Ok(diff(&parse_state(before)?, &parse_state(after)?))
+}
+
+impl fmt::Display for StateDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.accounts.is_empty() {
+            return write!(f, "no state changes");
+        }
+        for (i, (address, account)) in self.accounts.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{address:?}")?;
+            if let Some((old, new)) = account.balance {
+                let change = if new >= old {
+                    format!("+{}", new - old)
+                } else {
+                    format!("-{}", old - new)
+                };
+                write!(f, "\n  balance: {old} -> {new} ({change} wei)")?;
+            }
+            for (slot, (old, new)) in &account.storage {
+                write!(f, "\n  storage[{slot:#x}]: {old:#x} -> {new:#x}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drained_target_shows_up_in_the_diff() {
+        let before = parse_state("1000000000000000000").unwrap();
+        let after = parse_state("0").unwrap();
+        let target = H160::from_str(TARGET_ADDRESS).unwrap();
+
+        let diff = diff(&before, &after);
+        assert_eq!(
+            diff.accounts[&target].balance,
+            Some((U256::exp10(18), U256::zero()))
+        );
+        assert_eq!(
+            diff.to_string(),
+            "0x1000000000000000000000000000000000000000\n  \
+             balance: 1000000000000000000 -> 0 (-1000000000000000000 wei)"
+        );
+    }
+
+    #[test]
+    fn storage_changes_are_listed_per_slot() {
+        let before = parse_state(
+            r#"{
+                "0x1000000000000000000000000000000000000000": { "balance": "5", "storage": { "0x0": "0x1", "0x1": "0x2" } },
+                "0x2000000000000000000000000000000000000000": { "balance": "0x10" }
+            }"#,
+        )
+        .unwrap();
+        let after = parse_state(
+            r#"{
+                "0x1000000000000000000000000000000000000000": { "balance": "5", "storage": { "0x0": "0x1", "0x1": "0x3" } },
+                "0x2000000000000000000000000000000000000000": { "balance": "0x10" },
+                "0x3000000000000000000000000000000000000000": { "storage": { "0x7": "0x1" } }
+            }"#,
+        )
+        .unwrap();
+
+        let diff = diff(&before, &after);
+        assert_eq!(diff.accounts.len(), 2);
+        let target = &diff.accounts[&H160::from_str(TARGET_ADDRESS).unwrap()];
+        assert_eq!(target.balance, None);
+        assert_eq!(
+            target.storage,
+            BTreeMap::from([(U256::one(), (U256::from(2), U256::from(3)))])
+        );
+        let created =
+            &diff.accounts[&H160::from_str("0x3000000000000000000000000000000000000000").unwrap()];
+        assert_eq!(
+            created.storage,
+            BTreeMap::from([(U256::from(7), (U256::zero(), U256::one()))])
+        );
+    }
+
+    #[test]
+    fn malformed_states_are_rejected() {
+        assert!(matches!(
+            parse_state("0xzz"),
+            Err(ParseError::Word {
+                field: "balance",
+                ..
+            })
+        ));
+        assert!(matches!(
+            parse_state(r#"{ "0x12": {} }"#),
+            Err(ParseError::Address(_))
+        ));
+        assert!(matches!(
+            parse_state(r#"{ "0x1000000000000000000000000000000000000000": { "balance": 5 } }"#),
+            Err(ParseError::Malformed(_))
+        ));
+        assert_eq!(
+            diff(&EvmState::new(), &EvmState::new()).to_string(),
+            "no state changes"
+        );
+    }
+}
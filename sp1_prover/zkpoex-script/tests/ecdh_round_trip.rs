@@ -0,0 +1,90 @@
+//! The contract between the zkpoex and ecdh scripts: the key zkpoex persists is the one the ecdh
+//! script reads, encrypts to the vendors and commits to, so a vendor recovers exactly that key.
+//! The zkpoex prover is mocked, so this runs in moments.
+
+#![cfg(feature = "prover")]
+
+use std::fs;
+
+use clap::ValueEnum;
+use ecdh_script::keys::{
+    decrypt_key_cipher, encrypt_key_to, read_key_file, Curve, Kdf, DEFAULT_KEY_FILE,
+};
+use sha3::{Digest, Keccak256};
+use zkpoex_script::{
+    backend::MockBackend,
+    cipher::PayloadCipher,
+    digest::PrivateInputsDigest,
+    drand::MockDrand,
+    inputs_hash, key_and_nonce,
+    output::{write_atomic, ENC_KEY_FILE},
+    report::ProgramReport,
+    settings::BlockchainSettings,
+    Disclosure, ProveOptions, PublicValues, ZkPoExProver,
+};
+
+const CALLDATA: &str = "63d9b770";
+const SEED: u64 = 7;
+
+#[test]
+fn key_persisted_by_zkpoex_is_recovered_through_ecdh() {
+    // The mock commits what the program would: the hash of the key the seed draws.
+    let (key, _) = key_and_nonce(PayloadCipher::default(), None, None, Some(SEED));
+    let settings = BlockchainSettings::default();
+    let public_values = PublicValues {
+        before: "{}".to_string(),
+        after: "{}".to_string(),
+        hash_private_inputs: PrivateInputsDigest([1; 32]),
+        chacha_cipher: vec![2, 3],
+        key_hash: hex::encode(Keccak256::digest(key)),
+        inputs_hash: inputs_hash(CALLDATA, &settings.to_json()),
+    };
+    let backend = MockBackend {
+        public_values: public_values.encode(),
+        report: ProgramReport::default(),
+        vkey: "0x00".to_string(),
+    };
+    let options = ProveOptions {
+        disclosure: Disclosure::Immediate,
+        setup_cache: None,
+        seed: Some(SEED),
+        ..Default::default()
+    };
+    let prover = ZkPoExProver::with_backend(backend, options, None, &MockDrand::default()).unwrap();
+    let (fixture, _) = prover.prove(CALLDATA, &settings).unwrap();
+
+    // Persist the key as `--persist-key` does, under the name the ecdh script looks for.
+    assert!(DEFAULT_KEY_FILE.ends_with(ENC_KEY_FILE));
+    let dir = std::env::temp_dir().join(format!("zkpoex-ecdh-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let key_file = dir.join(ENC_KEY_FILE);
+    write_atomic(&key_file, fixture.key).unwrap();
+
+    let read = read_key_file(&key_file).unwrap();
+    assert_eq!(read, fixture.key);
+    let key_hash: [u8; 32] = Keccak256::digest(read).into();
+    assert_eq!(hex::encode(key_hash), public_values.key_hash);
+
+    for &curve in Curve::value_variants() {
+        let (local_sk, local_pk) = curve.random_key_pair();
+        let (vendor_sk, vendor_pk) = curve.random_key_pair();
+        let nonce = [9; 12];
+        let key_ciphers = encrypt_key_to(
+            curve,
+            Kdf::Keccak256,
+            &local_sk,
+            &[vendor_pk],
+            &read,
+            &nonce,
+        )
+        .unwrap();
+
+        // The vendor's side of the exchange.
+        let shared = curve.shared_secret(&vendor_sk, &local_pk).unwrap();
+        let recovered =
+            decrypt_key_cipher(Kdf::Keccak256, &shared, &nonce, &key_ciphers[0], key_hash).unwrap();
+        assert_eq!(recovered, fixture.key);
+    }
+
+    fs::remove_dir_all(dir).unwrap();
+}